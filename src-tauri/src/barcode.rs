@@ -0,0 +1,184 @@
+use serde::Serialize;
+
+// Product barcode symbologies. Numeric codes of EAN/UPC length are read as
+// EAN/UPC and must carry a valid GS1 check digit; anything else printable is
+// accepted as Code128 (code set B, no spaces).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Symbology {
+    Ean8,
+    UpcA,
+    Ean13,
+    Code128,
+}
+
+pub const MAX_CODE128_LEN: usize = 48;
+
+// GS1 check digit for the digits before it: weights 3, 1, 3... from the right
+pub fn check_digit(digits: &str) -> Option<u8> {
+    let mut sum = 0;
+    for (i, b) in digits.bytes().rev().enumerate() {
+        if !b.is_ascii_digit() {
+            return None;
+        }
+        let weight = if i % 2 == 0 { 3 } else { 1 };
+        sum += (b - b'0') as u32 * weight;
+    }
+    Some(((10 - sum % 10) % 10) as u8)
+}
+
+// Whether the last digit of `code` is the check digit of the rest
+pub fn has_valid_check_digit(code: &str) -> bool {
+    match code.len().checked_sub(1).map(|last| code.split_at(last)) {
+        Some((body, last)) if !body.is_empty() => {
+            check_digit(body).is_some_and(|digit| last.as_bytes()[0] == b'0' + digit)
+        }
+        _ => false,
+    }
+}
+
+// Symbology of a well-formed code, or None if the code is malformed
+pub fn detect(code: &str) -> Option<Symbology> {
+    let numeric = !code.is_empty() && code.bytes().all(|b| b.is_ascii_digit());
+    let gs1 = match code.len() {
+        8 => Some(Symbology::Ean8),
+        12 => Some(Symbology::UpcA),
+        13 => Some(Symbology::Ean13),
+        _ => None,
+    };
+    match gs1 {
+        Some(symbology) if numeric => has_valid_check_digit(code).then_some(symbology),
+        _ if (1..=MAX_CODE128_LEN).contains(&code.len()) && code.bytes().all(|b| b.is_ascii_graphic()) => {
+            Some(Symbology::Code128)
+        }
+        _ => None,
+    }
+}
+
+// Complete a 12-digit body into an EAN-13 code
+pub fn ean13(body: &str) -> Option<String> {
+    if body.len() != 12 {
+        return None;
+    }
+    check_digit(body).map(|digit| format!("{}{}", body, digit))
+}
+
+// EAN/UPC digit patterns, one module per character: set A (odd parity) for
+// the left half; set B (even parity) is set C reversed, and set C is set A
+// inverted.
+const EAN_SET_A: [&str; 10] = [
+    "0001101", "0011001", "0010011", "0111101", "0100011", "0110001", "0101111", "0111011", "0110111", "0001011",
+];
+// Parity of the six left digits of an EAN-13 for each leading digit (true = set B)
+const EAN13_PARITY: [&str; 10] = [
+    "AAAAAA", "AABABB", "AABBAB", "AABBBA", "ABAABB", "ABBAAB", "ABBBAA", "ABABAB", "ABABBA", "ABBABA",
+];
+
+// Code 128 symbols as bar/space widths, indexed by symbol value
+const CODE128_WIDTHS: [&str; 107] = [
+    "212222", "222122", "222221", "121223", "121322", "131222", "122213", "122312", "132212", "221213",
+    "221312", "231212", "112232", "122132", "122231", "113222", "123122", "123221", "223211", "221132",
+    "221231", "213212", "223112", "312131", "311222", "321122", "321221", "312212", "322112", "322211",
+    "212123", "212321", "232121", "111323", "131123", "131321", "112313", "132113", "132311", "211313",
+    "231113", "231311", "112133", "112331", "132131", "113123", "113321", "133121", "313121", "211331",
+    "231131", "213113", "213311", "213131", "311123", "311321", "331121", "312113", "312311", "332111",
+    "314111", "221411", "431111", "111224", "111422", "121124", "121421", "141122", "141221", "112214",
+    "112412", "122114", "122411", "142112", "142211", "241211", "221114", "413111", "241112", "134111",
+    "111242", "121142", "121241", "114212", "124112", "124211", "411212", "421112", "421211", "212141",
+    "214121", "412121", "111143", "111341", "131141", "114113", "114311", "411113", "411311", "113141",
+    "114131", "311141", "411131", "211412", "211214", "211232", "2331112",
+];
+const CODE128_START_B: usize = 104;
+const CODE128_STOP: usize = 106;
+
+fn ean_digit(digit: u8, set: char) -> impl Iterator<Item = bool> {
+    let pattern = EAN_SET_A[(digit - b'0') as usize].as_bytes();
+    (0..7).map(move |i| match set {
+        'A' => pattern[i] == b'1',
+        'B' => pattern[6 - i] == b'0',
+        _ => pattern[i] == b'0',
+    })
+}
+
+// Guard bars around two halves; `sets` gives the set of each left digit
+fn ean_bars(left: &[u8], sets: &str, right: &[u8]) -> Vec<bool> {
+    let mut bars = vec![true, false, true];
+    for (&digit, set) in left.iter().zip(sets.chars()) {
+        bars.extend(ean_digit(digit, set));
+    }
+    bars.extend([false, true, false, true, false]);
+    for &digit in right {
+        bars.extend(ean_digit(digit, 'C'));
+    }
+    bars.extend([true, false, true]);
+    bars
+}
+
+fn code128_bars(code: &str) -> Vec<bool> {
+    let values: Vec<usize> = code.bytes().map(|b| (b - b' ') as usize).collect();
+    let checksum = values
+        .iter()
+        .enumerate()
+        .fold(CODE128_START_B, |sum, (i, value)| sum + value * (i + 1))
+        % 103;
+
+    let mut bars = Vec::new();
+    let symbols = std::iter::once(CODE128_START_B).chain(values).chain([checksum, CODE128_STOP]);
+    for symbol in symbols {
+        for (i, width) in CODE128_WIDTHS[symbol].bytes().enumerate() {
+            bars.extend(std::iter::repeat_n(i % 2 == 0, (width - b'0') as usize));
+        }
+    }
+    bars
+}
+
+// The code as modules from left to right (true = bar), without quiet zones,
+// or None if it is not a well-formed code
+pub fn bars(code: &str) -> Option<Vec<bool>> {
+    let digits = code.as_bytes();
+    Some(match detect(code)? {
+        Symbology::Ean13 => ean_bars(&digits[1..7], EAN13_PARITY[(digits[0] - b'0') as usize], &digits[7..]),
+        // UPC-A is an EAN-13 with a leading zero
+        Symbology::UpcA => ean_bars(&digits[..6], "AAAAAA", &digits[6..]),
+        Symbology::Ean8 => ean_bars(&digits[..4], "AAAA", &digits[4..]),
+        Symbology::Code128 => code128_bars(code),
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Embedded {
+    // Value is the line price in cents
+    Price,
+    // Value is the weight in grams
+    Weight,
+}
+
+// An in-store EAN-13 code carrying a price or weight, laid out as
+// PP IIIII VVVVV C: a prefix, a five-digit item code, the value and the check
+// digit. The product is found by its template code: the same code with the
+// value zeroed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EmbeddedCode {
+    pub kind: Embedded,
+    pub template: String,
+    pub value: u32,
+}
+
+pub fn parse_embedded(code: &str, price_prefixes: &[String], weight_prefixes: &[String]) -> Option<EmbeddedCode> {
+    if detect(code) != Some(Symbology::Ean13) {
+        return None;
+    }
+    let prefix = &code[..2];
+    let kind = if price_prefixes.iter().any(|p| p == prefix) {
+        Embedded::Price
+    } else if weight_prefixes.iter().any(|p| p == prefix) {
+        Embedded::Weight
+    } else {
+        return None;
+    };
+    Some(EmbeddedCode {
+        kind,
+        template: ean13(&format!("{}00000", &code[..7]))?,
+        value: code[7..12].parse().ok()?,
+    })
+}
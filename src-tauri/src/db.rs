@@ -1,4 +1,4 @@
-use rusqlite::{Connection, Result};
+use rusqlite::{params, Connection, Result};
 use std::fs;
 use std::path::PathBuf;
 use tauri::path::BaseDirectory;
@@ -15,7 +15,13 @@ pub fn init_db(app: &tauri::AppHandle) -> Result<Connection> {
     }
 
     let conn = Connection::open(path)?;
+    migrate(&conn)?;
+    Ok(conn)
+}
 
+// Create the schema and bring an older database up to date; safe to run on
+// every start
+pub fn migrate(conn: &Connection) -> Result<()> {
     conn.execute_batch(
         "
         CREATE TABLE IF NOT EXISTS products (
@@ -44,16 +50,404 @@ pub fn init_db(app: &tauri::AppHandle) -> Result<Connection> {
             sale_id INTEGER NOT NULL,
             product_id INTEGER,
             product_name TEXT NOT NULL,
-            quantity INTEGER NOT NULL,
+            quantity REAL NOT NULL,
             price REAL NOT NULL,
             extra_amount REAL NOT NULL DEFAULT 0,
             FOREIGN KEY(sale_id) REFERENCES sales(id) ON DELETE CASCADE,
             FOREIGN KEY(product_id) REFERENCES products(id) ON DELETE SET NULL
         );
+
+        CREATE TABLE IF NOT EXISTS drawer_sessions (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            employee_id INTEGER NOT NULL,
+            terminal TEXT NOT NULL,
+            opening_float REAL NOT NULL,
+            opened_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            closed_at DATETIME,
+            counted_cash REAL,
+            expected_cash REAL,
+            over_short REAL,
+            FOREIGN KEY(employee_id) REFERENCES employees(id)
+        );
+
+        CREATE UNIQUE INDEX IF NOT EXISTS idx_drawer_sessions_open_terminal
+            ON drawer_sessions(terminal) WHERE closed_at IS NULL;
+        CREATE UNIQUE INDEX IF NOT EXISTS idx_drawer_sessions_open_employee
+            ON drawer_sessions(employee_id) WHERE closed_at IS NULL;
+
+        CREATE TABLE IF NOT EXISTS drawer_movements (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            session_id INTEGER NOT NULL,
+            kind TEXT NOT NULL CHECK(kind IN ('pay_in', 'pay_out')),
+            amount REAL NOT NULL,
+            reason TEXT NOT NULL,
+            timestamp DATETIME DEFAULT CURRENT_TIMESTAMP,
+            FOREIGN KEY(session_id) REFERENCES drawer_sessions(id) ON DELETE CASCADE
+        );
+
+        CREATE TABLE IF NOT EXISTS payments (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            sale_id INTEGER NOT NULL,
+            method TEXT NOT NULL,
+            amount REAL NOT NULL,
+            drawer_session_id INTEGER,
+            timestamp DATETIME DEFAULT CURRENT_TIMESTAMP,
+            FOREIGN KEY(sale_id) REFERENCES sales(id) ON DELETE CASCADE,
+            FOREIGN KEY(drawer_session_id) REFERENCES drawer_sessions(id)
+        );
+
+        CREATE TABLE IF NOT EXISTS settings (
+            key TEXT PRIMARY KEY,
+            value TEXT NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS z_reports (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            z_number INTEGER NOT NULL UNIQUE,
+            business_date TEXT NOT NULL,
+            period_start DATETIME NOT NULL,
+            period_end DATETIME NOT NULL,
+            last_sale_id INTEGER NOT NULL,
+            employee_id INTEGER,
+            data TEXT NOT NULL,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        );
+
+        CREATE TRIGGER IF NOT EXISTS z_reports_no_update BEFORE UPDATE ON z_reports
+        BEGIN
+            SELECT RAISE(ABORT, 'Z reports are immutable');
+        END;
+
+        CREATE TRIGGER IF NOT EXISTS z_reports_no_delete BEFORE DELETE ON z_reports
+        BEGIN
+            SELECT RAISE(ABORT, 'Z reports are immutable');
+        END;
+
+        CREATE TABLE IF NOT EXISTS receipt_templates (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            kind TEXT NOT NULL CHECK(kind IN ('receipt', 'kitchen')),
+            language TEXT NOT NULL,
+            rtl INTEGER NOT NULL DEFAULT 0,
+            header TEXT NOT NULL DEFAULT '',
+            body TEXT NOT NULL DEFAULT '',
+            footer TEXT NOT NULL DEFAULT '',
+            updated_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            UNIQUE(kind, language)
+        );
+
+        CREATE TABLE IF NOT EXISTS document_sequences (
+            terminal TEXT NOT NULL,
+            doc_type TEXT NOT NULL CHECK(doc_type IN ('sale', 'refund', 'z_report')),
+            prefix TEXT NOT NULL,
+            reset_period TEXT NOT NULL DEFAULT 'never' CHECK(reset_period IN ('never', 'yearly', 'daily')),
+            padding INTEGER NOT NULL DEFAULT 6,
+            period TEXT NOT NULL DEFAULT '',
+            last_number INTEGER NOT NULL DEFAULT 0,
+            PRIMARY KEY (terminal, doc_type)
+        );
+
+        CREATE TABLE IF NOT EXISTS customers (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL,
+            phone TEXT UNIQUE,
+            email TEXT,
+            notes TEXT NOT NULL DEFAULT '',
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        );
+
+        CREATE TABLE IF NOT EXISTS loyalty_ledger (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            customer_id INTEGER NOT NULL,
+            sale_id INTEGER,
+            points INTEGER NOT NULL,
+            reason TEXT NOT NULL CHECK(reason IN ('earn', 'redeem', 'refund', 'adjust')),
+            note TEXT NOT NULL DEFAULT '',
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            FOREIGN KEY(customer_id) REFERENCES customers(id) ON DELETE CASCADE,
+            FOREIGN KEY(sale_id) REFERENCES sales(id)
+        );
+
+        CREATE TABLE IF NOT EXISTS gift_cards (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            code TEXT NOT NULL UNIQUE,
+            kind TEXT NOT NULL CHECK(kind IN ('gift_card', 'store_credit')),
+            initial_amount REAL NOT NULL,
+            expires_at DATE,
+            customer_id INTEGER,
+            sale_id INTEGER,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            FOREIGN KEY(customer_id) REFERENCES customers(id) ON DELETE SET NULL,
+            FOREIGN KEY(sale_id) REFERENCES sales(id)
+        );
+
+        CREATE TABLE IF NOT EXISTS gift_card_ledger (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            card_id INTEGER NOT NULL,
+            sale_id INTEGER,
+            amount REAL NOT NULL,
+            kind TEXT NOT NULL CHECK(kind IN ('issue', 'redeem', 'void', 'adjust')),
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            FOREIGN KEY(card_id) REFERENCES gift_cards(id) ON DELETE CASCADE,
+            FOREIGN KEY(sale_id) REFERENCES sales(id)
+        );
+
+        CREATE TABLE IF NOT EXISTS time_entries (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            employee_id INTEGER NOT NULL,
+            clock_in DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            clock_out DATETIME,
+            note TEXT NOT NULL DEFAULT '',
+            FOREIGN KEY(employee_id) REFERENCES employees(id) ON DELETE CASCADE
+        );
+
+        CREATE UNIQUE INDEX IF NOT EXISTS idx_time_entries_open
+            ON time_entries(employee_id) WHERE clock_out IS NULL;
+
+        CREATE TABLE IF NOT EXISTS time_breaks (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            entry_id INTEGER NOT NULL,
+            started_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            ended_at DATETIME,
+            paid INTEGER NOT NULL DEFAULT 0,
+            FOREIGN KEY(entry_id) REFERENCES time_entries(id) ON DELETE CASCADE
+        );
+
+        CREATE TABLE IF NOT EXISTS shifts (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            employee_id INTEGER NOT NULL,
+            starts_at DATETIME NOT NULL,
+            ends_at DATETIME NOT NULL,
+            note TEXT NOT NULL DEFAULT '',
+            CHECK(ends_at > starts_at),
+            FOREIGN KEY(employee_id) REFERENCES employees(id) ON DELETE CASCADE
+        );
+
+        CREATE TABLE IF NOT EXISTS tips (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            sale_id INTEGER NOT NULL,
+            employee_id INTEGER NOT NULL,
+            amount REAL NOT NULL,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            FOREIGN KEY(sale_id) REFERENCES sales(id) ON DELETE CASCADE,
+            FOREIGN KEY(employee_id) REFERENCES employees(id)
+        );
+
+        CREATE TABLE IF NOT EXISTS service_charge_rules (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL,
+            min_guests INTEGER NOT NULL,
+            percent REAL NOT NULL,
+            active INTEGER NOT NULL DEFAULT 1
+        );
+
+        CREATE TABLE IF NOT EXISTS login_lockouts (
+            key TEXT PRIMARY KEY,
+            failures INTEGER NOT NULL DEFAULT 0,
+            locked_until DATETIME
+        );
+
+        -- Signed-in users; employee_id is NULL for the admin
+        CREATE TABLE IF NOT EXISTS sessions (
+            token TEXT PRIMARY KEY,
+            employee_id INTEGER REFERENCES employees(id) ON DELETE CASCADE,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            last_used_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        );
+
+        CREATE TABLE IF NOT EXISTS login_failures (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            kind TEXT NOT NULL,
+            account TEXT,
+            terminal TEXT NOT NULL,
+            locked INTEGER NOT NULL DEFAULT 0,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        );
+
+        CREATE TABLE IF NOT EXISTS roles (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT UNIQUE NOT NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS role_permissions (
+            role_id INTEGER NOT NULL,
+            permission TEXT NOT NULL,
+            PRIMARY KEY(role_id, permission),
+            FOREIGN KEY(role_id) REFERENCES roles(id) ON DELETE CASCADE
+        );
+
+        CREATE TABLE IF NOT EXISTS overrides (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            permission TEXT NOT NULL,
+            employee_id INTEGER NOT NULL,
+            manager_id INTEGER NOT NULL,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            used_at DATETIME,
+            FOREIGN KEY(employee_id) REFERENCES employees(id),
+            FOREIGN KEY(manager_id) REFERENCES employees(id)
+        );
+
+        CREATE TABLE IF NOT EXISTS variant_attributes (
+            product_id INTEGER NOT NULL,
+            name TEXT NOT NULL,
+            value TEXT NOT NULL,
+            PRIMARY KEY(product_id, name),
+            FOREIGN KEY(product_id) REFERENCES products(id) ON DELETE CASCADE
+        );
+
+        CREATE TABLE IF NOT EXISTS stock_movements (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            product_id INTEGER NOT NULL,
+            sale_id INTEGER,
+            quantity REAL NOT NULL,
+            kind TEXT NOT NULL CHECK(kind IN ('sale', 'refund', 'void', 'adjust')),
+            note TEXT NOT NULL DEFAULT '',
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            FOREIGN KEY(product_id) REFERENCES products(id) ON DELETE CASCADE,
+            FOREIGN KEY(sale_id) REFERENCES sales(id)
+        );
+
+        CREATE TABLE IF NOT EXISTS bundle_groups (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            bundle_id INTEGER NOT NULL,
+            name TEXT NOT NULL,
+            position INTEGER NOT NULL,
+            FOREIGN KEY(bundle_id) REFERENCES products(id) ON DELETE CASCADE
+        );
+
+        CREATE TABLE IF NOT EXISTS bundle_options (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            group_id INTEGER NOT NULL,
+            product_id INTEGER NOT NULL,
+            quantity REAL NOT NULL DEFAULT 1,
+            FOREIGN KEY(group_id) REFERENCES bundle_groups(id) ON DELETE CASCADE,
+            FOREIGN KEY(product_id) REFERENCES products(id) ON DELETE CASCADE
+        );
+
+        CREATE TABLE IF NOT EXISTS sale_item_components (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            sale_item_id INTEGER NOT NULL,
+            product_id INTEGER,
+            product_name TEXT NOT NULL,
+            quantity REAL NOT NULL,
+            unit TEXT NOT NULL DEFAULT 'each',
+            price REAL NOT NULL,
+            FOREIGN KEY(sale_item_id) REFERENCES sale_items(id) ON DELETE CASCADE,
+            FOREIGN KEY(product_id) REFERENCES products(id) ON DELETE SET NULL
+        );
+
+        CREATE TABLE IF NOT EXISTS product_prices (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            product_id INTEGER NOT NULL,
+            price REAL NOT NULL,
+            effective_at DATETIME NOT NULL,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            FOREIGN KEY(product_id) REFERENCES products(id) ON DELETE CASCADE
+        );
+
+        CREATE TABLE IF NOT EXISTS availability_rules (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            product_id INTEGER,
+            category TEXT,
+            days TEXT NOT NULL DEFAULT '1234567',
+            start_time TEXT,
+            end_time TEXT,
+            start_date TEXT,
+            end_date TEXT,
+            CHECK((product_id IS NULL) != (category IS NULL)),
+            FOREIGN KEY(product_id) REFERENCES products(id) ON DELETE CASCADE
+        );
+
+        CREATE TABLE IF NOT EXISTS product_barcodes (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            product_id INTEGER NOT NULL,
+            barcode TEXT NOT NULL UNIQUE,
+            pack_quantity INTEGER NOT NULL DEFAULT 1,
+            FOREIGN KEY(product_id) REFERENCES products(id) ON DELETE CASCADE
+        );
         ",
     )?;
 
-    Ok(conn)
+    add_column_if_missing(conn, "employees", "pin_hash", "TEXT")?;
+    add_column_if_missing(conn, "employees", "role_id", "INTEGER REFERENCES roles(id)")?;
+    add_column_if_missing(conn, "sales", "discount", "REAL NOT NULL DEFAULT 0")?;
+    add_column_if_missing(conn, "sales", "tax", "REAL NOT NULL DEFAULT 0")?;
+    add_column_if_missing(conn, "sales", "voided", "INTEGER NOT NULL DEFAULT 0")?;
+    add_column_if_missing(conn, "sales", "refund_of", "INTEGER REFERENCES sales(id)")?;
+    add_column_if_missing(conn, "sales", "receipt_number", "TEXT")?;
+    add_column_if_missing(conn, "sales", "terminal", "TEXT")?;
+    add_column_if_missing(conn, "sales", "completed_at", "DATETIME")?;
+    add_column_if_missing(conn, "sales", "employee_name", "TEXT")?;
+    add_column_if_missing(conn, "sales", "tax_rate", "REAL")?;
+    add_column_if_missing(conn, "sales", "customer_id", "INTEGER REFERENCES customers(id)")?;
+    add_column_if_missing(conn, "sales", "guests", "INTEGER")?;
+    add_column_if_missing(conn, "sales", "service_charge", "REAL NOT NULL DEFAULT 0")?;
+    // Part of `discount` paid with loyalty points
+    add_column_if_missing(conn, "sales", "points_discount", "REAL NOT NULL DEFAULT 0")?;
+    // Unknown for products priced before this column existed
+    add_column_if_missing(conn, "products", "price_updated_at", "DATETIME")?;
+    add_column_if_missing(conn, "products", "net_content", "REAL")?;
+    add_column_if_missing(conn, "products", "net_content_unit", "TEXT CHECK(net_content_unit IN ('g', 'kg', 'ml', 'l'))")?;
+    add_column_if_missing(conn, "products", "unit", "TEXT NOT NULL DEFAULT 'each' CHECK(unit IN ('each', 'kg', 'g', 'l'))")?;
+    add_column_if_missing(conn, "products", "parent_id", "INTEGER REFERENCES products(id) ON DELETE CASCADE")?;
+    add_column_if_missing(conn, "products", "track_stock", "INTEGER NOT NULL DEFAULT 0")?;
+    add_column_if_missing(conn, "products", "category", "TEXT")?;
+    // Local date the product was 86'd (sold out) on; it is back on sale the next day
+    add_column_if_missing(conn, "products", "sold_out_on", "TEXT")?;
+    // Set when the product is archived: hidden from the POS, kept for reports
+    add_column_if_missing(conn, "products", "archived_at", "DATETIME")?;
+    // Older databases declare `sale_items.quantity` INTEGER; its affinity
+    // keeps fractional quantities as REAL, so the column is left as is
+    add_column_if_missing(conn, "sale_items", "unit", "TEXT NOT NULL DEFAULT 'each'")?;
+    add_column_if_missing(conn, "sale_items", "gift_card_id", "INTEGER REFERENCES gift_cards(id)")?;
+    add_column_if_missing(conn, "z_reports", "document_number", "TEXT")?;
+    add_column_if_missing(conn, "tips", "kind", "TEXT NOT NULL DEFAULT 'tip' CHECK(kind IN ('tip', 'service_charge'))")?;
+    add_column_if_missing(conn, "tips", "payment_id", "INTEGER REFERENCES payments(id)")?;
+    add_column_if_missing(conn, "tips", "drawer_session_id", "INTEGER REFERENCES drawer_sessions(id)")?;
+    add_column_if_missing(conn, "login_lockouts", "last_failure_at", "DATETIME")?;
+
+    conn.execute_batch(
+        "
+        CREATE UNIQUE INDEX IF NOT EXISTS idx_sales_refund_of ON sales(refund_of) WHERE refund_of IS NOT NULL;
+        CREATE UNIQUE INDEX IF NOT EXISTS idx_sales_receipt_number ON sales(receipt_number) WHERE receipt_number IS NOT NULL;
+        CREATE INDEX IF NOT EXISTS idx_sales_customer ON sales(customer_id);
+        CREATE INDEX IF NOT EXISTS idx_loyalty_ledger_customer ON loyalty_ledger(customer_id);
+        CREATE INDEX IF NOT EXISTS idx_gift_card_ledger_card ON gift_card_ledger(card_id);
+        CREATE INDEX IF NOT EXISTS idx_tips_sale ON tips(sale_id);
+        CREATE INDEX IF NOT EXISTS idx_product_barcodes_product ON product_barcodes(product_id);
+        CREATE INDEX IF NOT EXISTS idx_products_price_updated_at ON products(price_updated_at);
+        CREATE INDEX IF NOT EXISTS idx_products_parent ON products(parent_id);
+        CREATE INDEX IF NOT EXISTS idx_stock_movements_product ON stock_movements(product_id);
+        CREATE INDEX IF NOT EXISTS idx_stock_movements_sale ON stock_movements(sale_id);
+        CREATE INDEX IF NOT EXISTS idx_bundle_groups_bundle ON bundle_groups(bundle_id);
+        CREATE INDEX IF NOT EXISTS idx_bundle_options_group ON bundle_options(group_id);
+        CREATE INDEX IF NOT EXISTS idx_bundle_options_product ON bundle_options(product_id);
+        CREATE INDEX IF NOT EXISTS idx_sale_item_components_item ON sale_item_components(sale_item_id);
+        CREATE INDEX IF NOT EXISTS idx_product_prices_product ON product_prices(product_id, effective_at);
+        CREATE INDEX IF NOT EXISTS idx_availability_rules_product ON availability_rules(product_id);
+
+        -- Products priced before the history existed start it with their
+        -- current price, from when it was set if known
+        INSERT INTO product_prices (product_id, price, effective_at)
+        SELECT id, price, IFNULL(price_updated_at, '1970-01-01 00:00:00') FROM products
+        WHERE NOT EXISTS (SELECT 1 FROM product_prices WHERE product_id = products.id);
+        ",
+    )?;
+
+    Ok(())
+}
+
+// SQLite has no `ADD COLUMN IF NOT EXISTS`, so older databases are upgraded here.
+fn add_column_if_missing(conn: &Connection, table: &str, column: &str, definition: &str) -> Result<()> {
+    let count: i32 = conn.query_row(
+        "SELECT COUNT(*) FROM pragma_table_info(?1) WHERE name = ?2",
+        params![table, column],
+        |row| row.get(0),
+    )?;
+
+    if count == 0 {
+        conn.execute(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition), [])?;
+    }
+    Ok(())
 }
 
 pub fn establish_connection(app: &tauri::AppHandle) -> Result<Connection, rusqlite::Error> {
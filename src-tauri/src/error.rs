@@ -0,0 +1,132 @@
+use rusqlite::ffi;
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+use std::fmt;
+
+// Error returned by models and commands. It reaches the frontend as
+// `{ kind, code, field, message }`: `kind` is one of "validation",
+// "not_found", "conflict", "unauthorized", "database" or "io", `code` says
+// what went wrong in a fixed vocabulary (e.g. "required", "duplicate",
+// "void_item") and `field` names the input or column involved, if any.
+// `message` is English text for logs; the UI translates from kind/code/field.
+#[derive(Debug)]
+pub enum AppError {
+    // Input rejected before it reached the database
+    Validation { field: String, code: String, message: String },
+    // `entity` is the kind of record looked up, e.g. "sale"
+    NotFound { entity: String },
+    // The change clashes with existing data (unique or foreign key constraint)
+    Conflict { field: Option<String>, code: String, message: String },
+    // `permission` is the catalog code the employee lacks, or "session" when
+    // the caller is not signed in
+    Unauthorized { permission: String },
+    Database(rusqlite::Error),
+    // Printer or file system failure
+    Io(std::io::Error),
+}
+
+pub type Result<T, E = AppError> = std::result::Result<T, E>;
+
+impl AppError {
+    pub fn validation(field: &str, code: &str, message: impl Into<String>) -> AppError {
+        AppError::Validation { field: field.to_string(), code: code.to_string(), message: message.into() }
+    }
+
+    pub fn not_found(entity: &str) -> AppError {
+        AppError::NotFound { entity: entity.to_string() }
+    }
+
+    pub fn unauthorized(permission: &str) -> AppError {
+        AppError::Unauthorized { permission: permission.to_string() }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            AppError::Validation { .. } => "validation",
+            AppError::NotFound { .. } => "not_found",
+            AppError::Conflict { .. } => "conflict",
+            AppError::Unauthorized { .. } => "unauthorized",
+            AppError::Database(_) => "database",
+            AppError::Io(_) => "io",
+        }
+    }
+
+    pub fn code(&self) -> &str {
+        match self {
+            AppError::Validation { code, .. } | AppError::Conflict { code, .. } => code,
+            AppError::NotFound { .. } => "not_found",
+            AppError::Unauthorized { permission } => permission,
+            AppError::Database(_) => "database",
+            AppError::Io(_) => "io",
+        }
+    }
+
+    pub fn field(&self) -> Option<&str> {
+        match self {
+            AppError::Validation { field, .. } => Some(field),
+            AppError::NotFound { entity } => Some(entity),
+            AppError::Conflict { field, .. } => field.as_deref(),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AppError::Validation { message, .. } | AppError::Conflict { message, .. } => f.write_str(message),
+            AppError::NotFound { entity } => write!(f, "{} not found", entity),
+            AppError::Unauthorized { permission } => write!(f, "Not authorized: {}", permission),
+            AppError::Database(e) => write!(f, "Database error: {}", e),
+            AppError::Io(e) => write!(f, "I/O error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl Serialize for AppError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("AppError", 4)?;
+        state.serialize_field("kind", self.kind())?;
+        state.serialize_field("code", self.code())?;
+        state.serialize_field("field", &self.field())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.end()
+    }
+}
+
+// "UNIQUE constraint failed: products.barcode" -> "barcode"
+fn constraint_column(message: &str) -> Option<String> {
+    let columns = message.split_once(": ")?.1;
+    let first = columns.split(", ").next()?;
+    Some(first.rsplit('.').next().unwrap_or(first).to_string())
+}
+
+impl From<rusqlite::Error> for AppError {
+    fn from(e: rusqlite::Error) -> AppError {
+        if let rusqlite::Error::QueryReturnedNoRows = e {
+            return AppError::not_found("record");
+        }
+        if let rusqlite::Error::SqliteFailure(failure, Some(message)) = &e {
+            let code = match failure.extended_code {
+                ffi::SQLITE_CONSTRAINT_UNIQUE | ffi::SQLITE_CONSTRAINT_PRIMARYKEY => Some("duplicate"),
+                ffi::SQLITE_CONSTRAINT_FOREIGNKEY => Some("in_use"),
+                _ => None,
+            };
+            if let Some(code) = code {
+                return AppError::Conflict {
+                    field: constraint_column(message),
+                    code: code.to_string(),
+                    message: message.clone(),
+                };
+            }
+        }
+        AppError::Database(e)
+    }
+}
+
+impl From<std::io::Error> for AppError {
+    fn from(e: std::io::Error) -> AppError {
+        AppError::Io(e)
+    }
+}
@@ -0,0 +1,151 @@
+pub mod sheet;
+pub mod zpl;
+
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::error::{AppError, Result};
+use crate::models::products::{product_from_row, Product, PRODUCT_COLUMNS};
+use crate::models::settings::get_setting_f64;
+use crate::receipt::money;
+
+// What goes on one shelf label
+#[derive(Debug, Clone)]
+pub struct Label {
+    pub name: String,
+    pub price: String,
+    // e.g. "12.50 / kg", for packed products with a net content
+    pub unit_price: Option<String>,
+    pub barcode: String,
+}
+
+impl Label {
+    pub fn new(product: &Product, show_unit_price: bool) -> Label {
+        // Products sold by measure are priced per unit already; packed ones
+        // need a net content
+        let (price, content) = match product.unit.as_str() {
+            "each" => (money(product.price), product.net_content.zip(product.net_content_unit.as_deref())),
+            unit => (format!("{} / {}", money(product.price), unit), Some((1.0, unit))),
+        };
+        let unit_price = match content {
+            Some((content, unit)) if show_unit_price => unit_price(product.price, content, unit)
+                .map(|(price, per)| format!("{} / {}", money(price), per))
+                .filter(|text| *text != price),
+            _ => None,
+        };
+        Label {
+            name: product.name.clone(),
+            price,
+            unit_price,
+            barcode: product.barcode.clone(),
+        }
+    }
+}
+
+// Price per kilogram or litre of a product holding `content` of `unit`
+pub fn unit_price(price: f64, content: f64, unit: &str) -> Option<(f64, &'static str)> {
+    let (amount, per) = match unit {
+        "g" => (content / 1000.0, "kg"),
+        "kg" => (content, "kg"),
+        "ml" => (content / 1000.0, "l"),
+        "l" => (content, "l"),
+        _ => return None,
+    };
+    (amount > 0.0).then(|| ((price / amount * 100.0).round() / 100.0, per))
+}
+
+// Labels for the given products, in the order given
+pub fn load_labels(conn: &Connection, product_ids: &[i32], show_unit_price: bool) -> Result<Vec<Label>> {
+    let mut stmt = conn.prepare(&format!("SELECT {} FROM products p WHERE p.id = ?1", PRODUCT_COLUMNS))?;
+    let mut labels = Vec::new();
+    for id in product_ids {
+        let product = stmt
+            .query_row(params![id], product_from_row)
+            .optional()?
+            .ok_or_else(|| AppError::not_found("product"))?;
+        labels.push(Label::new(&product, show_unit_price));
+    }
+    Ok(labels)
+}
+
+// A line of the layout: distance of its top from the top of the label and
+// its height, in millimetres
+#[derive(Debug, Clone, Copy)]
+pub struct Line {
+    pub top: f64,
+    pub size: f64,
+}
+
+// Where things go on a label. Both renderers use the same proportions, so a
+// sheet proof looks like what the Zebra prints.
+#[derive(Debug, Clone, Copy)]
+pub struct Layout {
+    pub width: f64,
+    pub height: f64,
+    pub padding: f64,
+    pub name: Line,
+    pub price: Line,
+    pub unit_price: Line,
+    pub bars: Line,
+    pub digits: Line,
+}
+
+impl Layout {
+    pub fn new(width: f64, height: f64) -> Layout {
+        let padding = 2.0;
+        let inner = height - 2.0 * padding;
+        let line = |top: f64, size: f64| Line { top: padding + top * inner, size: size * inner };
+        Layout {
+            width,
+            height,
+            padding,
+            name: line(0.0, 0.16),
+            price: line(0.2, 0.24),
+            unit_price: line(0.47, 0.08),
+            bars: line(0.6, 0.3),
+            digits: line(0.91, 0.09),
+        }
+    }
+
+    pub fn inner_width(&self) -> f64 {
+        self.width - 2.0 * self.padding
+    }
+}
+
+// First characters of `text` that fit in `width` at `advance` per character
+pub fn fit(text: &str, width: f64, advance: f64) -> String {
+    let max = (width / advance).floor().max(0.0) as usize;
+    text.chars().take(max).collect()
+}
+
+// Label settings, read from the `settings` table:
+// label_width_mm and label_height_mm (Zebra roll labels, default 50 x 30),
+// label_printer_dpi (203 or 300), and label_sheet_columns, label_sheet_rows,
+// label_sheet_width_mm and label_sheet_height_mm (A4 label stock, default
+// 3 x 8 labels of 70 x 37 mm).
+pub struct LabelOptions {
+    pub roll: Layout,
+    pub dots_per_mm: f64,
+    pub sheet: Layout,
+    pub sheet_columns: usize,
+    pub sheet_rows: usize,
+}
+
+impl LabelOptions {
+    pub fn from_settings(conn: &Connection) -> Result<LabelOptions> {
+        let size = |key: &str, default: f64| -> Result<f64> {
+            Ok(get_setting_f64(conn, key, default)?.clamp(15.0, 200.0))
+        };
+        let count = |key: &str, default: f64| -> Result<usize> {
+            Ok(get_setting_f64(conn, key, default)?.clamp(1.0, 20.0) as usize)
+        };
+        let dots_per_mm = if get_setting_f64(conn, "label_printer_dpi", 203.0)? >= 300.0 { 12.0 } else { 8.0 };
+
+        Ok(LabelOptions {
+            roll: Layout::new(size("label_width_mm", 50.0)?, size("label_height_mm", 30.0)?),
+            dots_per_mm,
+            sheet: Layout::new(size("label_sheet_width_mm", 70.0)?, size("label_sheet_height_mm", 37.0)?),
+            sheet_columns: count("label_sheet_columns", 3.0)?,
+            sheet_rows: count("label_sheet_rows", 8.0)?,
+        })
+    }
+}
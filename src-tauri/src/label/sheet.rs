@@ -0,0 +1,148 @@
+// A4 PDF sheets for label stock: labels fill a grid centred on the page, left
+// to right and top to bottom. Like receipt PDFs, text is Courier in
+// Windows-1252, so Arabic names come out as '?'.
+
+use super::{fit, Label, Layout};
+use crate::barcode;
+use crate::receipt::escpos::CodePage;
+use crate::receipt::export::{escape_pdf, COURIER_ADVANCE, PT_PER_MM};
+
+const A4_WIDTH_MM: f64 = 210.0;
+const A4_HEIGHT_MM: f64 = 297.0;
+// EAN/UPC module width at 100% magnification; bars are never drawn wider
+const MAX_MODULE_MM: f64 = 0.33;
+// 80% magnification, the smallest the symbology allows and about the
+// narrowest bar an office printer reproduces reliably
+const MIN_MODULE_MM: f64 = 0.264;
+
+// Content stream for one label whose top left corner is at (`left`, `top`) mm
+fn draw_label(content: &mut Vec<u8>, label: &Label, layout: &Layout, left: f64, top: f64) {
+    let x = |mm: f64| (left + mm) * PT_PER_MM;
+    let y = |mm: f64| (A4_HEIGHT_MM - top - mm) * PT_PER_MM;
+
+    let mut text = |font: &str, line_top: f64, size_mm: f64, value: &str, center: bool| {
+        let value = fit(value, layout.inner_width(), size_mm * COURIER_ADVANCE);
+        let width = value.chars().count() as f64 * size_mm * COURIER_ADVANCE;
+        let offset = if center { (layout.width - width) / 2.0 } else { layout.padding };
+        content.extend(
+            format!(
+                "BT /{} {:.2} Tf {:.2} {:.2} Td (",
+                font,
+                size_mm * PT_PER_MM,
+                x(offset),
+                y(line_top + size_mm * 0.8)
+            )
+            .bytes(),
+        );
+        content.extend(escape_pdf(&CodePage::Wpc1252.encode(&value)));
+        content.extend(b") Tj ET\n");
+    };
+
+    text("F2", layout.name.top, layout.name.size, &label.name, false);
+    text("F2", layout.price.top, layout.price.size, &label.price, false);
+    if let Some(unit_price) = &label.unit_price {
+        text("F1", layout.unit_price.top, layout.unit_price.size, unit_price, false);
+    }
+    let Some(bars) = barcode::bars(&label.barcode) else { return };
+    text("F1", layout.digits.top, layout.digits.size, &label.barcode, true);
+
+    // One filled rectangle per run of bar modules. A label too narrow for
+    // readable bars keeps only the digits.
+    let module = (layout.inner_width() / (bars.len() + 20) as f64).min(MAX_MODULE_MM);
+    if module < MIN_MODULE_MM {
+        return;
+    }
+    let start = (layout.width - module * bars.len() as f64) / 2.0;
+    let mut i = 0;
+    while i < bars.len() {
+        if bars[i] {
+            let run = bars[i..].iter().take_while(|&&bar| bar).count();
+            content.extend(
+                format!(
+                    "{:.2} {:.2} {:.2} {:.2} re\n",
+                    x(start + i as f64 * module),
+                    y(layout.bars.top + layout.bars.size),
+                    run as f64 * module * PT_PER_MM,
+                    layout.bars.size * PT_PER_MM
+                )
+                .bytes(),
+            );
+            i += run;
+        } else {
+            i += 1;
+        }
+    }
+    content.extend(b"f\n");
+}
+
+// `skip` leaves the first positions of the first sheet empty, for reusing a
+// partly used sheet. Each label is repeated `copies` times.
+pub fn render(labels: &[Label], layout: &Layout, columns: usize, rows: usize, skip: usize, copies: u32) -> Vec<u8> {
+    let per_page = columns * rows;
+    let left = ((A4_WIDTH_MM - columns as f64 * layout.width) / 2.0).max(0.0);
+    let top = ((A4_HEIGHT_MM - rows as f64 * layout.height) / 2.0).max(0.0);
+
+    let positions = labels.iter().flat_map(|label| std::iter::repeat_n(label, copies as usize));
+    let mut pages: Vec<Vec<u8>> = Vec::new();
+    for (slot, label) in (skip % per_page..).zip(positions) {
+        if pages.is_empty() || slot % per_page == 0 {
+            pages.push(Vec::new());
+        }
+        let cell = slot % per_page;
+        let (column, row) = (cell % columns, cell / columns);
+        draw_label(
+            pages.last_mut().unwrap(),
+            label,
+            layout,
+            left + column as f64 * layout.width,
+            top + row as f64 * layout.height,
+        );
+    }
+    if pages.is_empty() {
+        pages.push(Vec::new());
+    }
+
+    // Catalog, page tree and fonts first, then a page and its contents for each sheet
+    let kids: Vec<String> = (0..pages.len()).map(|i| format!("{} 0 R", 5 + 2 * i)).collect();
+    let mut objects: Vec<Vec<u8>> = vec![
+        b"<< /Type /Catalog /Pages 2 0 R >>".to_vec(),
+        format!("<< /Type /Pages /Kids [{}] /Count {} >>", kids.join(" "), pages.len()).into_bytes(),
+        b"<< /Type /Font /Subtype /Type1 /BaseFont /Courier /Encoding /WinAnsiEncoding >>".to_vec(),
+        b"<< /Type /Font /Subtype /Type1 /BaseFont /Courier-Bold /Encoding /WinAnsiEncoding >>".to_vec(),
+    ];
+    for (i, content) in pages.into_iter().enumerate() {
+        objects.push(
+            format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {:.2} {:.2}] \
+                /Resources << /Font << /F1 3 0 R /F2 4 0 R >> >> /Contents {} 0 R >>",
+                A4_WIDTH_MM * PT_PER_MM,
+                A4_HEIGHT_MM * PT_PER_MM,
+                6 + 2 * i
+            )
+            .into_bytes(),
+        );
+        objects.push(
+            [format!("<< /Length {} >>\nstream\n", content.len()).into_bytes(), content, b"\nendstream".to_vec()].concat(),
+        );
+    }
+
+    let mut out = b"%PDF-1.4\n".to_vec();
+    let mut offsets = Vec::new();
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(out.len());
+        out.extend(format!("{} 0 obj\n", i + 1).bytes());
+        out.extend(object);
+        out.extend(b"\nendobj\n");
+    }
+
+    let xref = out.len();
+    out.extend(format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).bytes());
+    for offset in offsets {
+        out.extend(format!("{:010} 00000 n \n", offset).bytes());
+    }
+    out.extend(
+        format!("trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n", objects.len() + 1, xref).bytes(),
+    );
+
+    out
+}
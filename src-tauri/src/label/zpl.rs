@@ -0,0 +1,69 @@
+// ZPL II for Zebra label printers: one format per label, repeated with ^PQ.
+//
+// Text uses the printer's scalable font 0 in UTF-8 (^CI28). The built-in
+// font has no Arabic glyphs; those need a font downloaded to the printer.
+
+use super::{fit, Label, Layout};
+use crate::barcode::{self, Symbology};
+
+// ^FH hex escapes for the characters ZPL reads as commands
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '_' => out.push_str("_5F"),
+            '^' => out.push_str("_5E"),
+            '~' => out.push_str("_7E"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+// Barcode command and field data for a code; the printer adds the check
+// digit of EAN/UPC codes itself
+fn barcode_field(code: &str, height: u32) -> Option<String> {
+    let (command, data) = match barcode::detect(code)? {
+        Symbology::Ean13 => (format!("^BEN,{},Y,N", height), code[..12].to_string()),
+        Symbology::Ean8 => (format!("^B8N,{},Y,N", height), code[..7].to_string()),
+        Symbology::UpcA => (format!("^BUN,{},Y,N,Y", height), code[..11].to_string()),
+        // '>' starts a code set switch in ^BC, "><" is a literal '>'
+        Symbology::Code128 => (format!("^BCN,{},Y,N,N", height), code.replace('>', "><")),
+    };
+    Some(format!("{}^FH^FD{}^FS", command, escape(&data)))
+}
+
+pub fn render(labels: &[Label], layout: &Layout, dots_per_mm: f64, copies: u32) -> Vec<u8> {
+    let dots = |mm: f64| (mm * dots_per_mm).round() as u32;
+    // Font 0 characters are roughly half as wide as they are tall
+    let text = |top: f64, size: f64, value: &str| {
+        format!(
+            "^FO{},{}^A0N,{},{}^FH^FD{}^FS\n",
+            dots(layout.padding),
+            dots(top),
+            dots(size),
+            dots(size),
+            escape(&fit(value, layout.inner_width(), size * 0.55))
+        )
+    };
+
+    let mut out = String::new();
+    for label in labels {
+        out.push_str(&format!("^XA\n^CI28\n^PW{}\n^LL{}\n", dots(layout.width), dots(layout.height)));
+        out.push_str(&text(layout.name.top, layout.name.size, &label.name));
+        out.push_str(&text(layout.price.top, layout.price.size, &label.price));
+        if let Some(unit_price) = &label.unit_price {
+            out.push_str(&text(layout.unit_price.top, layout.unit_price.size, unit_price));
+        }
+        if let Some(modules) = barcode::bars(&label.barcode).map(|bars| bars.len()) {
+            // Widest whole-dot module that fits with ten-module quiet zones
+            let module = (dots(layout.inner_width()) / (modules as u32 + 20)).clamp(1, 4);
+            let left = (dots(layout.width).saturating_sub(module * modules as u32)) / 2;
+            if let Some(field) = barcode_field(&label.barcode, dots(layout.bars.size)) {
+                out.push_str(&format!("^FO{},{}^BY{}{}\n", left, dots(layout.bars.top), module, field));
+            }
+        }
+        out.push_str(&format!("^PQ{}\n^XZ\n", copies));
+    }
+    out.into_bytes()
+}
@@ -1,25 +1,92 @@
+mod barcode;
 mod db;
+mod error;
+mod label;
+mod validation;
 mod models;
+mod receipt;
+mod scale;
+mod unit;
 
 use db::establish_connection;
 
 use db::init_db;
-use models::admin::{create_admin_table, add_admin, validate_admin, update_admin, verify_admin_password};
+use error::AppError;
+use models::admin::{create_admin_table, add_admin, authenticate_admin, update_admin, verify_admin_password, AdminLogin};
 use models::employee::{
-    create_employee_table, add_employee, get_employees, update_employee, verify_employee, delete_employee, Employee,
+    create_employee_table, add_employee, get_employees, update_employee, authenticate_employee, delete_employee,
+    set_employee_pin, clear_employee_pin, login_with_pin, Employee, EmployeeLogin,
+};
+use models::lockout::{get_lockouts, clear_failures, get_login_failures, Lockout, LoginFailure};
+use models::session::{start_session, end_session, Actor};
+use models::permission::{
+    get_permission_catalog, save_role, delete_role, get_roles, set_employee_role, get_employee_permissions,
+    manager_override, authorize_for_sale, require_permission, discount_over_limit, is_price_edit,
+    get_overrides, ManagerOverride, OverrideEntry, Permission, Role,
+};
+use models::timeclock::{
+    clock_in, clock_out, start_break, end_break, get_clock_status, get_time_entries, update_time_entry, add_shift,
+    delete_shift, get_shifts, get_timesheets, timesheet_csv, Shift, TimeEntry, Timesheet,
 };
 use models::products::{
-    create_product_table, add_product, get_products, update_product, delete_product, Product,
+    create_product_table, add_product, get_products, update_product, delete_product, archive_product, restore_product,
+    generate_barcode, set_product_net_content, get_price_changed_products, Product,
+};
+use models::availability::{
+    add_availability_rule, delete_availability_rule, get_availability_rules, set_sold_out, set_product_category,
+    AvailabilityRule,
+};
+use models::price::{schedule_price, cancel_scheduled_price, get_price_history, price_at, PriceEntry};
+use models::product_barcode::{
+    add_product_barcode, delete_product_barcode, get_product_barcodes, lookup_barcode, BarcodeMatch, ProductBarcode,
+};
+use models::bundle::{set_bundle, get_bundle, BundleGroup};
+use models::variant::{add_variant, get_variants, Variant, VariantAttribute};
+use models::stock::{
+    set_stock_tracking, adjust_stock, get_stock_levels, get_stock_movements, StockLevel, StockMovement,
+};
+use models::sale::{
+    start_sale, add_sale_item, complete_sale, get_all_sales, apply_sale_discount, void_sale, refund_sale,
+    set_sale_guests, SaleReport,
+};
+use models::report::{
+    get_report, get_product_sales, get_component_sales, get_price_changes, PriceChange, ProductSales, SalesReport,
+};
+use models::payment::{add_payment, get_sale_payments, Payment};
+use models::drawer::{
+    open_drawer_session, get_open_drawer_session, add_drawer_movement, get_drawer_movements,
+    close_drawer_session, get_drawer_sessions, DrawerSession, DrawerMovement,
 };
-use models::sale::{start_sale, add_sale_item, update_sale_total, get_all_sales, SaleReport};
-use models::report::{get_report, SalesReport};
+use models::settings::{get_settings, get_setting, set_setting, Setting};
+use models::shift_report::{get_x_report, issue_z_report, get_z_report, get_z_reports, ShiftReport, ZReportSummary};
+use models::document::{resolve_terminal, get_document_sequences, set_document_sequence, DocumentSequence};
+use models::customer::{
+    add_customer, update_customer, delete_customer, get_customer, search_customers, set_sale_customer,
+    get_customer_sales, get_top_customers, Customer, CustomerSale, TopCustomer,
+};
+use models::loyalty::{get_loyalty_ledger, adjust_points, redeem_points, LoyaltyEntry};
+use models::gift_card::{
+    sell_gift_card, get_gift_card, get_gift_card_ledger, redeem_gift_card, issue_store_credit,
+    get_gift_card_liability, GiftCard, GiftCardEntry, GiftCardLiability,
+};
+use models::performance::{get_employee_performance, EmployeePerformance};
+use models::tip::{
+    add_tip, get_tip_payouts, add_service_charge_rule, set_service_charge_rule_active, delete_service_charge_rule,
+    get_service_charge_rules, ServiceChargeRule, TipPayoutReport,
+};
+use models::receipt_template::{save_receipt_template, delete_receipt_template, ReceiptTemplate};
+use label::{load_labels, sheet, zpl, LabelOptions};
+use receipt::printer::{send, PrinterTarget};
+use scale::{read_stable, Reading};
+use receipt::template::{all_templates, default_template, render};
+use receipt::{escpos, export, load_receipt, render_copy, render_receipt, render_text, ReceiptOptions};
 
 
 // ---------------- ADMIN COMMANDS ----------------
 #[tauri::command]
-fn setup_admin(app: tauri::AppHandle) -> Result<(), String> {
-    let conn = init_db(&app).map_err(|e| e.to_string())?;
-    create_admin_table(&conn).map_err(|e| e.to_string())?;
+fn setup_admin(app: tauri::AppHandle) -> Result<(), AppError> {
+    let conn = init_db(&app)?;
+    create_admin_table(&conn)?;
 
     let count: i32 = conn
         .query_row("SELECT COUNT(*) FROM admins", [], |row| row.get(0))
@@ -33,9 +100,49 @@ fn setup_admin(app: tauri::AppHandle) -> Result<(), String> {
 }
 
 #[tauri::command]
-fn login_admin(app: tauri::AppHandle, name: String, password: String) -> Result<bool, String> {
-    let conn = init_db(&app).map_err(|e| e.to_string())?;
-    validate_admin(&conn, &name, &password).map_err(|e| e.to_string())
+fn login_admin(
+    app: tauri::AppHandle,
+    name: String,
+    password: String,
+    terminal: Option<String>,
+) -> Result<AdminLogin, AppError> {
+    let conn = init_db(&app)?;
+    let terminal = resolve_terminal(&conn, terminal.as_deref())?;
+    let mut login = authenticate_admin(&conn, &name, &password, &terminal)?;
+    if login.success {
+        login.session = Some(start_session(&conn, Actor::Admin)?);
+    }
+    Ok(login)
+}
+
+#[tauri::command]
+fn logout_cmd(app: tauri::AppHandle, session: String) -> Result<(), AppError> {
+    let conn = init_db(&app)?;
+    end_session(&conn, &session)
+}
+
+#[tauri::command]
+fn get_lockouts_cmd(app: tauri::AppHandle) -> Result<Vec<Lockout>, AppError> {
+    let conn = init_db(&app)?;
+    get_lockouts(&conn)
+}
+
+#[tauri::command]
+fn unlock_login_cmd(
+    app: tauri::AppHandle,
+    key: String,
+    session: Option<String>,
+    override_id: Option<i64>,
+) -> Result<(), AppError> {
+    let conn = init_db(&app)?;
+    require_permission(&conn, session.as_deref(), "manage_employees", override_id)?;
+    clear_failures(&conn, &key)
+}
+
+#[tauri::command]
+fn get_login_failures_cmd(app: tauri::AppHandle, limit: Option<i64>) -> Result<Vec<LoginFailure>, AppError> {
+    let conn = init_db(&app)?;
+    get_login_failures(&conn, limit.unwrap_or(100))
 }
 
 #[tauri::command]
@@ -44,140 +151,971 @@ fn update_admin_cmd(
     old_password: String,
     new_name: String,
     new_password: String,
-) -> Result<bool, String> {
-    let conn = init_db(&app).map_err(|e| e.to_string())?;
-    update_admin(&conn, &old_password, &new_name, &new_password)
-        .map_err(|e| e.to_string())
+    terminal: Option<String>,
+) -> Result<AdminLogin, AppError> {
+    let conn = init_db(&app)?;
+    let terminal = resolve_terminal(&conn, terminal.as_deref())?;
+    update_admin(&conn, &old_password, &new_name, &new_password, &terminal)
 }
 
 #[tauri::command]
-fn verify_old_password_cmd(app: tauri::AppHandle, old_password: String) -> Result<bool, String> {
-    let conn = init_db(&app).map_err(|e| e.to_string())?;
-    verify_admin_password(&conn, &old_password).map_err(|e| e.to_string())
+fn verify_old_password_cmd(
+    app: tauri::AppHandle,
+    old_password: String,
+    terminal: Option<String>,
+) -> Result<AdminLogin, AppError> {
+    let conn = init_db(&app)?;
+    let terminal = resolve_terminal(&conn, terminal.as_deref())?;
+    verify_admin_password(&conn, &old_password, &terminal)
 }
 
 #[tauri::command]
-fn reset_admin(app: tauri::AppHandle) -> Result<(), String> {
-    let conn = init_db(&app).map_err(|e| e.to_string())?;
-    conn.execute("UPDATE admins SET name='admin', password='1234' WHERE id=1", [])
-        .map_err(|e| e.to_string())?;
+fn reset_admin(app: tauri::AppHandle) -> Result<(), AppError> {
+    let conn = init_db(&app)?;
+    conn.execute("UPDATE admins SET name='admin', password='1234' WHERE id=1", [])?;
     Ok(())
 }
 
 #[tauri::command]
-fn get_admin_name(app: tauri::AppHandle) -> Result<String, String> {
-    let conn = init_db(&app).map_err(|e| e.to_string())?;
+fn get_admin_name(app: tauri::AppHandle) -> Result<String, AppError> {
+    let conn = init_db(&app)?;
     let name: String = conn
-        .query_row("SELECT name FROM admins WHERE id = 1", [], |row| row.get(0))
-        .map_err(|e| e.to_string())?;
+        .query_row("SELECT name FROM admins WHERE id = 1", [], |row| row.get(0))?;
     Ok(name)
 }
 
 
 // -------- EMPLOYEE COMMANDS --------
 #[tauri::command]
-fn setup_employee_table(app: tauri::AppHandle) -> Result<(), String> {
-    let conn = init_db(&app).map_err(|e| e.to_string())?;
-    create_employee_table(&conn).map_err(|e| e.to_string())
+fn setup_employee_table(app: tauri::AppHandle) -> Result<(), AppError> {
+    let conn = init_db(&app)?;
+    create_employee_table(&conn)
+}
+
+#[tauri::command]
+fn add_new_employee(
+    app: tauri::AppHandle,
+    name: String,
+    password: String,
+    session: Option<String>,
+    override_id: Option<i64>,
+) -> Result<(), AppError> {
+    let conn = init_db(&app)?;
+    require_permission(&conn, session.as_deref(), "manage_employees", override_id)?;
+    add_employee(&conn, &name, &password).map(|_| ())
+}
+
+#[tauri::command]
+fn fetch_employees(app: tauri::AppHandle) -> Result<Vec<Employee>, AppError> {
+    let conn = init_db(&app)?;
+    get_employees(&conn)
+}
+
+#[tauri::command]
+fn update_employee_cmd(
+    app: tauri::AppHandle,
+    id: i32,
+    name: String,
+    password: String,
+    session: Option<String>,
+    override_id: Option<i64>,
+) -> Result<(), AppError> {
+    let conn = init_db(&app)?;
+    require_permission(&conn, session.as_deref(), "manage_employees", override_id)?;
+    update_employee(&conn, id, &name, &password).map(|_| ())
+}
+
+#[tauri::command]
+fn login_employee_cmd(
+    app: tauri::AppHandle,
+    name: String,
+    password: String,
+    terminal: Option<String>,
+) -> Result<EmployeeLogin, AppError> {
+    let conn = init_db(&app)?;
+    let terminal = resolve_terminal(&conn, terminal.as_deref())?;
+    with_session(&conn, authenticate_employee(&conn, &name, &password, &terminal)?)
+}
+
+#[tauri::command]
+fn delete_employee_cmd(
+    app: tauri::AppHandle,
+    id: i32,
+    session: Option<String>,
+    override_id: Option<i64>,
+) -> Result<(), AppError> {
+    let conn = init_db(&app)?;
+    require_permission(&conn, session.as_deref(), "manage_employees", override_id)?;
+    delete_employee(&conn, id).map(|_| ())
+}
+
+#[tauri::command]
+fn set_employee_pin_cmd(
+    app: tauri::AppHandle,
+    id: i32,
+    pin: String,
+    session: Option<String>,
+    override_id: Option<i64>,
+) -> Result<bool, AppError> {
+    let conn = init_db(&app)?;
+    require_permission(&conn, session.as_deref(), "manage_employees", override_id)?;
+    set_employee_pin(&conn, id, &pin)
+}
+
+#[tauri::command]
+fn clear_employee_pin_cmd(
+    app: tauri::AppHandle,
+    id: i32,
+    session: Option<String>,
+    override_id: Option<i64>,
+) -> Result<(), AppError> {
+    let conn = init_db(&app)?;
+    require_permission(&conn, session.as_deref(), "manage_employees", override_id)?;
+    clear_employee_pin(&conn, id).map(|_| ())
+}
+
+// Used both for quick login and for switching user on the lock screen; the
+// POS keeps its cart, so an order in progress survives the switch.
+#[tauri::command]
+fn pin_login_cmd(app: tauri::AppHandle, pin: String, terminal: Option<String>) -> Result<EmployeeLogin, AppError> {
+    let conn = init_db(&app)?;
+    let terminal = resolve_terminal(&conn, terminal.as_deref())?;
+    with_session(&conn, login_with_pin(&conn, &pin, &terminal)?)
+}
+
+// Start a session for an employee who signed in
+fn with_session(conn: &rusqlite::Connection, mut login: EmployeeLogin) -> Result<EmployeeLogin, AppError> {
+    if let Some(employee) = &login.employee {
+        login.session = Some(start_session(conn, Actor::Employee(employee.id))?);
+    }
+    Ok(login)
+}
+
+
+// ---------------- ROLE COMMANDS ----------------
+#[tauri::command]
+fn get_permission_catalog_cmd() -> Vec<Permission> {
+    get_permission_catalog()
+}
+
+#[tauri::command]
+fn get_roles_cmd(app: tauri::AppHandle) -> Result<Vec<Role>, AppError> {
+    let conn = init_db(&app)?;
+    get_roles(&conn)
+}
+
+#[tauri::command]
+fn save_role_cmd(
+    app: tauri::AppHandle,
+    id: Option<i64>,
+    name: String,
+    permissions: Vec<String>,
+    session: Option<String>,
+    override_id: Option<i64>,
+) -> Result<i64, AppError> {
+    let conn = init_db(&app)?;
+    require_permission(&conn, session.as_deref(), "manage_employees", override_id)?;
+    save_role(&conn, id, &name, &permissions)
+}
+
+#[tauri::command]
+fn delete_role_cmd(
+    app: tauri::AppHandle,
+    id: i64,
+    session: Option<String>,
+    override_id: Option<i64>,
+) -> Result<(), AppError> {
+    let conn = init_db(&app)?;
+    require_permission(&conn, session.as_deref(), "manage_employees", override_id)?;
+    delete_role(&conn, id)
+}
+
+#[tauri::command]
+fn set_employee_role_cmd(
+    app: tauri::AppHandle,
+    employee_id: i32,
+    role_id: Option<i64>,
+    session: Option<String>,
+    override_id: Option<i64>,
+) -> Result<(), AppError> {
+    let conn = init_db(&app)?;
+    require_permission(&conn, session.as_deref(), "manage_employees", override_id)?;
+    set_employee_role(&conn, employee_id, role_id).map(|_| ())
+}
+
+#[tauri::command]
+fn get_employee_permissions_cmd(app: tauri::AppHandle, employee_id: i32) -> Result<Vec<String>, AppError> {
+    let conn = init_db(&app)?;
+    get_employee_permissions(&conn, employee_id)
+}
+
+// Supervisor PIN entered on the cashier's screen; the returned override id
+// is passed to the guarded command and works once.
+#[tauri::command]
+fn manager_override_cmd(
+    app: tauri::AppHandle,
+    pin: String,
+    permission: String,
+    employee_id: i32,
+    terminal: Option<String>,
+) -> Result<ManagerOverride, AppError> {
+    let conn = init_db(&app)?;
+    let terminal = resolve_terminal(&conn, terminal.as_deref())?;
+    manager_override(&conn, &pin, &permission, employee_id, &terminal)
+}
+
+#[tauri::command]
+fn get_overrides_cmd(app: tauri::AppHandle, limit: Option<i64>) -> Result<Vec<OverrideEntry>, AppError> {
+    let conn = init_db(&app)?;
+    get_overrides(&conn, limit.unwrap_or(100))
+}
+
+
+// ---------------- TIME CLOCK COMMANDS ----------------
+#[tauri::command]
+fn clock_in_cmd(app: tauri::AppHandle, employee_id: i32) -> Result<bool, AppError> {
+    let conn = init_db(&app)?;
+    clock_in(&conn, employee_id)
+}
+
+#[tauri::command]
+fn clock_out_cmd(app: tauri::AppHandle, employee_id: i32) -> Result<bool, AppError> {
+    let conn = init_db(&app)?;
+    clock_out(&conn, employee_id)
+}
+
+#[tauri::command]
+fn start_break_cmd(app: tauri::AppHandle, employee_id: i32, paid: bool) -> Result<bool, AppError> {
+    let conn = init_db(&app)?;
+    start_break(&conn, employee_id, paid)
+}
+
+#[tauri::command]
+fn end_break_cmd(app: tauri::AppHandle, employee_id: i32) -> Result<bool, AppError> {
+    let conn = init_db(&app)?;
+    end_break(&conn, employee_id)
+}
+
+#[tauri::command]
+fn get_clock_status_cmd(app: tauri::AppHandle, employee_id: i32) -> Result<Option<TimeEntry>, AppError> {
+    let conn = init_db(&app)?;
+    get_clock_status(&conn, employee_id)
+}
+
+#[tauri::command]
+fn get_time_entries_cmd(
+    app: tauri::AppHandle,
+    start_date: String,
+    end_date: String,
+    employee_id: Option<i32>,
+) -> Result<Vec<TimeEntry>, AppError> {
+    let conn = init_db(&app)?;
+    get_time_entries(&conn, &start_date, &end_date, employee_id)
+}
+
+#[tauri::command]
+fn update_time_entry_cmd(
+    app: tauri::AppHandle,
+    id: i64,
+    clock_in: String,
+    clock_out: Option<String>,
+    note: String,
+    session: Option<String>,
+    override_id: Option<i64>,
+) -> Result<(), AppError> {
+    let conn = init_db(&app)?;
+    require_permission(&conn, session.as_deref(), "manage_employees", override_id)?;
+    update_time_entry(&conn, id, &clock_in, clock_out.as_deref(), &note)
+        .map(|_| ())
 }
 
 #[tauri::command]
-fn add_new_employee(app: tauri::AppHandle, name: String, password: String) -> Result<(), String> {
-    let conn = init_db(&app).map_err(|e| e.to_string())?;
-    add_employee(&conn, &name, &password).map(|_| ()).map_err(|e| e.to_string())
+fn add_shift_cmd(
+    app: tauri::AppHandle,
+    employee_id: i32,
+    starts_at: String,
+    ends_at: String,
+    note: String,
+    session: Option<String>,
+    override_id: Option<i64>,
+) -> Result<i64, AppError> {
+    let conn = init_db(&app)?;
+    require_permission(&conn, session.as_deref(), "manage_employees", override_id)?;
+    add_shift(&conn, employee_id, &starts_at, &ends_at, &note)
 }
 
 #[tauri::command]
-fn fetch_employees(app: tauri::AppHandle) -> Result<Vec<Employee>, String> {
-    let conn = init_db(&app).map_err(|e| e.to_string())?;
-    get_employees(&conn).map_err(|e| e.to_string())
+fn delete_shift_cmd(
+    app: tauri::AppHandle,
+    id: i64,
+    session: Option<String>,
+    override_id: Option<i64>,
+) -> Result<(), AppError> {
+    let conn = init_db(&app)?;
+    require_permission(&conn, session.as_deref(), "manage_employees", override_id)?;
+    delete_shift(&conn, id).map(|_| ())
 }
 
 #[tauri::command]
-fn update_employee_cmd(app: tauri::AppHandle, id: i32, name: String, password: String) -> Result<(), String> {
-    let conn = init_db(&app).map_err(|e| e.to_string())?;
-    update_employee(&conn, id, &name, &password).map(|_| ()).map_err(|e| e.to_string())
+fn get_shifts_cmd(app: tauri::AppHandle, start_date: String, end_date: String) -> Result<Vec<Shift>, AppError> {
+    let conn = init_db(&app)?;
+    get_shifts(&conn, &start_date, &end_date)
 }
 
 #[tauri::command]
-fn login_employee_cmd(app: tauri::AppHandle, name: String, password: String) -> Result<Option<Employee>, String> {
-    let conn = init_db(&app).map_err(|e| e.to_string())?;
-    verify_employee(&conn, &name, &password).map_err(|e| e.to_string())
+fn get_timesheets_cmd(
+    app: tauri::AppHandle,
+    start_date: String,
+    end_date: String,
+    session: Option<String>,
+    override_id: Option<i64>,
+) -> Result<Vec<Timesheet>, AppError> {
+    let conn = init_db(&app)?;
+    require_permission(&conn, session.as_deref(), "view_reports", override_id)?;
+    get_timesheets(&conn, &start_date, &end_date)
 }
 
+// Write the pay period's timesheets to `path` as CSV
 #[tauri::command]
-fn delete_employee_cmd(app: tauri::AppHandle, id: i32) -> Result<(), String> {
-    let conn = init_db(&app).map_err(|e| e.to_string())?;
-    delete_employee(&conn, id).map(|_| ()).map_err(|e| e.to_string())
+fn export_timesheets_cmd(
+    app: tauri::AppHandle,
+    start_date: String,
+    end_date: String,
+    path: String,
+    session: Option<String>,
+    override_id: Option<i64>,
+) -> Result<(), AppError> {
+    let conn = init_db(&app)?;
+    require_permission(&conn, session.as_deref(), "view_reports", override_id)?;
+    let csv = timesheet_csv(&conn, &start_date, &end_date)?;
+    Ok(std::fs::write(&path, csv)?)
 }
 
 
 // ---------------- PRODUCT COMMANDS ----------------
 #[tauri::command]
-fn setup_product_table(app: tauri::AppHandle) -> Result<(), String> {
-    let conn = init_db(&app).map_err(|e| e.to_string())?;
-    create_product_table(&conn).map_err(|e| e.to_string())
+fn setup_product_table(app: tauri::AppHandle) -> Result<(), AppError> {
+    let conn = init_db(&app)?;
+    create_product_table(&conn)
 }
 
 #[tauri::command]
-fn add_product_cmd(app: tauri::AppHandle, name: String, price: f64, barcode: Option<String>) -> Result<(), String> {
-    let conn = init_db(&app).map_err(|e| e.to_string())?;
-    add_product(&conn, &name, price, barcode.as_deref())
-        .map_err(|e| e.to_string())?;
+fn add_product_cmd(
+    app: tauri::AppHandle,
+    name: String,
+    price: f64,
+    barcode: Option<String>,
+    unit: Option<String>,
+) -> Result<(), AppError> {
+    let conn = init_db(&app)?;
+    add_product(&conn, &name, price, barcode.as_deref(), unit.as_deref())?;
     Ok(())
 }
 
 #[tauri::command]
-fn get_products_cmd(app: tauri::AppHandle) -> Result<Vec<Product>, String> {
-    let conn = init_db(&app).map_err(|e| e.to_string())?;
-    get_products(&conn).map_err(|e| e.to_string())
+fn get_products_cmd(app: tauri::AppHandle, include_archived: Option<bool>) -> Result<Vec<Product>, AppError> {
+    let conn = init_db(&app)?;
+    get_products(&conn, include_archived.unwrap_or(false))
 }
 
 #[tauri::command]
-fn update_product_cmd(app: tauri::AppHandle, id: i32, name: String, price: f64, barcode: String) -> Result<(), String> {
-    let conn = init_db(&app).map_err(|e| e.to_string())?;
-    update_product(&conn, id, &name, price, &barcode).map(|_| ()).map_err(|e| e.to_string())
+fn update_product_cmd(
+    app: tauri::AppHandle,
+    id: i32,
+    name: String,
+    price: f64,
+    barcode: String,
+    unit: String,
+) -> Result<(), AppError> {
+    let conn = init_db(&app)?;
+    update_product(&conn, id, &name, price, &barcode, &unit).map(|_| ())
 }
 
 #[tauri::command]
-fn delete_product_cmd(app: tauri::AppHandle, id: i32) -> Result<(), String> {
-    let conn = init_db(&app).map_err(|e| e.to_string())?;
-    delete_product(&conn, id).map(|_| ()).map_err(|e| e.to_string())
+fn delete_product_cmd(app: tauri::AppHandle, id: i32) -> Result<(), AppError> {
+    let conn = init_db(&app)?;
+    delete_product(&conn, id).map(|_| ())
+}
+
+#[tauri::command]
+fn archive_product_cmd(app: tauri::AppHandle, id: i32) -> Result<(), AppError> {
+    let conn = init_db(&app)?;
+    archive_product(&conn, id).map(|_| ())
+}
+
+#[tauri::command]
+fn restore_product_cmd(app: tauri::AppHandle, id: i32) -> Result<(), AppError> {
+    let conn = init_db(&app)?;
+    restore_product(&conn, id).map(|_| ())
+}
+
+// Next free in-store EAN-13 code, for filling in the product form
+#[tauri::command]
+fn generate_barcode_cmd(app: tauri::AppHandle) -> Result<String, AppError> {
+    let conn = init_db(&app)?;
+    generate_barcode(&conn)
+}
+
+// Resolve a scanned code to a product, pack size and price (None if unknown)
+#[tauri::command]
+fn lookup_barcode_cmd(app: tauri::AppHandle, barcode: String) -> Result<Option<BarcodeMatch>, AppError> {
+    let conn = init_db(&app)?;
+    lookup_barcode(&conn, &barcode)
+}
+
+#[tauri::command]
+fn get_product_barcodes_cmd(app: tauri::AppHandle, product_id: i32) -> Result<Vec<ProductBarcode>, AppError> {
+    let conn = init_db(&app)?;
+    get_product_barcodes(&conn, product_id)
+}
+
+#[tauri::command]
+fn add_product_barcode_cmd(
+    app: tauri::AppHandle,
+    product_id: i32,
+    barcode: String,
+    pack_quantity: i32,
+) -> Result<i64, AppError> {
+    let conn = init_db(&app)?;
+    add_product_barcode(&conn, product_id, &barcode, pack_quantity)
+}
+
+#[tauri::command]
+fn delete_product_barcode_cmd(app: tauri::AppHandle, id: i64) -> Result<(), AppError> {
+    let conn = init_db(&app)?;
+    delete_product_barcode(&conn, id).map(|_| ())
+}
+
+#[tauri::command]
+fn set_product_net_content_cmd(
+    app: tauri::AppHandle,
+    id: i32,
+    net_content: Option<f64>,
+    unit: Option<String>,
+) -> Result<(), AppError> {
+    let conn = init_db(&app)?;
+    set_product_net_content(&conn, id, net_content, unit.as_deref()).map(|_| ())
+}
+
+// Products to relabel after price changes since `since`
+#[tauri::command]
+fn get_price_changed_products_cmd(app: tauri::AppHandle, since: String) -> Result<Vec<Product>, AppError> {
+    let conn = init_db(&app)?;
+    get_price_changed_products(&conn, &since)
+}
+
+#[tauri::command]
+fn get_price_history_cmd(app: tauri::AppHandle, product_id: i32) -> Result<Vec<PriceEntry>, AppError> {
+    let conn = init_db(&app)?;
+    get_price_history(&conn, product_id)
+}
+
+#[tauri::command]
+fn schedule_price_cmd(
+    app: tauri::AppHandle,
+    product_id: i32,
+    price: f64,
+    effective_at: String,
+) -> Result<i64, AppError> {
+    let conn = init_db(&app)?;
+    schedule_price(&conn, product_id, price, &effective_at)
+}
+
+#[tauri::command]
+fn cancel_scheduled_price_cmd(app: tauri::AppHandle, id: i64) -> Result<(), AppError> {
+    let conn = init_db(&app)?;
+    cancel_scheduled_price(&conn, id).map(|_| ())
+}
+
+// What the product cost at `at` (a date or date and time)
+#[tauri::command]
+fn get_price_at_cmd(app: tauri::AppHandle, product_id: i32, at: String) -> Result<Option<f64>, AppError> {
+    let conn = init_db(&app)?;
+    price_at(&conn, product_id, &at)
+}
+
+// Current weight on the scale at `scale_port`, waiting up to about two
+// seconds for it to settle. The wait runs off the main thread so the window
+// stays responsive.
+#[tauri::command]
+async fn read_scale_cmd(app: tauri::AppHandle) -> Result<Reading, AppError> {
+    let port = get_setting(&init_db(&app)?, "scale_port")?
+        .ok_or_else(|| AppError::validation("scale_port", "required", "No scale configured"))?;
+    let reading = tauri::async_runtime::spawn_blocking(move || {
+        let mut scale = scale::open(&port);
+        read_stable(scale.as_mut(), 10, std::time::Duration::from_millis(200))
+    })
+    .await
+    .map_err(|e| std::io::Error::other(e.to_string()))?;
+    Ok(reading?)
+}
+
+// ---------------- VARIANT & STOCK COMMANDS ----------------
+#[tauri::command]
+fn add_variant_cmd(
+    app: tauri::AppHandle,
+    parent_id: i32,
+    attributes: Vec<VariantAttribute>,
+    price: f64,
+    barcode: Option<String>,
+) -> Result<i64, AppError> {
+    let conn = init_db(&app)?;
+    add_variant(&conn, parent_id, &attributes, price, barcode.as_deref())
+}
+
+#[tauri::command]
+fn get_variants_cmd(app: tauri::AppHandle, parent_id: i32) -> Result<Vec<Variant>, AppError> {
+    let conn = init_db(&app)?;
+    get_variants(&conn, parent_id)
+}
+
+#[tauri::command]
+fn set_stock_tracking_cmd(app: tauri::AppHandle, product_id: i32, track: bool) -> Result<(), AppError> {
+    let conn = init_db(&app)?;
+    set_stock_tracking(&conn, product_id, track).map(|_| ())
+}
+
+#[tauri::command]
+fn adjust_stock_cmd(app: tauri::AppHandle, product_id: i32, quantity: f64, note: String) -> Result<i64, AppError> {
+    let conn = init_db(&app)?;
+    adjust_stock(&conn, product_id, quantity, &note)
+}
+
+#[tauri::command]
+fn get_stock_levels_cmd(app: tauri::AppHandle) -> Result<Vec<StockLevel>, AppError> {
+    let conn = init_db(&app)?;
+    get_stock_levels(&conn)
+}
+
+#[tauri::command]
+fn get_stock_movements_cmd(
+    app: tauri::AppHandle,
+    product_id: i32,
+    limit: Option<i64>,
+) -> Result<Vec<StockMovement>, AppError> {
+    let conn = init_db(&app)?;
+    get_stock_movements(&conn, product_id, limit.unwrap_or(100))
+}
+
+// ---------------- AVAILABILITY COMMANDS ----------------
+#[tauri::command]
+fn get_availability_rules_cmd(app: tauri::AppHandle) -> Result<Vec<AvailabilityRule>, AppError> {
+    let conn = init_db(&app)?;
+    get_availability_rules(&conn)
+}
+
+#[tauri::command]
+fn add_availability_rule_cmd(app: tauri::AppHandle, rule: AvailabilityRule) -> Result<i64, AppError> {
+    let conn = init_db(&app)?;
+    add_availability_rule(&conn, &rule)
+}
+
+#[tauri::command]
+fn delete_availability_rule_cmd(app: tauri::AppHandle, id: i64) -> Result<(), AppError> {
+    let conn = init_db(&app)?;
+    delete_availability_rule(&conn, id).map(|_| ())
+}
+
+#[tauri::command]
+fn set_product_category_cmd(app: tauri::AppHandle, id: i32, category: Option<String>) -> Result<(), AppError> {
+    let conn = init_db(&app)?;
+    set_product_category(&conn, id, category.as_deref()).map(|_| ())
+}
+
+// "86" a product for the rest of today, or put it back on sale
+#[tauri::command]
+fn set_sold_out_cmd(app: tauri::AppHandle, product_id: i32, sold_out: bool) -> Result<(), AppError> {
+    let conn = init_db(&app)?;
+    set_sold_out(&conn, product_id, sold_out, chrono::Local::now().date_naive()).map(|_| ())
+}
+
+// ---------------- BUNDLE COMMANDS ----------------
+#[tauri::command]
+fn set_bundle_cmd(app: tauri::AppHandle, bundle_id: i32, groups: Vec<BundleGroup>) -> Result<(), AppError> {
+    let conn = init_db(&app)?;
+    set_bundle(&conn, bundle_id, &groups)
+}
+
+#[tauri::command]
+fn get_bundle_cmd(app: tauri::AppHandle, bundle_id: i32) -> Result<Vec<BundleGroup>, AppError> {
+    let conn = init_db(&app)?;
+    get_bundle(&conn, bundle_id)
+}
+
+// ---------------- LABEL COMMANDS ----------------
+// Shelf labels on the Zebra printer at `label_printer_target`
+#[tauri::command]
+fn print_labels_cmd(app: tauri::AppHandle, product_ids: Vec<i32>, copies: u32, unit_price: bool) -> Result<(), AppError> {
+    let conn = init_db(&app)?;
+    let copies = validation::copies(copies)?;
+    let target = get_setting(&conn, "label_printer_target")?
+        .ok_or_else(|| AppError::validation("label_printer_target", "required", "No label printer configured"))?;
+    let labels = load_labels(&conn, &product_ids, unit_price)?;
+    let options = LabelOptions::from_settings(&conn)?;
+
+    let bytes = zpl::render(&labels, &options.roll, options.dots_per_mm, copies);
+    Ok(send(&PrinterTarget::parse(&target), &bytes)?)
+}
+
+// Shelf labels as an A4 PDF sheet saved to `path`, starting `skip` positions
+// into the first sheet
+#[tauri::command]
+fn export_labels_cmd(
+    app: tauri::AppHandle,
+    product_ids: Vec<i32>,
+    copies: u32,
+    unit_price: bool,
+    skip: Option<usize>,
+    path: String,
+) -> Result<(), AppError> {
+    let conn = init_db(&app)?;
+    let copies = validation::copies(copies)?;
+    let labels = load_labels(&conn, &product_ids, unit_price)?;
+    let options = LabelOptions::from_settings(&conn)?;
+
+    let bytes = sheet::render(
+        &labels,
+        &options.sheet,
+        options.sheet_columns,
+        options.sheet_rows,
+        skip.unwrap_or(0),
+        copies,
+    );
+    Ok(std::fs::write(&path, bytes)?)
 }
 
 // ---------------- SALE COMMANDS ----------------
 #[tauri::command]
-fn start_sale_cmd(app: tauri::AppHandle, employee_id: i32) -> Result<i64, String> {
-    let conn = init_db(&app).map_err(|e| e.to_string())?;
+fn start_sale_cmd(app: tauri::AppHandle, employee_id: i32) -> Result<i64, AppError> {
+    let conn = init_db(&app)?;
 
-    start_sale(&conn, employee_id).map_err(|e| e.to_string())
+    start_sale(&conn, employee_id)
 }
 
 
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 fn add_sale_item_cmd(
     app: tauri::AppHandle,
     sale_id: i64,
     product_id: i32,
-    quantity: i32,
+    quantity: f64,
     price: f64,  
-    extra_amount: f64 
-) -> Result<(), String> {
-    let conn = init_db(&app).map_err(|e| e.to_string())?;
-    add_sale_item(&conn, sale_id, product_id, quantity, price, extra_amount)
-        .map_err(|e| e.to_string())
+    extra_amount: f64,
+    barcode: Option<String>,
+    override_id: Option<i64>,
+    choices: Option<Vec<i32>>,
+) -> Result<(), AppError> {
+    let conn = init_db(&app)?;
+    if is_price_edit(&conn, product_id, price, barcode.as_deref())?
+        && !authorize_for_sale(&conn, sale_id, "edit_prices", override_id)?
+    {
+        return Err(AppError::unauthorized("edit_prices"));
+    }
+    add_sale_item(&conn, sale_id, product_id, quantity, price, extra_amount, &choices.unwrap_or_default())
 }
 
 #[tauri::command]
-fn finalize_sale_cmd(app: tauri::AppHandle, sale_id: i64) -> Result<(), String> {
-    let conn = init_db(&app).map_err(|e| e.to_string())?;
-    update_sale_total(&conn, sale_id).map_err(|e| e.to_string())
+fn finalize_sale_cmd(app: tauri::AppHandle, sale_id: i64, terminal: Option<String>) -> Result<String, AppError> {
+    let conn = init_db(&app)?;
+    let terminal = resolve_terminal(&conn, terminal.as_deref())?;
+    complete_sale(&conn, sale_id, &terminal)
 }
 
 #[tauri::command]
-fn get_all_sales_cmd(app: tauri::AppHandle) -> Result<Vec<SaleReport>, String> {
-    let conn = init_db(&app).map_err(|e| e.to_string())?;
-    get_all_sales(&conn).map_err(|e| e.to_string())
+fn get_all_sales_cmd(
+    app: tauri::AppHandle,
+    session: Option<String>,
+    override_id: Option<i64>,
+) -> Result<Vec<SaleReport>, AppError> {
+    let conn = init_db(&app)?;
+    require_permission(&conn, session.as_deref(), "view_reports", override_id)?;
+    get_all_sales(&conn)
+}
+
+#[tauri::command]
+fn apply_discount_cmd(
+    app: tauri::AppHandle,
+    sale_id: i64,
+    discount: f64,
+    override_id: Option<i64>,
+) -> Result<(), AppError> {
+    let conn = init_db(&app)?;
+    if discount_over_limit(&conn, sale_id, discount)?
+        && !authorize_for_sale(&conn, sale_id, "discount_over_limit", override_id)?
+    {
+        return Err(AppError::unauthorized("discount_over_limit"));
+    }
+    apply_sale_discount(&conn, sale_id, discount)
+}
+
+#[tauri::command]
+fn void_sale_cmd(
+    app: tauri::AppHandle,
+    sale_id: i64,
+    session: Option<String>,
+    override_id: Option<i64>,
+) -> Result<bool, AppError> {
+    let conn = init_db(&app)?;
+    require_permission(&conn, session.as_deref(), "void_item", override_id)?;
+    void_sale(&conn, sale_id)
+}
+
+#[tauri::command]
+fn refund_sale_cmd(
+    app: tauri::AppHandle,
+    sale_id: i64,
+    session: Option<String>,
+    terminal: Option<String>,
+    override_id: Option<i64>,
+) -> Result<i64, AppError> {
+    let conn = init_db(&app)?;
+    // The refund is rung up by an employee, so the admin cannot issue one
+    let employee_id = require_permission(&conn, session.as_deref(), "refund", override_id)?
+        .employee_id()
+        .ok_or_else(|| AppError::unauthorized("refund"))?;
+    let terminal = resolve_terminal(&conn, terminal.as_deref())?;
+    refund_sale(&conn, sale_id, employee_id, &terminal)
+}
+
+#[tauri::command]
+fn add_payment_cmd(app: tauri::AppHandle, sale_id: i64, method: String, amount: f64) -> Result<i64, AppError> {
+    let conn = init_db(&app)?;
+    add_payment(&conn, sale_id, &method, amount)
+}
+
+#[tauri::command]
+fn get_sale_payments_cmd(app: tauri::AppHandle, sale_id: i64) -> Result<Vec<Payment>, AppError> {
+    let conn = init_db(&app)?;
+    get_sale_payments(&conn, sale_id)
+}
+
+#[tauri::command]
+fn add_tip_cmd(
+    app: tauri::AppHandle,
+    sale_id: i64,
+    amount: f64,
+    payment_id: Option<i64>,
+) -> Result<i64, AppError> {
+    let conn = init_db(&app)?;
+    add_tip(&conn, sale_id, amount, payment_id)
+}
+
+#[tauri::command]
+fn set_sale_guests_cmd(app: tauri::AppHandle, sale_id: i64, guests: Option<i64>) -> Result<Option<f64>, AppError> {
+    let conn = init_db(&app)?;
+    set_sale_guests(&conn, sale_id, guests)
+}
+
+
+// ---------------- CASH DRAWER COMMANDS ----------------
+#[tauri::command]
+fn open_drawer_cmd(
+    app: tauri::AppHandle,
+    employee_id: i32,
+    terminal: String,
+    opening_float: f64,
+) -> Result<i64, AppError> {
+    let conn = init_db(&app)?;
+    open_drawer_session(&conn, employee_id, &terminal, opening_float)
+}
+
+// Kick the drawer open on the receipt printer outside of a sale
+#[tauri::command]
+fn open_drawer_no_sale_cmd(
+    app: tauri::AppHandle,
+    session: Option<String>,
+    override_id: Option<i64>,
+) -> Result<(), AppError> {
+    let conn = init_db(&app)?;
+    require_permission(&conn, session.as_deref(), "open_drawer_no_sale", override_id)?;
+    let target = get_setting(&conn, "printer_target")?
+        .ok_or_else(|| AppError::validation("printer_target", "required", "No receipt printer configured"))?;
+    Ok(send(&PrinterTarget::parse(&target), &escpos::drawer_kick())?)
+}
+
+#[tauri::command]
+fn get_open_drawer_cmd(app: tauri::AppHandle, terminal: String) -> Result<Option<DrawerSession>, AppError> {
+    let conn = init_db(&app)?;
+    get_open_drawer_session(&conn, &terminal)
+}
+
+#[tauri::command]
+fn drawer_movement_cmd(
+    app: tauri::AppHandle,
+    session_id: i64,
+    kind: String,
+    amount: f64,
+    reason: String,
+) -> Result<bool, AppError> {
+    let conn = init_db(&app)?;
+    add_drawer_movement(&conn, session_id, &kind, amount, &reason)
+}
+
+#[tauri::command]
+fn get_drawer_movements_cmd(app: tauri::AppHandle, session_id: i64) -> Result<Vec<DrawerMovement>, AppError> {
+    let conn = init_db(&app)?;
+    get_drawer_movements(&conn, session_id)
+}
+
+// Blind close: nothing about the expected amount is returned to the cashier.
+#[tauri::command]
+fn close_drawer_cmd(app: tauri::AppHandle, session_id: i64, counted_cash: f64) -> Result<bool, AppError> {
+    let conn = init_db(&app)?;
+    close_drawer_session(&conn, session_id, counted_cash)
+}
+
+#[tauri::command]
+fn get_drawer_sessions_cmd(
+    app: tauri::AppHandle,
+    start_date: Option<String>,
+    end_date: Option<String>,
+    employee_id: Option<i32>,
+) -> Result<Vec<DrawerSession>, AppError> {
+    let conn = init_db(&app)?;
+    get_drawer_sessions(&conn, start_date.as_deref(), end_date.as_deref(), employee_id)
+}
+
+
+// ---------------- CUSTOMER COMMANDS ----------------
+#[tauri::command]
+fn add_customer_cmd(
+    app: tauri::AppHandle,
+    name: String,
+    phone: Option<String>,
+    email: Option<String>,
+    notes: String,
+) -> Result<i64, AppError> {
+    let conn = init_db(&app)?;
+    add_customer(&conn, &name, phone.as_deref(), email.as_deref(), &notes)
+}
+
+#[tauri::command]
+fn update_customer_cmd(
+    app: tauri::AppHandle,
+    id: i64,
+    name: String,
+    phone: Option<String>,
+    email: Option<String>,
+    notes: String,
+) -> Result<(), AppError> {
+    let conn = init_db(&app)?;
+    update_customer(&conn, id, &name, phone.as_deref(), email.as_deref(), &notes)
+        .map(|_| ())
+}
+
+#[tauri::command]
+fn delete_customer_cmd(app: tauri::AppHandle, id: i64) -> Result<(), AppError> {
+    let conn = init_db(&app)?;
+    delete_customer(&conn, id).map(|_| ())
+}
+
+#[tauri::command]
+fn get_customer_cmd(app: tauri::AppHandle, id: i64) -> Result<Option<Customer>, AppError> {
+    let conn = init_db(&app)?;
+    get_customer(&conn, id)
+}
+
+#[tauri::command]
+fn search_customers_cmd(app: tauri::AppHandle, query: String) -> Result<Vec<Customer>, AppError> {
+    let conn = init_db(&app)?;
+    search_customers(&conn, &query)
+}
+
+#[tauri::command]
+fn set_sale_customer_cmd(app: tauri::AppHandle, sale_id: i64, customer_id: Option<i64>) -> Result<bool, AppError> {
+    let conn = init_db(&app)?;
+    set_sale_customer(&conn, sale_id, customer_id)
+}
+
+#[tauri::command]
+fn get_customer_sales_cmd(app: tauri::AppHandle, customer_id: i64) -> Result<Vec<CustomerSale>, AppError> {
+    let conn = init_db(&app)?;
+    get_customer_sales(&conn, customer_id)
+}
+
+#[tauri::command]
+fn get_loyalty_ledger_cmd(app: tauri::AppHandle, customer_id: i64) -> Result<Vec<LoyaltyEntry>, AppError> {
+    let conn = init_db(&app)?;
+    get_loyalty_ledger(&conn, customer_id)
+}
+
+#[tauri::command]
+fn adjust_points_cmd(app: tauri::AppHandle, customer_id: i64, points: i64, note: String) -> Result<(), AppError> {
+    let conn = init_db(&app)?;
+    adjust_points(&conn, customer_id, points, &note)
+}
+
+#[tauri::command]
+fn redeem_points_cmd(app: tauri::AppHandle, sale_id: i64, points: i64, as_tender: bool) -> Result<bool, AppError> {
+    let conn = init_db(&app)?;
+    redeem_points(&conn, sale_id, points, as_tender)
+}
+
+#[tauri::command]
+fn get_top_customers_cmd(
+    app: tauri::AppHandle,
+    start_date: Option<String>,
+    end_date: Option<String>,
+    limit: Option<i64>,
+    session: Option<String>,
+    override_id: Option<i64>,
+) -> Result<Vec<TopCustomer>, AppError> {
+    let conn = init_db(&app)?;
+    require_permission(&conn, session.as_deref(), "view_reports", override_id)?;
+    get_top_customers(&conn, start_date.as_deref(), end_date.as_deref(), limit.unwrap_or(10))
+}
+
+
+// ---------------- GIFT CARD COMMANDS ----------------
+#[tauri::command]
+fn sell_gift_card_cmd(
+    app: tauri::AppHandle,
+    sale_id: i64,
+    amount: f64,
+    expires_at: Option<String>,
+) -> Result<String, AppError> {
+    let conn = init_db(&app)?;
+    sell_gift_card(&conn, sale_id, amount, expires_at.as_deref())
+}
+
+#[tauri::command]
+fn get_gift_card_cmd(app: tauri::AppHandle, code: String) -> Result<Option<GiftCard>, AppError> {
+    let conn = init_db(&app)?;
+    get_gift_card(&conn, &code)
+}
+
+#[tauri::command]
+fn get_gift_card_ledger_cmd(app: tauri::AppHandle, card_id: i64) -> Result<Vec<GiftCardEntry>, AppError> {
+    let conn = init_db(&app)?;
+    get_gift_card_ledger(&conn, card_id)
+}
+
+#[tauri::command]
+fn redeem_gift_card_cmd(app: tauri::AppHandle, sale_id: i64, code: String, amount: f64) -> Result<bool, AppError> {
+    let conn = init_db(&app)?;
+    redeem_gift_card(&conn, sale_id, &code, amount)
+}
+
+#[tauri::command]
+fn issue_store_credit_cmd(
+    app: tauri::AppHandle,
+    refund_id: i64,
+    expires_at: Option<String>,
+) -> Result<String, AppError> {
+    let conn = init_db(&app)?;
+    issue_store_credit(&conn, refund_id, expires_at.as_deref())
+}
+
+#[tauri::command]
+fn get_gift_card_liability_cmd(
+    app: tauri::AppHandle,
+    session: Option<String>,
+    override_id: Option<i64>,
+) -> Result<GiftCardLiability, AppError> {
+    let conn = init_db(&app)?;
+    require_permission(&conn, session.as_deref(), "view_reports", override_id)?;
+    get_gift_card_liability(&conn)
 }
 
 
@@ -187,13 +1125,320 @@ fn get_report_cmd(
     app: tauri::AppHandle,
     start_date: Option<String>,
     end_date: Option<String>,
-) -> Result<SalesReport, String> {
-    let conn = establish_connection(&app).map_err(|e| e.to_string())?;
+    session: Option<String>,
+    override_id: Option<i64>,
+) -> Result<SalesReport, AppError> {
+    let conn = establish_connection(&app)?;
+    require_permission(&conn, session.as_deref(), "view_reports", override_id)?;
 
     let start = start_date.as_deref();
     let end = end_date.as_deref();
 
-    get_report(&conn, start, end).map_err(|e| e.to_string())
+    get_report(&conn, start, end)
+}
+
+// Sales per product, rolled up to parent products unless `by_variant`
+#[tauri::command]
+fn get_product_sales_cmd(
+    app: tauri::AppHandle,
+    start_date: Option<String>,
+    end_date: Option<String>,
+    by_variant: bool,
+    session: Option<String>,
+    override_id: Option<i64>,
+) -> Result<Vec<ProductSales>, AppError> {
+    let conn = init_db(&app)?;
+    require_permission(&conn, session.as_deref(), "view_reports", override_id)?;
+    get_product_sales(&conn, start_date.as_deref(), end_date.as_deref(), by_variant)
+}
+
+// Price changes that took effect in the period
+#[tauri::command]
+fn get_price_changes_cmd(
+    app: tauri::AppHandle,
+    start_date: Option<String>,
+    end_date: Option<String>,
+    session: Option<String>,
+    override_id: Option<i64>,
+) -> Result<Vec<PriceChange>, AppError> {
+    let conn = init_db(&app)?;
+    require_permission(&conn, session.as_deref(), "view_reports", override_id)?;
+    get_price_changes(&conn, start_date.as_deref(), end_date.as_deref())
+}
+
+// Sales per product with bundles split into their components
+#[tauri::command]
+fn get_component_sales_cmd(
+    app: tauri::AppHandle,
+    start_date: Option<String>,
+    end_date: Option<String>,
+    session: Option<String>,
+    override_id: Option<i64>,
+) -> Result<Vec<ProductSales>, AppError> {
+    let conn = init_db(&app)?;
+    require_permission(&conn, session.as_deref(), "view_reports", override_id)?;
+    get_component_sales(&conn, start_date.as_deref(), end_date.as_deref())
+}
+
+// Report commands take the caller's session: the admin, or an employee
+// with view_reports (or an override for it).
+#[tauri::command]
+fn get_x_report_cmd(
+    app: tauri::AppHandle,
+    session: Option<String>,
+    override_id: Option<i64>,
+) -> Result<ShiftReport, AppError> {
+    let conn = init_db(&app)?;
+    require_permission(&conn, session.as_deref(), "view_reports", override_id)?;
+    get_x_report(&conn)
+}
+
+#[tauri::command]
+fn issue_z_report_cmd(
+    app: tauri::AppHandle,
+    session: Option<String>,
+    terminal: Option<String>,
+    override_id: Option<i64>,
+) -> Result<ShiftReport, AppError> {
+    let conn = init_db(&app)?;
+    let actor = require_permission(&conn, session.as_deref(), "view_reports", override_id)?;
+    let terminal = resolve_terminal(&conn, terminal.as_deref())?;
+    issue_z_report(&conn, actor.employee_id(), &terminal, chrono::Local::now().date_naive())
+}
+
+#[tauri::command]
+fn get_z_report_cmd(
+    app: tauri::AppHandle,
+    z_number: i64,
+    session: Option<String>,
+    override_id: Option<i64>,
+) -> Result<Option<ShiftReport>, AppError> {
+    let conn = init_db(&app)?;
+    require_permission(&conn, session.as_deref(), "view_reports", override_id)?;
+    get_z_report(&conn, z_number)
+}
+
+#[tauri::command]
+fn get_z_reports_cmd(
+    app: tauri::AppHandle,
+    session: Option<String>,
+    override_id: Option<i64>,
+) -> Result<Vec<ZReportSummary>, AppError> {
+    let conn = init_db(&app)?;
+    require_permission(&conn, session.as_deref(), "view_reports", override_id)?;
+    get_z_reports(&conn)
+}
+
+
+#[tauri::command]
+fn get_employee_performance_cmd(
+    app: tauri::AppHandle,
+    start_date: Option<String>,
+    end_date: Option<String>,
+    session: Option<String>,
+    override_id: Option<i64>,
+) -> Result<Vec<EmployeePerformance>, AppError> {
+    let conn = init_db(&app)?;
+    require_permission(&conn, session.as_deref(), "view_reports", override_id)?;
+    get_employee_performance(&conn, start_date.as_deref(), end_date.as_deref())
+}
+
+#[tauri::command]
+fn get_tip_payouts_cmd(
+    app: tauri::AppHandle,
+    start_date: Option<String>,
+    end_date: Option<String>,
+    session: Option<String>,
+    override_id: Option<i64>,
+) -> Result<TipPayoutReport, AppError> {
+    let conn = init_db(&app)?;
+    require_permission(&conn, session.as_deref(), "view_reports", override_id)?;
+    get_tip_payouts(&conn, start_date.as_deref(), end_date.as_deref())
+}
+
+
+// ---------------- RECEIPT COMMANDS ----------------
+#[tauri::command]
+fn preview_receipt_cmd(app: tauri::AppHandle, sale_id: i64, language: Option<String>) -> Result<String, AppError> {
+    let conn = init_db(&app)?;
+    let (blocks, options) =
+        render_receipt(&conn, sale_id, "receipt", language.as_deref())?;
+    Ok(render_text(&blocks, &options))
+}
+
+#[tauri::command]
+fn print_receipt_cmd(app: tauri::AppHandle, sale_id: i64, language: Option<String>) -> Result<(), AppError> {
+    let conn = init_db(&app)?;
+    let target = get_setting(&conn, "printer_target")?
+        .ok_or_else(|| AppError::validation("printer_target", "required", "No receipt printer configured"))?;
+    let (blocks, options) =
+        render_receipt(&conn, sale_id, "receipt", language.as_deref())?;
+
+    let bytes = escpos::encode(&blocks, &options);
+    Ok(send(&PrinterTarget::parse(&target), &bytes)?)
+}
+
+// Kitchen tickets go to `kitchen_printer_target`, or the receipt printer if unset
+#[tauri::command]
+fn print_kitchen_ticket_cmd(app: tauri::AppHandle, sale_id: i64) -> Result<(), AppError> {
+    let conn = init_db(&app)?;
+    let target = match get_setting(&conn, "kitchen_printer_target")? {
+        Some(target) => target,
+        None => get_setting(&conn, "printer_target")?
+            .ok_or_else(|| AppError::validation("printer_target", "required", "No kitchen printer configured"))?,
+    };
+    let (blocks, options) = render_receipt(&conn, sale_id, "kitchen", None)?;
+
+    let bytes = escpos::encode(&blocks, &options);
+    Ok(send(&PrinterTarget::parse(&target), &bytes)?)
+}
+
+// Reprint a past sale, marked as a copy
+#[tauri::command]
+fn reprint_receipt_cmd(app: tauri::AppHandle, sale_id: i64, language: Option<String>) -> Result<(), AppError> {
+    let conn = init_db(&app)?;
+    let target = get_setting(&conn, "printer_target")?
+        .ok_or_else(|| AppError::validation("printer_target", "required", "No receipt printer configured"))?;
+    let (blocks, options) = render_copy(&conn, sale_id, language.as_deref())?;
+
+    let bytes = escpos::encode(&blocks, &options);
+    Ok(send(&PrinterTarget::parse(&target), &bytes)?)
+}
+
+// Save a copy of a past sale as "pdf" or "html" to `path`. The PDF fonts have
+// no Arabic, so right-to-left templates can only be saved as HTML.
+#[tauri::command]
+fn export_receipt_cmd(
+    app: tauri::AppHandle,
+    sale_id: i64,
+    format: String,
+    path: String,
+    language: Option<String>,
+) -> Result<(), AppError> {
+    let conn = init_db(&app)?;
+    let (blocks, options) = render_copy(&conn, sale_id, language.as_deref())?;
+
+    let bytes = match format.as_str() {
+        "pdf" if options.rtl => {
+            return Err(AppError::validation(
+                "format",
+                "rtl_unsupported",
+                "PDF receipts cannot show right-to-left text, export as HTML instead",
+            ))
+        }
+        "pdf" => export::render_pdf(&blocks, &options),
+        "html" => {
+            let number = load_receipt(&conn, sale_id)?.number;
+            export::render_html(&blocks, &options, &number).into_bytes()
+        }
+        _ => return Err(AppError::validation("format", "unsupported", format!("Unsupported receipt format: {}", format))),
+    };
+    Ok(std::fs::write(&path, bytes)?)
+}
+
+#[tauri::command]
+fn get_receipt_templates_cmd(app: tauri::AppHandle) -> Result<Vec<ReceiptTemplate>, AppError> {
+    let conn = init_db(&app)?;
+    all_templates(&conn)
+}
+
+#[tauri::command]
+fn save_receipt_template_cmd(app: tauri::AppHandle, template: ReceiptTemplate) -> Result<(), AppError> {
+    let conn = init_db(&app)?;
+    save_receipt_template(&conn, &template)
+}
+
+#[tauri::command]
+fn reset_receipt_template_cmd(app: tauri::AppHandle, kind: String, language: String) -> Result<ReceiptTemplate, AppError> {
+    let conn = init_db(&app)?;
+    delete_receipt_template(&conn, &kind, &language)?;
+    Ok(default_template(&kind, &language))
+}
+
+// Preview an unsaved template against a sale (the latest completed one by default)
+#[tauri::command]
+fn preview_template_cmd(
+    app: tauri::AppHandle,
+    template: ReceiptTemplate,
+    sale_id: Option<i64>,
+) -> Result<String, AppError> {
+    let conn = init_db(&app)?;
+    let sale_id = match sale_id {
+        Some(id) => id,
+        None => conn
+            .query_row("SELECT MAX(id) FROM sales WHERE receipt_number IS NOT NULL", [], |row| {
+                row.get::<_, Option<i64>>(0)
+            })?
+            .ok_or_else(|| AppError::not_found("sale"))?,
+    };
+    let receipt = load_receipt(&conn, sale_id)?;
+    let mut options = ReceiptOptions::from_settings(&conn)?;
+    options.rtl = template.rtl;
+
+    Ok(render_text(&render(&template, &receipt, &options), &options))
+}
+
+// ---------------- SETTINGS COMMANDS ----------------
+#[tauri::command]
+fn get_settings_cmd(app: tauri::AppHandle) -> Result<Vec<Setting>, AppError> {
+    let conn = init_db(&app)?;
+    get_settings(&conn)
+}
+
+#[tauri::command]
+fn set_setting_cmd(app: tauri::AppHandle, key: String, value: String) -> Result<(), AppError> {
+    let conn = init_db(&app)?;
+    set_setting(&conn, &key, &value)
+}
+
+#[tauri::command]
+fn get_service_charge_rules_cmd(app: tauri::AppHandle) -> Result<Vec<ServiceChargeRule>, AppError> {
+    let conn = init_db(&app)?;
+    get_service_charge_rules(&conn)
+}
+
+#[tauri::command]
+fn add_service_charge_rule_cmd(
+    app: tauri::AppHandle,
+    name: String,
+    min_guests: i64,
+    percent: f64,
+) -> Result<i64, AppError> {
+    let conn = init_db(&app)?;
+    add_service_charge_rule(&conn, &name, min_guests, percent)
+}
+
+#[tauri::command]
+fn set_service_charge_rule_active_cmd(app: tauri::AppHandle, id: i64, active: bool) -> Result<(), AppError> {
+    let conn = init_db(&app)?;
+    set_service_charge_rule_active(&conn, id, active)?;
+    Ok(())
+}
+
+#[tauri::command]
+fn delete_service_charge_rule_cmd(app: tauri::AppHandle, id: i64) -> Result<(), AppError> {
+    let conn = init_db(&app)?;
+    delete_service_charge_rule(&conn, id)?;
+    Ok(())
+}
+
+#[tauri::command]
+fn get_document_sequences_cmd(app: tauri::AppHandle) -> Result<Vec<DocumentSequence>, AppError> {
+    let conn = init_db(&app)?;
+    get_document_sequences(&conn)
+}
+
+#[tauri::command]
+fn set_document_sequence_cmd(
+    app: tauri::AppHandle,
+    terminal: String,
+    doc_type: String,
+    prefix: String,
+    reset_period: String,
+    padding: i32,
+) -> Result<(), AppError> {
+    let conn = init_db(&app)?;
+    set_document_sequence(&conn, &terminal, &doc_type, &prefix, &reset_period, padding)
 }
 
 
@@ -209,26 +1454,150 @@ pub fn run() {
             verify_old_password_cmd,
             reset_admin,
             get_admin_name,
+            get_lockouts_cmd,
+            unlock_login_cmd,
+            logout_cmd,
+            get_login_failures_cmd,
             // Employee
             setup_employee_table,
             add_new_employee,
             fetch_employees,
             update_employee_cmd,
             login_employee_cmd,
+            set_employee_pin_cmd,
+            clear_employee_pin_cmd,
+            pin_login_cmd,
             delete_employee_cmd,
+            // Roles
+            get_permission_catalog_cmd,
+            get_roles_cmd,
+            save_role_cmd,
+            delete_role_cmd,
+            set_employee_role_cmd,
+            get_employee_permissions_cmd,
+            manager_override_cmd,
+            get_overrides_cmd,
+            // Time clock
+            clock_in_cmd,
+            clock_out_cmd,
+            start_break_cmd,
+            end_break_cmd,
+            get_clock_status_cmd,
+            get_time_entries_cmd,
+            update_time_entry_cmd,
+            add_shift_cmd,
+            delete_shift_cmd,
+            get_shifts_cmd,
+            get_timesheets_cmd,
+            export_timesheets_cmd,
             // Product
             setup_product_table,
             add_product_cmd,
             get_products_cmd,
             update_product_cmd,
             delete_product_cmd,
+            archive_product_cmd,
+            restore_product_cmd,
+            generate_barcode_cmd,
+            lookup_barcode_cmd,
+            get_product_barcodes_cmd,
+            add_product_barcode_cmd,
+            delete_product_barcode_cmd,
+            set_product_net_content_cmd,
+            get_price_changed_products_cmd,
+            get_price_history_cmd,
+            schedule_price_cmd,
+            cancel_scheduled_price_cmd,
+            get_price_at_cmd,
+            read_scale_cmd,
+            // Variants & stock
+            add_variant_cmd,
+            get_variants_cmd,
+            set_stock_tracking_cmd,
+            adjust_stock_cmd,
+            get_stock_levels_cmd,
+            get_stock_movements_cmd,
+            // Availability
+            get_availability_rules_cmd,
+            add_availability_rule_cmd,
+            delete_availability_rule_cmd,
+            set_product_category_cmd,
+            set_sold_out_cmd,
+            // Bundles
+            set_bundle_cmd,
+            get_bundle_cmd,
+            // Labels
+            print_labels_cmd,
+            export_labels_cmd,
             // sale
             start_sale_cmd,
             add_sale_item_cmd,
             finalize_sale_cmd,
             get_all_sales_cmd,
+            apply_discount_cmd,
+            void_sale_cmd,
+            refund_sale_cmd,
+            add_payment_cmd,
+            get_sale_payments_cmd,
+            add_tip_cmd,
+            set_sale_guests_cmd,
+            // Cash drawer
+            open_drawer_cmd,
+            open_drawer_no_sale_cmd,
+            get_open_drawer_cmd,
+            drawer_movement_cmd,
+            get_drawer_movements_cmd,
+            close_drawer_cmd,
+            get_drawer_sessions_cmd,
+            // Customers
+            add_customer_cmd,
+            update_customer_cmd,
+            delete_customer_cmd,
+            get_customer_cmd,
+            search_customers_cmd,
+            set_sale_customer_cmd,
+            get_customer_sales_cmd,
+            get_loyalty_ledger_cmd,
+            adjust_points_cmd,
+            redeem_points_cmd,
+            get_top_customers_cmd,
+            // Gift cards
+            sell_gift_card_cmd,
+            get_gift_card_cmd,
+            get_gift_card_ledger_cmd,
+            redeem_gift_card_cmd,
+            issue_store_credit_cmd,
+            get_gift_card_liability_cmd,
             // Reports
             get_report_cmd,
+            get_product_sales_cmd,
+            get_component_sales_cmd,
+            get_price_changes_cmd,
+            get_x_report_cmd,
+            issue_z_report_cmd,
+            get_z_report_cmd,
+            get_z_reports_cmd,
+            get_employee_performance_cmd,
+            get_tip_payouts_cmd,
+            // Receipts
+            preview_receipt_cmd,
+            print_receipt_cmd,
+            print_kitchen_ticket_cmd,
+            reprint_receipt_cmd,
+            export_receipt_cmd,
+            get_receipt_templates_cmd,
+            save_receipt_template_cmd,
+            reset_receipt_template_cmd,
+            preview_template_cmd,
+            // Settings
+            get_settings_cmd,
+            set_setting_cmd,
+            get_document_sequences_cmd,
+            set_document_sequence_cmd,
+            get_service_charge_rules_cmd,
+            add_service_charge_rule_cmd,
+            set_service_charge_rule_active_cmd,
+            delete_service_charge_rule_cmd,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -1,6 +1,18 @@
-use rusqlite::{params, Connection, Result};
+use rusqlite::{params, Connection};
+use serde::Serialize;
 
+use crate::error::Result;
+use crate::models::lockout::{check_login, login_failed, login_succeeded};
+use crate::validation;
 
+#[derive(Debug, Serialize)]
+pub struct AdminLogin {
+    pub success: bool,
+    // Seconds before another attempt is accepted (0 if not locked)
+    pub locked_for: i64,
+    // Token for guarded commands, set by `login_admin` on success
+    pub session: Option<String>,
+}
 
 pub fn create_admin_table(conn: &Connection) -> Result<()> {
     conn.execute(
@@ -15,6 +27,8 @@ pub fn create_admin_table(conn: &Connection) -> Result<()> {
 }
 
 pub fn add_admin(conn: &Connection, name: &str, password: &str) -> Result<()> {
+    let name = validation::name("name", name)?;
+    let password = validation::password("password", password)?;
     conn.execute(
         "INSERT INTO admins (name, password) VALUES (?1, ?2)",
         params![name, password],
@@ -28,20 +42,47 @@ pub fn validate_admin(conn: &Connection, name: &str, password: &str) -> Result<b
     Ok(count > 0)
 }
 
-pub fn verify_admin_password(conn: &Connection, old_password: &str) -> Result<bool> {
-    let mut stmt = conn.prepare("SELECT password FROM admins WHERE id = 1")?;
-    let stored_password: String = stmt.query_row([], |row| row.get(0))?;
-    Ok(stored_password == old_password)
+// Admin login, with failed attempts counted per admin name and per terminal
+// (see lockout.rs)
+pub fn authenticate_admin(conn: &Connection, name: &str, password: &str, terminal: &str) -> Result<AdminLogin> {
+    let locked = check_login(conn, "admin", Some(name), terminal)?;
+    if locked > 0 {
+        return Ok(AdminLogin { success: false, locked_for: locked, session: None });
+    }
+
+    if validate_admin(conn, name, password)? {
+        login_succeeded(conn, "admin", Some(name), terminal)?;
+        Ok(AdminLogin { success: true, locked_for: 0, session: None })
+    } else {
+        let locked_for = login_failed(conn, "admin", Some(name), terminal)?;
+        Ok(AdminLogin { success: false, locked_for, session: None })
+    }
+}
+
+// Check the current admin password before changing the account. Counted
+// like a login, so it cannot be used to guess the password.
+pub fn verify_admin_password(conn: &Connection, old_password: &str, terminal: &str) -> Result<AdminLogin> {
+    let name: String = conn.query_row("SELECT name FROM admins WHERE id = 1", [], |row| row.get(0))?;
+    authenticate_admin(conn, &name, old_password, terminal)
 }
 
-pub fn update_admin(conn: &Connection, old_password: &str, new_name: &str, new_password: &str) -> Result<bool> {
-    if !verify_admin_password(conn, old_password)? {
-        return Ok(false);
+pub fn update_admin(
+    conn: &Connection,
+    old_password: &str,
+    new_name: &str,
+    new_password: &str,
+    terminal: &str,
+) -> Result<AdminLogin> {
+    let new_name = validation::name("name", new_name)?;
+    let new_password = validation::password("password", new_password)?;
+    let login = verify_admin_password(conn, old_password, terminal)?;
+    if !login.success {
+        return Ok(login);
     }
 
     conn.execute(
         "UPDATE admins SET name = ?1, password = ?2 WHERE id = 1",
         params![new_name, new_password],
     )?;
-    Ok(true)
+    Ok(login)
 }
\ No newline at end of file
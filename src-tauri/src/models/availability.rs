@@ -0,0 +1,214 @@
+use chrono::{Datelike, NaiveDate, NaiveDateTime, NaiveTime};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{AppError, Result};
+use crate::models::products::{product_from_row, Product, PRODUCT_COLUMNS};
+use crate::validation;
+
+// When a product may be sold. A rule belongs to a product (and applies to
+// its variants) or to a category. A product with no rules of its own or of
+// its category is always sellable; otherwise it is sellable while any rule
+// matches, in local time. Independently of the rules, a product can be
+// "86'd" (sold out) for the rest of the day.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AvailabilityRule {
+    #[serde(default)]
+    pub id: i64,
+    pub product_id: Option<i32>,
+    pub category: Option<String>,
+    // ISO weekdays the rule covers, Monday = 1 ... Sunday = 7, e.g. "12345"
+    pub days: String,
+    // "HH:MM"; an end before the start runs past midnight
+    pub start_time: Option<String>,
+    pub end_time: Option<String>,
+    // "YYYY-MM-DD", both inclusive, e.g. a seasonal menu
+    pub start_date: Option<String>,
+    pub end_date: Option<String>,
+}
+
+impl AvailabilityRule {
+    pub fn matches(&self, now: NaiveDateTime) -> bool {
+        let date = now.date().format("%Y-%m-%d").to_string();
+        if self.start_date.as_ref().is_some_and(|start| date < *start)
+            || self.end_date.as_ref().is_some_and(|end| date > *end)
+        {
+            return false;
+        }
+
+        let time = now.time().format("%H:%M").to_string();
+        let weekday = now.weekday().number_from_monday();
+        match (&self.start_time, &self.end_time) {
+            (Some(start), Some(end)) if start > end => {
+                // Past midnight the window belongs to the day it opened
+                let yesterday = if weekday == 1 { 7 } else { weekday - 1 };
+                (time >= *start && self.covers(weekday)) || (time < *end && self.covers(yesterday))
+            }
+            (Some(start), Some(end)) => time >= *start && time < *end && self.covers(weekday),
+            _ => self.covers(weekday),
+        }
+    }
+
+    fn covers(&self, weekday: u32) -> bool {
+        self.days.contains(char::from_digit(weekday, 10).unwrap_or('0'))
+    }
+}
+
+fn time_of_day(field: &str, value: Option<&str>) -> Result<Option<String>> {
+    let Some(value) = value.map(str::trim).filter(|v| !v.is_empty()) else {
+        return Ok(None);
+    };
+    NaiveTime::parse_from_str(value, "%H:%M")
+        .map(|t| Some(t.format("%H:%M").to_string()))
+        .map_err(|_| AppError::validation(field, "invalid_format", format!("{} must be HH:MM", field)))
+}
+
+fn day(field: &str, value: Option<&str>) -> Result<Option<String>> {
+    let Some(value) = value.map(str::trim).filter(|v| !v.is_empty()) else {
+        return Ok(None);
+    };
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map(|d| Some(d.format("%Y-%m-%d").to_string()))
+        .map_err(|_| AppError::validation(field, "invalid_format", format!("{} must be YYYY-MM-DD", field)))
+}
+
+pub fn add_availability_rule(conn: &Connection, rule: &AvailabilityRule) -> Result<i64> {
+    let category = match rule.category.as_deref() {
+        Some(category) => Some(validation::name("category", category)?),
+        None => None,
+    };
+    if rule.product_id.is_some() == category.is_some() {
+        return Err(AppError::validation("product", "required", "a rule is for either a product or a category"));
+    }
+    let mut days: Vec<char> = rule.days.chars().filter(|c| !c.is_whitespace()).collect();
+    days.sort_unstable();
+    days.dedup();
+    if days.is_empty() || days.iter().any(|c| !('1'..='7').contains(c)) {
+        return Err(AppError::validation("days", "invalid_format", "days must be weekday numbers 1-7"));
+    }
+    let start_time = time_of_day("start_time", rule.start_time.as_deref())?;
+    let end_time = time_of_day("end_time", rule.end_time.as_deref())?;
+    if start_time.is_some() != end_time.is_some() || (start_time.is_some() && start_time == end_time) {
+        return Err(AppError::validation("end_time", "invalid_format", "give both a start and a different end time"));
+    }
+    let start_date = day("start_date", rule.start_date.as_deref())?;
+    let end_date = day("end_date", rule.end_date.as_deref())?;
+    if let (Some(start), Some(end)) = (&start_date, &end_date) {
+        if start > end {
+            return Err(AppError::validation("end_date", "out_of_range", "the end date is before the start date"));
+        }
+    }
+
+    conn.execute(
+        "INSERT INTO availability_rules (product_id, category, days, start_time, end_time, start_date, end_date)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![
+            rule.product_id,
+            category,
+            days.into_iter().collect::<String>(),
+            start_time,
+            end_time,
+            start_date,
+            end_date
+        ],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+pub fn delete_availability_rule(conn: &Connection, id: i64) -> Result<usize> {
+    Ok(conn.execute("DELETE FROM availability_rules WHERE id = ?1", params![id])?)
+}
+
+fn rule_from_row(row: &rusqlite::Row) -> rusqlite::Result<AvailabilityRule> {
+    Ok(AvailabilityRule {
+        id: row.get(0)?,
+        product_id: row.get(1)?,
+        category: row.get(2)?,
+        days: row.get(3)?,
+        start_time: row.get(4)?,
+        end_time: row.get(5)?,
+        start_date: row.get(6)?,
+        end_date: row.get(7)?,
+    })
+}
+
+pub fn get_availability_rules(conn: &Connection) -> Result<Vec<AvailabilityRule>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, product_id, category, days, start_time, end_time, start_date, end_date
+        FROM availability_rules ORDER BY category, product_id, id",
+    )?;
+    let rows = stmt.query_map([], rule_from_row)?;
+
+    let mut rules = Vec::new();
+    for rule in rows {
+        rules.push(rule?);
+    }
+    Ok(rules)
+}
+
+// Rules that govern a product: its own, its parent's and its category's
+fn applies(rule: &AvailabilityRule, product: &Product) -> bool {
+    match (&rule.product_id, &rule.category) {
+        (Some(id), _) => *id == product.id || Some(*id) == product.parent_id,
+        (None, Some(category)) => product.category.as_ref() == Some(category),
+        (None, None) => false,
+    }
+}
+
+// Set `sold_out` and `available` on products read for selling
+pub fn mark_available(conn: &Connection, products: &mut [Product], now: NaiveDateTime) -> Result<()> {
+    let rules = get_availability_rules(conn)?;
+    let today = now.date().format("%Y-%m-%d").to_string();
+    for product in products.iter_mut() {
+        let mut governing = rules.iter().filter(|rule| applies(rule, product)).peekable();
+        let in_window = governing.peek().is_none() || governing.any(|rule| rule.matches(now));
+        product.sold_out = product.sold_out_on.as_ref() == Some(&today);
+        product.available = !product.archived && !product.sold_out && in_window;
+    }
+    Ok(())
+}
+
+// Refuse to sell a product outside its availability, while it is 86'd or
+// once it is archived
+pub fn check_available(conn: &Connection, product_id: i32, now: NaiveDateTime) -> Result<()> {
+    let product = conn
+        .query_row(
+            &format!("SELECT {} FROM products p WHERE p.id = ?1", PRODUCT_COLUMNS),
+            params![product_id],
+            product_from_row,
+        )
+        .optional()?
+        .ok_or_else(|| AppError::not_found("product"))?;
+    let mut products = [product];
+    mark_available(conn, &mut products, now)?;
+    let [product] = products;
+    if !product.available {
+        return Err(AppError::Conflict {
+            field: Some("product".to_string()),
+            code: "unavailable".to_string(),
+            message: format!("{} is not available now", product.name),
+        });
+    }
+    Ok(())
+}
+
+// 86 a product (and its variants) for today, or put it back on sale
+pub fn set_sold_out(conn: &Connection, product_id: i32, sold_out: bool, today: NaiveDate) -> Result<usize> {
+    let day = sold_out.then(|| today.format("%Y-%m-%d").to_string());
+    Ok(conn.execute(
+        "UPDATE products SET sold_out_on = ?1 WHERE id = ?2 OR parent_id = ?2",
+        params![day, product_id],
+    )?)
+}
+
+// Category used by availability rules; variants follow their parent
+pub fn set_product_category(conn: &Connection, product_id: i32, category: Option<&str>) -> Result<usize> {
+    let category = match category.map(str::trim).filter(|c| !c.is_empty()) {
+        Some(category) => Some(validation::name("category", category)?),
+        None => None,
+    };
+    Ok(conn.execute(
+        "UPDATE products SET category = ?1 WHERE id = ?2 OR parent_id = ?2",
+        params![category, product_id],
+    )?)
+}
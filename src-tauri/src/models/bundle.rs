@@ -0,0 +1,226 @@
+use chrono::Local;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{AppError, Result};
+use crate::models::availability::check_available;
+use crate::unit::Unit;
+use crate::validation;
+
+// A bundle (meal deal) is a product sold at its own price and made of
+// groups of components. A group with one option is a fixed component; a
+// group with several is a choice, e.g. "Side": fries or salad. When a bundle
+// is sold, the chosen components are stored with the sale line and its price
+// is split between them in proportion to their own prices.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleOption {
+    pub product_id: i32,
+    // Quantity of the component in one bundle, in the component's unit
+    pub quantity: f64,
+    // Filled in when read back
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub price: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleGroup {
+    pub name: String,
+    pub options: Vec<BundleOption>,
+}
+
+// Component of a sold bundle; `price` is its share of one bundle's price
+#[derive(Debug, Serialize)]
+pub struct SaleComponent {
+    pub product_id: Option<i32>,
+    pub name: String,
+    pub quantity: f64,
+    pub unit: String,
+    pub price: f64,
+}
+
+fn is_bundle(conn: &Connection, product_id: i32) -> Result<bool> {
+    Ok(conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM bundle_groups WHERE bundle_id = ?1)",
+        params![product_id],
+        |row| row.get(0),
+    )?)
+}
+
+// Replace the groups of a bundle; no groups turns it back into a plain
+// product. Bundles are sold by the piece and cannot contain other bundles.
+pub fn set_bundle(conn: &Connection, bundle_id: i32, groups: &[BundleGroup]) -> Result<()> {
+    let unit: String = conn
+        .query_row("SELECT unit FROM products WHERE id = ?1", params![bundle_id], |row| row.get(0))
+        .optional()?
+        .ok_or_else(|| AppError::not_found("product"))?;
+    if !groups.is_empty() {
+        if unit != Unit::Each.as_str() {
+            return Err(AppError::validation("unit", "unsupported", "bundles are sold by the piece"));
+        }
+        let in_bundle: bool = conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM bundle_options WHERE product_id = ?1)",
+            params![bundle_id],
+            |row| row.get(0),
+        )?;
+        if in_bundle {
+            return Err(AppError::validation("options", "invalid_format", "a bundle component cannot be a bundle"));
+        }
+    }
+
+    let tx = conn.unchecked_transaction()?;
+    tx.execute("DELETE FROM bundle_groups WHERE bundle_id = ?1", params![bundle_id])?;
+    for (position, group) in groups.iter().enumerate() {
+        let name = validation::name("name", &group.name)?;
+        if group.options.is_empty() {
+            return Err(AppError::validation("options", "required", format!("{} has no options", name)));
+        }
+        tx.execute(
+            "INSERT INTO bundle_groups (bundle_id, name, position) VALUES (?1, ?2, ?3)",
+            params![bundle_id, name, position as i64],
+        )?;
+        let group_id = tx.last_insert_rowid();
+
+        for option in &group.options {
+            let unit: String = tx
+                .query_row("SELECT unit FROM products WHERE id = ?1", params![option.product_id], |row| row.get(0))
+                .optional()?
+                .ok_or_else(|| AppError::not_found("product"))?;
+            if option.product_id == bundle_id || is_bundle(&tx, option.product_id)? {
+                return Err(AppError::validation("options", "invalid_format", "a bundle component cannot be a bundle"));
+            }
+            let quantity = validation::quantity(option.quantity, validation::unit(&unit)?)?;
+            tx.execute(
+                "INSERT INTO bundle_options (group_id, product_id, quantity) VALUES (?1, ?2, ?3)",
+                params![group_id, option.product_id, quantity],
+            )?;
+        }
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+// Groups of a bundle in order; empty for a plain product
+pub fn get_bundle(conn: &Connection, bundle_id: i32) -> Result<Vec<BundleGroup>> {
+    let mut stmt = conn.prepare(
+        "SELECT g.id, g.name, o.product_id, o.quantity, p.name, p.price
+        FROM bundle_groups g
+        JOIN bundle_options o ON o.group_id = g.id
+        JOIN products p ON p.id = o.product_id
+        WHERE g.bundle_id = ?1
+        ORDER BY g.position, o.id",
+    )?;
+    let rows = stmt.query_map(params![bundle_id], |row| {
+        Ok((
+            row.get::<_, i64>(0)?,
+            row.get::<_, String>(1)?,
+            BundleOption { product_id: row.get(2)?, quantity: row.get(3)?, name: row.get(4)?, price: row.get(5)? },
+        ))
+    })?;
+
+    let mut groups: Vec<(i64, BundleGroup)> = Vec::new();
+    for row in rows {
+        let (group_id, name, option) = row?;
+        match groups.last_mut() {
+            Some((id, group)) if *id == group_id => group.options.push(option),
+            _ => groups.push((group_id, BundleGroup { name, options: vec![option] })),
+        }
+    }
+    Ok(groups.into_iter().map(|(_, group)| group).collect())
+}
+
+// Store the components of a bundle sale line: fixed components plus the
+// option picked from each choice group (`choices` are product ids). The
+// line's unit price is split in proportion to the components' own prices,
+// rounded to cents with the remainder on the last component. Every component
+// must be available, like the bundle itself.
+pub fn add_sale_components(conn: &Connection, sale_item_id: i64, choices: &[i32]) -> Result<()> {
+    let (product_id, unit_price): (i32, f64) = conn.query_row(
+        "SELECT product_id, price + extra_amount FROM sale_items WHERE id = ?1",
+        params![sale_item_id],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )?;
+    let groups = get_bundle(conn, product_id)?;
+    if groups.is_empty() {
+        if !choices.is_empty() {
+            return Err(AppError::validation("choices", "invalid_format", "the product is not a bundle"));
+        }
+        return Ok(());
+    }
+
+    let mut picked: Vec<&BundleOption> = Vec::new();
+    for group in &groups {
+        let option = if group.options.len() == 1 {
+            group.options.first()
+        } else {
+            group.options.iter().find(|o| choices.contains(&o.product_id))
+        };
+        match option {
+            Some(option) => picked.push(option),
+            None => {
+                return Err(AppError::validation("choices", "required", format!("pick one option for {}", group.name)))
+            }
+        }
+    }
+    if choices.iter().any(|c| !picked.iter().any(|o| o.product_id == *c)) {
+        return Err(AppError::validation("choices", "invalid_format", "a choice is not an option of the bundle"));
+    }
+    let now = Local::now().naive_local();
+    for option in &picked {
+        check_available(conn, option.product_id, now)?;
+    }
+
+    let weights: Vec<f64> = picked.iter().map(|o| o.price * o.quantity).collect();
+    let total_weight: f64 = weights.iter().sum();
+    let mut remaining = unit_price;
+    for (i, option) in picked.iter().enumerate() {
+        let share = if i + 1 == picked.len() {
+            remaining
+        } else if total_weight > 0.0 {
+            (unit_price * weights[i] / total_weight * 100.0).round() / 100.0
+        } else {
+            (unit_price / picked.len() as f64 * 100.0).round() / 100.0
+        };
+        remaining -= share;
+        conn.execute(
+            "INSERT INTO sale_item_components (sale_item_id, product_id, product_name, quantity, unit, price)
+            SELECT ?1, id, name, ?2, unit, ?3 FROM products WHERE id = ?4",
+            params![sale_item_id, option.quantity, (share * 100.0).round() / 100.0, option.product_id],
+        )?;
+    }
+    Ok(())
+}
+
+// Copy the components of a sale line to another line, e.g. its refund
+pub fn copy_sale_components(conn: &Connection, from_item_id: i64, to_item_id: i64) -> Result<()> {
+    conn.execute(
+        "INSERT INTO sale_item_components (sale_item_id, product_id, product_name, quantity, unit, price)
+        SELECT ?2, product_id, product_name, quantity, unit, price
+        FROM sale_item_components WHERE sale_item_id = ?1 ORDER BY id",
+        params![from_item_id, to_item_id],
+    )?;
+    Ok(())
+}
+
+pub fn get_sale_components(conn: &Connection, sale_item_id: i64) -> Result<Vec<SaleComponent>> {
+    let mut stmt = conn.prepare(
+        "SELECT product_id, product_name, quantity, unit, price
+        FROM sale_item_components WHERE sale_item_id = ?1 ORDER BY id",
+    )?;
+    let rows = stmt.query_map(params![sale_item_id], |row| {
+        Ok(SaleComponent {
+            product_id: row.get(0)?,
+            name: row.get(1)?,
+            quantity: row.get(2)?,
+            unit: row.get(3)?,
+            price: row.get(4)?,
+        })
+    })?;
+
+    let mut components = Vec::new();
+    for component in rows {
+        components.push(component?);
+    }
+    Ok(components)
+}
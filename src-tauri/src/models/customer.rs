@@ -0,0 +1,195 @@
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::Serialize;
+
+use crate::error::Result;
+use crate::validation;
+
+#[derive(Debug, Serialize)]
+pub struct Customer {
+    pub id: i64,
+    pub name: String,
+    pub phone: Option<String>,
+    pub email: Option<String>,
+    pub notes: String,
+    pub points: i64,
+    pub created_at: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CustomerSale {
+    pub sale_id: i64,
+    pub receipt_number: Option<String>,
+    pub total: f64,
+    pub is_refund: bool,
+    pub voided: bool,
+    pub timestamp: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TopCustomer {
+    pub id: i64,
+    pub name: String,
+    pub phone: Option<String>,
+    pub visits: i64,
+    pub total_spent: f64,
+    pub points: i64,
+}
+
+const CUSTOMER_COLUMNS: &str = "c.id, c.name, c.phone, c.email, c.notes,
+    (SELECT IFNULL(SUM(points), 0) FROM loyalty_ledger WHERE customer_id = c.id), c.created_at";
+
+fn customer_from_row(row: &Row) -> rusqlite::Result<Customer> {
+    Ok(Customer {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        phone: row.get(2)?,
+        email: row.get(3)?,
+        notes: row.get(4)?,
+        points: row.get(5)?,
+        created_at: row.get(6)?,
+    })
+}
+
+pub fn add_customer(
+    conn: &Connection,
+    name: &str,
+    phone: Option<&str>,
+    email: Option<&str>,
+    notes: &str,
+) -> Result<i64> {
+    let name = validation::name("name", name)?;
+    let notes = validation::text("notes", notes)?;
+    // Blank contact fields are stored as NULL so they don't clash on the unique phone
+    conn.execute(
+        "INSERT INTO customers (name, phone, email, notes) VALUES (?1, ?2, ?3, ?4)",
+        params![name, validation::phone(phone)?, validation::email(email)?, notes],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+pub fn update_customer(
+    conn: &Connection,
+    id: i64,
+    name: &str,
+    phone: Option<&str>,
+    email: Option<&str>,
+    notes: &str,
+) -> Result<usize> {
+    let name = validation::name("name", name)?;
+    let notes = validation::text("notes", notes)?;
+    Ok(conn.execute(
+        "UPDATE customers SET name = ?1, phone = ?2, email = ?3, notes = ?4 WHERE id = ?5",
+        params![name, validation::phone(phone)?, validation::email(email)?, notes, id],
+    )?)
+}
+
+// Past sales keep their totals but lose the link to the deleted customer
+pub fn delete_customer(conn: &Connection, id: i64) -> Result<usize> {
+    let tx = conn.unchecked_transaction()?;
+    tx.execute("UPDATE sales SET customer_id = NULL WHERE customer_id = ?1", params![id])?;
+    tx.execute("DELETE FROM loyalty_ledger WHERE customer_id = ?1", params![id])?;
+    let deleted = tx.execute("DELETE FROM customers WHERE id = ?1", params![id])?;
+    tx.commit()?;
+    Ok(deleted)
+}
+
+pub fn get_customer(conn: &Connection, id: i64) -> Result<Option<Customer>> {
+    Ok(conn.query_row(
+        &format!("SELECT {} FROM customers c WHERE c.id = ?1", CUSTOMER_COLUMNS),
+        params![id],
+        customer_from_row,
+    )
+    .optional()?)
+}
+
+// Match on name, phone or email; an empty query lists everyone
+pub fn search_customers(conn: &Connection, query: &str) -> Result<Vec<Customer>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM customers c
+        WHERE c.name LIKE ?1 OR IFNULL(c.phone, '') LIKE ?1 OR IFNULL(c.email, '') LIKE ?1
+        ORDER BY c.name",
+        CUSTOMER_COLUMNS
+    ))?;
+    let rows = stmt.query_map(params![format!("%{}%", query.trim())], customer_from_row)?;
+
+    let mut customers = Vec::new();
+    for customer in rows {
+        customers.push(customer?);
+    }
+    Ok(customers)
+}
+
+// Attach (or with None, detach) a customer. Only open sales can change
+// hands, so points already earned stay with the right customer.
+pub fn set_sale_customer(conn: &Connection, sale_id: i64, customer_id: Option<i64>) -> Result<bool> {
+    let updated = conn.execute(
+        "UPDATE sales SET customer_id = ?1 WHERE id = ?2 AND receipt_number IS NULL",
+        params![customer_id, sale_id],
+    )?;
+    Ok(updated > 0)
+}
+
+// Completed sales and refunds for a customer, newest first
+pub fn get_customer_sales(conn: &Connection, customer_id: i64) -> Result<Vec<CustomerSale>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, receipt_number, total, refund_of IS NOT NULL, voided, timestamp
+        FROM sales WHERE customer_id = ?1 AND receipt_number IS NOT NULL
+        ORDER BY id DESC",
+    )?;
+    let rows = stmt.query_map(params![customer_id], |row| {
+        Ok(CustomerSale {
+            sale_id: row.get(0)?,
+            receipt_number: row.get(1)?,
+            total: row.get(2)?,
+            is_refund: row.get(3)?,
+            voided: row.get(4)?,
+            timestamp: row.get(5)?,
+        })
+    })?;
+
+    let mut sales = Vec::new();
+    for sale in rows {
+        sales.push(sale?);
+    }
+    Ok(sales)
+}
+
+// Customers ranked by net spend (refunds subtracted, voids ignored) between two dates
+pub fn get_top_customers(
+    conn: &Connection,
+    start_date: Option<&str>,
+    end_date: Option<&str>,
+    limit: i64,
+) -> Result<Vec<TopCustomer>> {
+    let start = start_date.unwrap_or("1970-01-01");
+    let end = end_date.unwrap_or("9999-12-31");
+
+    let mut stmt = conn.prepare(
+        "SELECT c.id, c.name, c.phone,
+                SUM(s.refund_of IS NULL), SUM(s.total),
+                (SELECT IFNULL(SUM(points), 0) FROM loyalty_ledger WHERE customer_id = c.id)
+        FROM customers c
+        JOIN sales s ON s.customer_id = c.id
+        WHERE s.receipt_number IS NOT NULL AND s.voided = 0
+            AND date(s.timestamp) BETWEEN ?1 AND ?2
+        GROUP BY c.id
+        ORDER BY SUM(s.total) DESC
+        LIMIT ?3",
+    )?;
+    let rows = stmt.query_map(params![start, end, limit], |row| {
+        Ok(TopCustomer {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            phone: row.get(2)?,
+            visits: row.get(3)?,
+            total_spent: row.get(4)?,
+            points: row.get(5)?,
+        })
+    })?;
+
+    let mut customers = Vec::new();
+    for customer in rows {
+        customers.push(customer?);
+    }
+    Ok(customers)
+}
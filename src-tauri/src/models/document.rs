@@ -0,0 +1,179 @@
+use chrono::Local;
+use rusqlite::{params, Connection};
+use serde::Serialize;
+
+use crate::error::{AppError, Result};
+use crate::models::settings::get_setting;
+
+#[derive(Debug, Serialize)]
+pub struct DocumentSequence {
+    pub terminal: String,
+    pub doc_type: String,
+    pub prefix: String,
+    pub reset_period: String,
+    pub padding: i32,
+    pub period: String,
+    pub last_number: i64,
+}
+
+// The terminal a command runs on: the explicit value, the `terminal_id`
+// setting, or "POS1".
+pub fn resolve_terminal(conn: &Connection, terminal: Option<&str>) -> Result<String> {
+    match terminal {
+        Some(terminal) if !terminal.trim().is_empty() => Ok(terminal.trim().to_string()),
+        _ => Ok(get_setting(conn, "terminal_id")?.unwrap_or_else(|| "POS1".to_string())),
+    }
+}
+
+fn default_prefix(terminal: &str, doc_type: &str) -> String {
+    match doc_type {
+        "refund" => format!("{}-R", terminal),
+        "z_report" => format!("{}-Z", terminal),
+        _ => format!("{}-", terminal),
+    }
+}
+
+// `{YYYY}`, `{YY}`, `{MM}` and `{DD}` in a prefix are replaced with today's date
+fn expand_prefix(prefix: &str) -> String {
+    let today = Local::now();
+    prefix
+        .replace("{YYYY}", &today.format("%Y").to_string())
+        .replace("{YY}", &today.format("%y").to_string())
+        .replace("{MM}", &today.format("%m").to_string())
+        .replace("{DD}", &today.format("%d").to_string())
+}
+
+fn current_period(reset_period: &str) -> String {
+    let today = Local::now();
+    match reset_period {
+        "yearly" => today.format("%Y").to_string(),
+        "daily" => today.format("%Y-%m-%d").to_string(),
+        _ => String::new(),
+    }
+}
+
+// Take the next number for a terminal and document type. This must run inside
+// the transaction that completes the document: if that transaction rolls
+// back, the number is released with it, so issued numbers have no gaps.
+pub fn allocate_document_number(conn: &Connection, terminal: &str, doc_type: &str) -> Result<String> {
+    conn.execute(
+        "INSERT OR IGNORE INTO document_sequences (terminal, doc_type, prefix) VALUES (?1, ?2, ?3)",
+        params![terminal, doc_type, default_prefix(terminal, doc_type)],
+    )?;
+
+    let (prefix, reset_period, padding, period, last_number): (String, String, usize, String, i64) = conn
+        .query_row(
+            "SELECT prefix, reset_period, padding, period, last_number
+            FROM document_sequences WHERE terminal = ?1 AND doc_type = ?2",
+            params![terminal, doc_type],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?)),
+        )?;
+
+    let now = current_period(&reset_period);
+    let next = if now == period { last_number + 1 } else { 1 };
+
+    conn.execute(
+        "UPDATE document_sequences SET period = ?1, last_number = ?2 WHERE terminal = ?3 AND doc_type = ?4",
+        params![now, next, terminal, doc_type],
+    )?;
+
+    Ok(format!("{}{:0width$}", expand_prefix(&prefix), next, width = padding))
+}
+
+pub fn get_document_sequences(conn: &Connection) -> Result<Vec<DocumentSequence>> {
+    let mut stmt = conn.prepare(
+        "SELECT terminal, doc_type, prefix, reset_period, padding, period, last_number
+        FROM document_sequences ORDER BY terminal, doc_type",
+    )?;
+    let rows = stmt.query_map([], |row| {
+        Ok(DocumentSequence {
+            terminal: row.get(0)?,
+            doc_type: row.get(1)?,
+            prefix: row.get(2)?,
+            reset_period: row.get(3)?,
+            padding: row.get(4)?,
+            period: row.get(5)?,
+            last_number: row.get(6)?,
+        })
+    })?;
+
+    let mut sequences = Vec::new();
+    for sequence in rows {
+        sequences.push(sequence?);
+    }
+    Ok(sequences)
+}
+
+const MAX_PADDING: i32 = 12;
+
+// Configure prefix and reset rule; the running counter is left untouched.
+// A counter that restarts needs the date in its prefix, or the numbers of one
+// period would repeat those of the last: {YYYY} or {YY} for a yearly reset,
+// and the full date for a daily one. Each sequence needs its own prefix, as
+// two sequences sharing one would issue the same numbers.
+pub fn set_document_sequence(
+    conn: &Connection,
+    terminal: &str,
+    doc_type: &str,
+    prefix: &str,
+    reset_period: &str,
+    padding: i32,
+) -> Result<()> {
+    if !matches!(doc_type, "sale" | "refund" | "z_report") {
+        return Err(AppError::validation(
+            "doc_type",
+            "invalid_format",
+            "document type must be sale, refund or z_report",
+        ));
+    }
+    let has_year = prefix.contains("{YYYY}") || prefix.contains("{YY}");
+    let dated = match reset_period {
+        "never" => true,
+        "yearly" => has_year,
+        "daily" => has_year && prefix.contains("{MM}") && prefix.contains("{DD}"),
+        _ => {
+            return Err(AppError::validation(
+                "reset_period",
+                "invalid_format",
+                "reset period must be never, yearly or daily",
+            ))
+        }
+    };
+    if !dated {
+        return Err(AppError::validation(
+            "prefix",
+            "invalid_format",
+            format!("a {} reset needs the date in the prefix", reset_period),
+        ));
+    }
+    if !(1..=MAX_PADDING).contains(&padding) {
+        return Err(AppError::validation(
+            "padding",
+            "out_of_range",
+            format!("padding must be between 1 and {}", MAX_PADDING),
+        ));
+    }
+
+    let taken: bool = conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM document_sequences
+            WHERE prefix = ?1 AND NOT (terminal = ?2 AND doc_type = ?3))",
+        params![prefix, terminal, doc_type],
+        |row| row.get(0),
+    )?;
+    if taken {
+        return Err(AppError::Conflict {
+            field: Some("prefix".to_string()),
+            code: "duplicate".to_string(),
+            message: "another document sequence already uses this prefix".to_string(),
+        });
+    }
+
+    conn.execute(
+        "INSERT INTO document_sequences (terminal, doc_type, prefix, reset_period, padding)
+        VALUES (?1, ?2, ?3, ?4, ?5)
+        ON CONFLICT(terminal, doc_type) DO UPDATE SET
+            prefix = excluded.prefix, reset_period = excluded.reset_period, padding = excluded.padding",
+        params![terminal, doc_type, prefix, reset_period, padding],
+    )?;
+    Ok(())
+}
@@ -0,0 +1,178 @@
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::Serialize;
+
+use crate::error::Result;
+use crate::validation;
+
+#[derive(Debug, Serialize)]
+pub struct DrawerSession {
+    pub id: i64,
+    pub employee_id: i32,
+    pub employee_name: String,
+    pub terminal: String,
+    pub opening_float: f64,
+    pub opened_at: String,
+    pub closed_at: Option<String>,
+    // Tips and service charges taken during the session and owed to staff.
+    // Cash ones are already in the expected cash until paid out.
+    pub tips: f64,
+    // The three fields below stay empty until the session is closed, so the
+    // cashier never sees the expected amount before counting (blind close).
+    pub counted_cash: Option<f64>,
+    pub expected_cash: Option<f64>,
+    pub over_short: Option<f64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DrawerMovement {
+    pub id: i64,
+    pub session_id: i64,
+    pub kind: String,
+    pub amount: f64,
+    pub reason: String,
+    pub timestamp: String,
+}
+
+const SESSION_COLUMNS: &str = "d.id, d.employee_id, IFNULL(e.name, ''), d.terminal, d.opening_float,
+    d.opened_at, d.closed_at, d.counted_cash, d.expected_cash, d.over_short,
+    IFNULL((SELECT SUM(t.amount) FROM tips t JOIN sales s ON t.sale_id = s.id
+        WHERE t.drawer_session_id = d.id AND s.voided = 0), 0)
+    FROM drawer_sessions d
+    LEFT JOIN employees e ON d.employee_id = e.id";
+
+fn session_from_row(row: &Row) -> rusqlite::Result<DrawerSession> {
+    Ok(DrawerSession {
+        id: row.get(0)?,
+        employee_id: row.get(1)?,
+        employee_name: row.get(2)?,
+        terminal: row.get(3)?,
+        opening_float: row.get(4)?,
+        opened_at: row.get(5)?,
+        closed_at: row.get(6)?,
+        counted_cash: row.get(7)?,
+        expected_cash: row.get(8)?,
+        over_short: row.get(9)?,
+        tips: row.get(10)?,
+    })
+}
+
+// Open a drawer on a terminal. Only one open session is allowed per terminal
+// and per employee (enforced by partial unique indexes).
+pub fn open_drawer_session(
+    conn: &Connection,
+    employee_id: i32,
+    terminal: &str,
+    opening_float: f64,
+) -> Result<i64> {
+    let opening_float = validation::price("opening_float", opening_float)?;
+    conn.execute(
+        "INSERT INTO drawer_sessions (employee_id, terminal, opening_float) VALUES (?1, ?2, ?3)",
+        params![employee_id, terminal, opening_float],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+pub fn get_open_drawer_session(conn: &Connection, terminal: &str) -> Result<Option<DrawerSession>> {
+    let sql = format!(
+        "SELECT {} WHERE d.terminal = ?1 AND d.closed_at IS NULL",
+        SESSION_COLUMNS
+    );
+    Ok(conn.query_row(&sql, params![terminal], session_from_row).optional()?)
+}
+
+// Record a pay-in or pay-out. Returns false if the session is already closed.
+pub fn add_drawer_movement(
+    conn: &Connection,
+    session_id: i64,
+    kind: &str,
+    amount: f64,
+    reason: &str,
+) -> Result<bool> {
+    let amount = validation::amount("amount", amount)?;
+    let reason = validation::text("reason", reason)?;
+    let inserted = conn.execute(
+        "INSERT INTO drawer_movements (session_id, kind, amount, reason)
+        SELECT id, ?2, ?3, ?4 FROM drawer_sessions WHERE id = ?1 AND closed_at IS NULL",
+        params![session_id, kind, amount, reason],
+    )?;
+    Ok(inserted > 0)
+}
+
+pub fn get_drawer_movements(conn: &Connection, session_id: i64) -> Result<Vec<DrawerMovement>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, session_id, kind, amount, reason, timestamp
+        FROM drawer_movements WHERE session_id = ?1 ORDER BY id",
+    )?;
+    let rows = stmt.query_map(params![session_id], |row| {
+        Ok(DrawerMovement {
+            id: row.get(0)?,
+            session_id: row.get(1)?,
+            kind: row.get(2)?,
+            amount: row.get(3)?,
+            reason: row.get(4)?,
+            timestamp: row.get(5)?,
+        })
+    })?;
+
+    let mut movements = Vec::new();
+    for movement in rows {
+        movements.push(movement?);
+    }
+    Ok(movements)
+}
+
+// Opening float + cash payments (voided sales excluded) + pay-ins - pay-outs
+pub fn expected_cash(conn: &Connection, session_id: i64) -> Result<f64> {
+    Ok(conn.query_row(
+        "SELECT d.opening_float
+            + IFNULL((SELECT SUM(p.amount) FROM payments p JOIN sales s ON p.sale_id = s.id
+                WHERE p.drawer_session_id = d.id AND p.method = 'cash' AND s.voided = 0), 0)
+            + IFNULL((SELECT SUM(amount) FROM drawer_movements
+                WHERE session_id = d.id AND kind = 'pay_in'), 0)
+            - IFNULL((SELECT SUM(amount) FROM drawer_movements
+                WHERE session_id = d.id AND kind = 'pay_out'), 0)
+        FROM drawer_sessions d WHERE d.id = ?1",
+        params![session_id],
+        |row| row.get(0),
+    )?)
+}
+
+// Blind close: the cashier submits the counted cash and the expected amount
+// and over/short are computed and stored. Returns false if already closed.
+pub fn close_drawer_session(conn: &Connection, session_id: i64, counted_cash: f64) -> Result<bool> {
+    let counted_cash = validation::price("counted_cash", counted_cash)?;
+    let expected = expected_cash(conn, session_id)?;
+    let updated = conn.execute(
+        "UPDATE drawer_sessions
+        SET closed_at = CURRENT_TIMESTAMP, counted_cash = ?1, expected_cash = ?2, over_short = ?1 - ?2
+        WHERE id = ?3 AND closed_at IS NULL",
+        params![counted_cash, expected, session_id],
+    )?;
+    Ok(updated > 0)
+}
+
+// Variance history for managers, newest first
+pub fn get_drawer_sessions(
+    conn: &Connection,
+    start_date: Option<&str>,
+    end_date: Option<&str>,
+    employee_id: Option<i32>,
+) -> Result<Vec<DrawerSession>> {
+    let start = start_date.unwrap_or("1970-01-01");
+    let end = end_date.unwrap_or("9999-12-31");
+
+    let sql = format!(
+        "SELECT {} WHERE date(d.opened_at) BETWEEN ?1 AND ?2
+            AND (?3 IS NULL OR d.employee_id = ?3)
+        ORDER BY d.opened_at DESC, d.id DESC",
+        SESSION_COLUMNS
+    );
+    let mut stmt = conn.prepare(&sql)?;
+    let rows = stmt.query_map(params![start, end, employee_id], session_from_row)?;
+
+    let mut sessions = Vec::new();
+    for session in rows {
+        sessions.push(session?);
+    }
+    Ok(sessions)
+}
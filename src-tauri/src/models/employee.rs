@@ -1,9 +1,28 @@
-use rusqlite::{params, Connection, Result};
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use rusqlite::{params, Connection};
+
+use crate::error::{AppError, Result};
+use crate::models::lockout::{check_login, login_failed, login_succeeded};
+use crate::validation;
 
 #[derive(Debug, serde::Serialize, serde::Deserialize)]
 pub struct Employee {
     pub id: i32,
     pub name: String,
+    #[serde(default)]
+    pub has_pin: bool,
+    #[serde(default)]
+    pub role_id: Option<i64>,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct EmployeeLogin {
+    pub employee: Option<Employee>,
+    // Seconds before another attempt is accepted (0 if not locked)
+    pub locked_for: i64,
+    // Token for guarded commands, set by the login commands on success
+    pub session: Option<String>,
 }
 
 pub fn create_employee_table(conn: &Connection) -> Result<()> {
@@ -19,29 +38,35 @@ pub fn create_employee_table(conn: &Connection) -> Result<()> {
 }
 
 pub fn add_employee(conn: &Connection, name: &str, password: &str) -> Result<usize> {
-    conn.execute(
+    let name = validation::name("name", name)?;
+    let password = validation::password("password", password)?;
+    Ok(conn.execute(
         "INSERT INTO employees (name, password) VALUES (?1, ?2)",
         params![name, password],
-    )
+    )?)
 }
 
 pub fn update_employee(conn: &Connection, id: i32, name: &str, password: &str) -> Result<usize> {
-    conn.execute(
+    let name = validation::name("name", name)?;
+    let password = validation::password("password", password)?;
+    Ok(conn.execute(
         "UPDATE employees SET name=?1, password=?2 WHERE id=?3",
         params![name, password, id],
-    )
+    )?)
 }
 
 pub fn delete_employee(conn: &Connection, id: i32) -> Result<usize> {
-    conn.execute("DELETE FROM employees WHERE id=?1", params![id])
+    Ok(conn.execute("DELETE FROM employees WHERE id=?1", params![id])?)
 }
 
 pub fn get_employees(conn: &Connection) -> Result<Vec<Employee>> {
-    let mut stmt = conn.prepare("SELECT id, name FROM employees ORDER BY id DESC")?;
+    let mut stmt = conn.prepare("SELECT id, name, pin_hash IS NOT NULL, role_id FROM employees ORDER BY id DESC")?;
     let rows = stmt.query_map([], |row| {
         Ok(Employee {
             id: row.get(0)?,
             name: row.get(1)?,
+            has_pin: row.get(2)?,
+            role_id: row.get(3)?,
         })
     })?;
 
@@ -54,15 +79,118 @@ pub fn get_employees(conn: &Connection) -> Result<Vec<Employee>> {
 
 /// Verify employee credentials for login
 pub fn verify_employee(conn: &Connection, name: &str, password: &str) -> Result<Option<Employee>> {
-    let mut stmt = conn.prepare("SELECT id, name FROM employees WHERE name = ?1 AND password = ?2")?;
+    let mut stmt = conn.prepare(
+        "SELECT id, name, pin_hash IS NOT NULL, role_id FROM employees WHERE name = ?1 AND password = ?2",
+    )?;
     let mut rows = stmt.query(params![name, password])?;
 
     if let Some(row) = rows.next()? {
         Ok(Some(Employee {
             id: row.get(0)?,
             name: row.get(1)?,
+            has_pin: row.get(2)?,
+            role_id: row.get(3)?,
         }))
     } else {
         Ok(None)
     }
 }
+
+fn hash_pin(pin: &str) -> Result<String> {
+    let salt = SaltString::encode_b64(&rand::random::<[u8; 16]>())
+        .map_err(|e| rusqlite::Error::ToSqlConversionFailure(e.to_string().into()))?;
+    let hash = Argon2::default()
+        .hash_password(pin.as_bytes(), &salt)
+        .map_err(|e| rusqlite::Error::ToSqlConversionFailure(e.to_string().into()))?;
+    Ok(hash.to_string())
+}
+
+fn pin_matches(pin: &str, hash: &str) -> bool {
+    PasswordHash::new(hash)
+        .map(|hash| Argon2::default().verify_password(pin.as_bytes(), &hash).is_ok())
+        .unwrap_or(false)
+}
+
+// PIN hashes are salted, so finding the owner of a PIN means checking it
+// against every employee that has one.
+pub(crate) fn find_pin_owner(conn: &Connection, pin: &str) -> Result<Option<Employee>> {
+    let mut stmt = conn.prepare("SELECT id, name, pin_hash, role_id FROM employees WHERE pin_hash IS NOT NULL")?;
+    let rows = stmt.query_map([], |row| {
+        Ok((
+            row.get::<_, i32>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, String>(2)?,
+            row.get::<_, Option<i64>>(3)?,
+        ))
+    })?;
+    for row in rows {
+        let (id, name, hash, role_id) = row?;
+        if pin_matches(pin, &hash) {
+            return Ok(Some(Employee { id, name, has_pin: true, role_id }));
+        }
+    }
+    Ok(None)
+}
+
+// Set an employee's quick-login PIN (4-8 digits, not used by anyone else).
+// Returns false if there is no such employee.
+pub fn set_employee_pin(conn: &Connection, id: i32, pin: &str) -> Result<bool> {
+    let pin = validation::pin(pin)?;
+    if find_pin_owner(conn, pin)?.is_some_and(|owner| owner.id != id) {
+        return Err(AppError::Conflict {
+            field: Some("pin".to_string()),
+            code: "duplicate".to_string(),
+            message: "pin is already used by another employee".to_string(),
+        });
+    }
+    let updated = conn.execute(
+        "UPDATE employees SET pin_hash = ?1 WHERE id = ?2",
+        params![hash_pin(pin)?, id],
+    )?;
+    Ok(updated > 0)
+}
+
+pub fn clear_employee_pin(conn: &Connection, id: i32) -> Result<usize> {
+    Ok(conn.execute("UPDATE employees SET pin_hash = NULL WHERE id = ?1", params![id])?)
+}
+
+// Password login, with failed attempts counted per employee name and per
+// terminal (see lockout.rs)
+pub fn authenticate_employee(conn: &Connection, name: &str, password: &str, terminal: &str) -> Result<EmployeeLogin> {
+    let locked = check_login(conn, "employee", Some(name), terminal)?;
+    if locked > 0 {
+        return Ok(EmployeeLogin { employee: None, locked_for: locked, session: None });
+    }
+
+    match verify_employee(conn, name, password)? {
+        Some(employee) => {
+            login_succeeded(conn, "employee", Some(name), terminal)?;
+            Ok(EmployeeLogin { employee: Some(employee), locked_for: 0, session: None })
+        }
+        None => {
+            let locked_for = login_failed(conn, "employee", Some(name), terminal)?;
+            Ok(EmployeeLogin { employee: None, locked_for, session: None })
+        }
+    }
+}
+
+// Quick login (and user switching on a locked screen) by PIN alone. A PIN
+// names no account, so failures count against the terminal and against all
+// PIN logins; a correct PIN does not clear the latter (see lockout.rs).
+pub fn login_with_pin(conn: &Connection, pin: &str, terminal: &str) -> Result<EmployeeLogin> {
+    let locked = check_login(conn, "pin", None, terminal)?;
+    if locked > 0 {
+        return Ok(EmployeeLogin { employee: None, locked_for: locked, session: None });
+    }
+
+    match find_pin_owner(conn, pin)? {
+        Some(employee) => {
+            login_succeeded(conn, "pin", None, terminal)?;
+            Ok(EmployeeLogin { employee: Some(employee), locked_for: 0, session: None })
+        }
+        None => {
+            let locked_for = login_failed(conn, "pin", None, terminal)?;
+            Ok(EmployeeLogin { employee: None, locked_for, session: None })
+        }
+    }
+}
@@ -0,0 +1,392 @@
+use rand::Rng;
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::Serialize;
+
+use crate::error::{AppError, Result};
+use crate::models::payment::add_payment;
+use crate::validation;
+
+// Gift cards and store credit share one table. The balance of a card is the
+// sum of its ledger; a gift card sold on a sale only gets its "issue" entry
+// (and so becomes usable) when that sale is completed.
+
+#[derive(Debug, Serialize)]
+pub struct GiftCard {
+    pub id: i64,
+    pub code: String,
+    pub kind: String,
+    pub initial_amount: f64,
+    pub balance: f64,
+    pub expires_at: Option<String>,
+    pub customer_id: Option<i64>,
+    pub active: bool,
+    pub expired: bool,
+    pub created_at: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct GiftCardEntry {
+    pub id: i64,
+    pub card_id: i64,
+    pub sale_id: Option<i64>,
+    pub amount: f64,
+    pub kind: String,
+    pub created_at: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct GiftCardLiability {
+    // Balances customers can still spend
+    pub outstanding: f64,
+    pub gift_card_outstanding: f64,
+    pub store_credit_outstanding: f64,
+    // Balances left on expired cards
+    pub expired: f64,
+    pub cards: Vec<GiftCard>,
+}
+
+const CODE_LENGTH: usize = 16;
+
+// Luhn check digit for a string of digits
+fn luhn_digit(digits: &str) -> u32 {
+    let sum: u32 = digits
+        .chars()
+        .rev()
+        .filter_map(|c| c.to_digit(10))
+        .enumerate()
+        .map(|(i, d)| if i % 2 == 0 { if d * 2 > 9 { d * 2 - 9 } else { d * 2 } } else { d })
+        .sum();
+    (10 - sum % 10) % 10
+}
+
+// Strip spaces and dashes and check length and check digit, so a mistyped
+// code is rejected before it is looked up
+pub fn normalize_code(code: &str) -> Option<String> {
+    let code: String = code.chars().filter(|c| !matches!(c, ' ' | '-')).collect();
+    if code.len() != CODE_LENGTH || !code.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let (body, check) = code.split_at(CODE_LENGTH - 1);
+    (check.parse::<u32>().ok()? == luhn_digit(body)).then_some(code)
+}
+
+// Groups of four for printing: 7123-4567-8901-2345
+pub fn format_code(code: &str) -> String {
+    code.as_bytes()
+        .chunks(4)
+        .map(|chunk| String::from_utf8_lossy(chunk).into_owned())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+fn generate_code(conn: &Connection) -> Result<String> {
+    let mut rng = rand::thread_rng();
+    loop {
+        let body: String = std::iter::once('7')
+            .chain((1..CODE_LENGTH - 1).map(|_| char::from(b'0' + rng.gen_range(0..10u8))))
+            .collect();
+        let code = format!("{}{}", body, luhn_digit(&body));
+
+        let exists: bool = conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM gift_cards WHERE code = ?1)",
+            params![code],
+            |row| row.get(0),
+        )?;
+        if !exists {
+            return Ok(code);
+        }
+    }
+}
+
+const CARD_COLUMNS: &str = "g.id, g.code, g.kind, g.initial_amount,
+    IFNULL((SELECT SUM(amount) FROM gift_card_ledger WHERE card_id = g.id), 0),
+    g.expires_at, g.customer_id,
+    EXISTS(SELECT 1 FROM gift_card_ledger WHERE card_id = g.id AND kind = 'issue'),
+    IFNULL(g.expires_at < date('now'), 0), g.created_at";
+
+fn card_from_row(row: &Row) -> rusqlite::Result<GiftCard> {
+    Ok(GiftCard {
+        id: row.get(0)?,
+        code: row.get(1)?,
+        kind: row.get(2)?,
+        initial_amount: row.get(3)?,
+        balance: row.get(4)?,
+        expires_at: row.get(5)?,
+        customer_id: row.get(6)?,
+        active: row.get(7)?,
+        expired: row.get(8)?,
+        created_at: row.get(9)?,
+    })
+}
+
+pub fn get_gift_card(conn: &Connection, code: &str) -> Result<Option<GiftCard>> {
+    let Some(code) = normalize_code(code) else {
+        return Ok(None);
+    };
+    Ok(conn.query_row(
+        &format!("SELECT {} FROM gift_cards g WHERE g.code = ?1", CARD_COLUMNS),
+        params![code],
+        card_from_row,
+    )
+    .optional()?)
+}
+
+pub fn get_gift_card_ledger(conn: &Connection, card_id: i64) -> Result<Vec<GiftCardEntry>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, card_id, sale_id, amount, kind, created_at
+        FROM gift_card_ledger WHERE card_id = ?1 ORDER BY id",
+    )?;
+    let rows = stmt.query_map(params![card_id], |row| {
+        Ok(GiftCardEntry {
+            id: row.get(0)?,
+            card_id: row.get(1)?,
+            sale_id: row.get(2)?,
+            amount: row.get(3)?,
+            kind: row.get(4)?,
+            created_at: row.get(5)?,
+        })
+    })?;
+
+    let mut entries = Vec::new();
+    for entry in rows {
+        entries.push(entry?);
+    }
+    Ok(entries)
+}
+
+// Expiry dates are stored as "YYYY-MM-DD" (any date SQLite reads is
+// accepted) and must lie in the future
+fn expiry_date(conn: &Connection, expires_at: Option<&str>) -> Result<Option<String>> {
+    let Some(expires_at) = expires_at else { return Ok(None) };
+    let (date, future): (Option<String>, bool) = conn.query_row(
+        "SELECT date(?1), IFNULL(date(?1) > date('now'), 0)",
+        params![expires_at],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )?;
+    let date = date.ok_or_else(|| AppError::validation("expires_at", "invalid_format", "not a valid date"))?;
+    if !future {
+        return Err(AppError::validation("expires_at", "out_of_range", "expiry date must be in the future"));
+    }
+    Ok(Some(date))
+}
+
+// Sell a gift card on an open sale: adds a sale line for the amount and a
+// card that becomes active when the sale is completed. Returns the new code.
+pub fn sell_gift_card(conn: &Connection, sale_id: i64, amount: f64, expires_at: Option<&str>) -> Result<String> {
+    let amount = validation::amount("amount", amount)?;
+    let expires_at = expiry_date(conn, expires_at)?;
+    let tx = conn.unchecked_transaction()?;
+
+    let open: bool = tx
+        .query_row(
+            "SELECT receipt_number IS NULL AND voided = 0 FROM sales WHERE id = ?1",
+            params![sale_id],
+            |row| row.get(0),
+        )
+        .optional()?
+        .ok_or_else(|| AppError::not_found("sale"))?;
+    if !open {
+        return Err(AppError::Conflict {
+            field: Some("sale".to_string()),
+            code: "completed".to_string(),
+            message: "gift cards can only be sold on an open sale".to_string(),
+        });
+    }
+
+    let code = generate_code(&tx)?;
+    tx.execute(
+        "INSERT INTO gift_cards (code, kind, initial_amount, expires_at, customer_id, sale_id)
+        SELECT ?1, 'gift_card', ?2, ?3, customer_id, id FROM sales WHERE id = ?4",
+        params![code, amount, expires_at, sale_id],
+    )?;
+    if tx.changes() == 0 {
+        return Err(AppError::not_found("sale"));
+    }
+    let card_id = tx.last_insert_rowid();
+
+    tx.execute(
+        "INSERT INTO sale_items (sale_id, product_id, product_name, quantity, price, gift_card_id)
+        VALUES (?1, NULL, ?2, 1, ?3, ?4)",
+        params![sale_id, format!("Gift card {}", format_code(&code)), amount, card_id],
+    )?;
+
+    tx.commit()?;
+    Ok(code)
+}
+
+// Load the cards sold on a sale. Called from `complete_sale` inside its transaction.
+pub fn activate_gift_cards(conn: &Connection, sale_id: i64) -> Result<()> {
+    conn.execute(
+        "INSERT INTO gift_card_ledger (card_id, sale_id, amount, kind)
+        SELECT g.id, g.sale_id, g.initial_amount, 'issue'
+        FROM gift_cards g
+        WHERE g.sale_id = ?1 AND g.kind = 'gift_card'
+            AND NOT EXISTS(SELECT 1 FROM gift_card_ledger WHERE card_id = g.id AND kind = 'issue')",
+        params![sale_id],
+    )?;
+    Ok(())
+}
+
+// Take `amount` off a card as a "gift_card" payment on an open sale. Partial
+// use leaves the rest on the card. Returns false for an unknown, inactive or
+// expired card, or if the balance does not cover the amount.
+pub fn redeem_gift_card(conn: &Connection, sale_id: i64, code: &str, amount: f64) -> Result<bool> {
+    let amount = validation::amount("amount", amount)?;
+    let tx = conn.unchecked_transaction()?;
+
+    let open: bool = tx
+        .query_row(
+            "SELECT receipt_number IS NULL AND voided = 0 FROM sales WHERE id = ?1",
+            params![sale_id],
+            |row| row.get(0),
+        )
+        .optional()?
+        .ok_or_else(|| AppError::not_found("sale"))?;
+    if !open {
+        return Err(AppError::Conflict {
+            field: Some("sale".to_string()),
+            code: "completed".to_string(),
+            message: "gift cards can only be redeemed on an open sale".to_string(),
+        });
+    }
+
+    let Some(card) = get_gift_card(&tx, code)? else {
+        return Ok(false);
+    };
+    // Compare in cents so a balance like 0.1 + 0.2 still covers 0.3
+    if !card.active || card.expired || (card.balance * 100.0).round() < (amount * 100.0).round() {
+        return Ok(false);
+    }
+
+    tx.execute(
+        "INSERT INTO gift_card_ledger (card_id, sale_id, amount, kind) VALUES (?1, ?2, ?3, 'redeem')",
+        params![card.id, sale_id, -amount],
+    )?;
+    add_payment(&tx, sale_id, if card.kind == "store_credit" { "store_credit" } else { "gift_card" }, amount)?;
+
+    tx.commit()?;
+    Ok(true)
+}
+
+// Pay out a refund as store credit instead of cash: a new card loaded with
+// the refunded amount, recorded as the refund's tender. Issuing twice for the
+// same refund returns the existing code.
+pub fn issue_store_credit(conn: &Connection, refund_id: i64, expires_at: Option<&str>) -> Result<String> {
+    let expires_at = expiry_date(conn, expires_at)?;
+    let tx = conn.unchecked_transaction()?;
+
+    let existing: Option<String> = tx
+        .query_row(
+            "SELECT code FROM gift_cards WHERE sale_id = ?1 AND kind = 'store_credit'",
+            params![refund_id],
+            |row| row.get(0),
+        )
+        .optional()?;
+    if let Some(code) = existing {
+        return Ok(code);
+    }
+
+    let (total, customer_id): (f64, Option<i64>) = tx.query_row(
+        "SELECT total, customer_id FROM sales WHERE id = ?1 AND refund_of IS NOT NULL",
+        params![refund_id],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )?;
+    let amount = -total;
+
+    let code = generate_code(&tx)?;
+    tx.execute(
+        "INSERT INTO gift_cards (code, kind, initial_amount, expires_at, customer_id, sale_id)
+        VALUES (?1, 'store_credit', ?2, ?3, ?4, ?5)",
+        params![code, amount, expires_at, customer_id, refund_id],
+    )?;
+    tx.execute(
+        "INSERT INTO gift_card_ledger (card_id, sale_id, amount, kind) VALUES (?1, ?2, ?3, 'issue')",
+        params![tx.last_insert_rowid(), refund_id, amount],
+    )?;
+    add_payment(&tx, refund_id, "store_credit", total)?;
+
+    tx.commit()?;
+    Ok(code)
+}
+
+// Empty the cards sold on a refunded sale, booked against the refund. A card
+// that has already been spent cannot be refunded. Called from `refund_sale`
+// inside its transaction.
+pub fn refund_gift_cards(conn: &Connection, sale_id: i64, refund_id: i64) -> Result<()> {
+    let used: bool = conn.query_row(
+        "SELECT EXISTS(
+            SELECT 1 FROM gift_cards g
+            WHERE g.sale_id = ?1 AND g.kind = 'gift_card'
+                AND ROUND(IFNULL((SELECT SUM(amount) FROM gift_card_ledger WHERE card_id = g.id), 0) * 100)
+                    < ROUND(g.initial_amount * 100)
+        )",
+        params![sale_id],
+        |row| row.get(0),
+    )?;
+    if used {
+        return Err(AppError::Conflict {
+            field: Some("gift_card".to_string()),
+            code: "in_use".to_string(),
+            message: "a gift card sold on the sale has been used".to_string(),
+        });
+    }
+
+    conn.execute(
+        "INSERT INTO gift_card_ledger (card_id, sale_id, amount, kind)
+        SELECT l.card_id, ?2, -SUM(l.amount), 'void'
+        FROM gift_cards g JOIN gift_card_ledger l ON l.card_id = g.id
+        WHERE g.sale_id = ?1 AND g.kind = 'gift_card'
+        GROUP BY l.card_id HAVING SUM(l.amount) != 0",
+        params![sale_id, refund_id],
+    )?;
+    Ok(())
+}
+
+// Undo a voided sale's effect on cards: money taken off cards goes back and
+// cards sold on it are emptied. Called from `void_sale`.
+pub fn reverse_gift_cards(conn: &Connection, sale_id: i64) -> Result<()> {
+    conn.execute(
+        "INSERT INTO gift_card_ledger (card_id, sale_id, amount, kind)
+        SELECT card_id, ?1, -SUM(amount), 'void'
+        FROM gift_card_ledger WHERE sale_id = ?1
+        GROUP BY card_id HAVING SUM(amount) != 0",
+        params![sale_id],
+    )?;
+    Ok(())
+}
+
+// Outstanding balances on active cards, split by kind, with expired balances apart
+pub fn get_gift_card_liability(conn: &Connection) -> Result<GiftCardLiability> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM gift_cards g
+        WHERE EXISTS(SELECT 1 FROM gift_card_ledger WHERE card_id = g.id)
+        ORDER BY g.id",
+        CARD_COLUMNS
+    ))?;
+    let rows = stmt.query_map([], card_from_row)?;
+
+    let mut liability = GiftCardLiability {
+        outstanding: 0.0,
+        gift_card_outstanding: 0.0,
+        store_credit_outstanding: 0.0,
+        expired: 0.0,
+        cards: Vec::new(),
+    };
+    for card in rows {
+        let card = card?;
+        if card.balance.abs() < 0.005 {
+            continue;
+        }
+        if card.expired {
+            liability.expired += card.balance;
+        } else if card.kind == "store_credit" {
+            liability.store_credit_outstanding += card.balance;
+        } else {
+            liability.gift_card_outstanding += card.balance;
+        }
+        liability.cards.push(card);
+    }
+    liability.outstanding = liability.gift_card_outstanding + liability.store_credit_outstanding;
+
+    Ok(liability)
+}
@@ -0,0 +1,222 @@
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+
+use crate::error::Result;
+
+// Failed sign-in tracking. Failures are counted against the account
+// ("admin:<name>", "employee:<name>") and against the terminal
+// ("terminal:<id>"). PINs name no account, and the terminal id comes from
+// the caller, so PIN failures also count against "pin:*" for all terminals
+// together. Each key gets a few free attempts, then every further
+// failure locks it for twice as long as the last one, up to a cap. A key
+// with no failure for an hour starts counting afresh.
+//
+// A successful sign-in clears the account and terminal keys, but not
+// "pin:*": anyone with a PIN of their own could otherwise reset it between
+// guesses. An admin can clear any key early.
+const FREE_ATTEMPTS: i64 = 3;
+const BASE_LOCK_SECONDS: i64 = 30;
+const MAX_LOCK_SECONDS: i64 = 15 * 60;
+const FORGET_AFTER: &str = "-1 hours";
+const SHARED_PIN_KEY: &str = "pin:*";
+
+#[derive(Debug, Serialize)]
+pub struct Lockout {
+    pub key: String,
+    pub failures: i64,
+    pub locked_until: Option<String>,
+    pub locked_for: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LoginFailure {
+    pub id: i64,
+    // "admin", "employee" or "pin"
+    pub kind: String,
+    pub account: Option<String>,
+    pub terminal: String,
+    // The attempt was refused without checking because a key was locked
+    pub locked: bool,
+    pub created_at: String,
+}
+
+// Seconds the key is still locked for (0 if not locked)
+pub fn locked_for(conn: &Connection, key: &str) -> Result<i64> {
+    let seconds: Option<i64> = conn
+        .query_row(
+            "SELECT CAST(ROUND((julianday(locked_until) - julianday('now')) * 86400) AS INTEGER)
+            FROM login_lockouts WHERE key = ?1 AND locked_until > datetime('now')",
+            params![key],
+            |row| row.get(0),
+        )
+        .optional()?;
+    Ok(seconds.unwrap_or(0).max(0))
+}
+
+// Count a failure and return how many seconds the key is now locked for
+pub fn record_failure(conn: &Connection, key: &str) -> Result<i64> {
+    let failures: i64 = conn.query_row(
+        "INSERT INTO login_lockouts (key, failures, last_failure_at) VALUES (?1, 1, CURRENT_TIMESTAMP)
+        ON CONFLICT(key) DO UPDATE SET
+            failures = CASE WHEN last_failure_at < datetime('now', ?2) THEN 1 ELSE failures + 1 END,
+            last_failure_at = CURRENT_TIMESTAMP
+        RETURNING failures",
+        params![key, FORGET_AFTER],
+        |row| row.get(0),
+    )?;
+
+    if failures <= FREE_ATTEMPTS {
+        return Ok(0);
+    }
+    let doublings = (failures - FREE_ATTEMPTS - 1).min(16) as u32;
+    let seconds = (BASE_LOCK_SECONDS << doublings).min(MAX_LOCK_SECONDS);
+    conn.execute(
+        "UPDATE login_lockouts SET locked_until = datetime('now', ?2) WHERE key = ?1",
+        params![key, format!("+{} seconds", seconds)],
+    )?;
+    Ok(seconds)
+}
+
+pub fn clear_failures(conn: &Connection, key: &str) -> Result<()> {
+    conn.execute("DELETE FROM login_lockouts WHERE key = ?1", params![key])?;
+    Ok(())
+}
+
+fn login_keys(kind: &str, account: Option<&str>, terminal: &str) -> Vec<String> {
+    vec![format!("terminal:{}", terminal), format!("{}:{}", kind, account.unwrap_or("*"))]
+}
+
+fn audit_failure(conn: &Connection, kind: &str, account: Option<&str>, terminal: &str, locked: bool) -> Result<()> {
+    conn.execute(
+        "INSERT INTO login_failures (kind, account, terminal, locked) VALUES (?1, ?2, ?3, ?4)",
+        params![kind, account, terminal, locked],
+    )?;
+    Ok(())
+}
+
+// Call before checking credentials. Returns the seconds left on the longest
+// lock covering the account or terminal; a refused attempt is audited.
+pub fn check_login(conn: &Connection, kind: &str, account: Option<&str>, terminal: &str) -> Result<i64> {
+    let mut locked = 0;
+    for key in login_keys(kind, account, terminal) {
+        locked = locked.max(locked_for(conn, &key)?);
+    }
+    if locked > 0 {
+        audit_failure(conn, kind, account, terminal, true)?;
+    }
+    Ok(locked)
+}
+
+// Record a wrong password or PIN; returns the seconds the caller is now locked for
+pub fn login_failed(conn: &Connection, kind: &str, account: Option<&str>, terminal: &str) -> Result<i64> {
+    audit_failure(conn, kind, account, terminal, false)?;
+    let mut locked = 0;
+    for key in login_keys(kind, account, terminal) {
+        locked = locked.max(record_failure(conn, &key)?);
+    }
+    Ok(locked)
+}
+
+pub fn login_succeeded(conn: &Connection, kind: &str, account: Option<&str>, terminal: &str) -> Result<()> {
+    for key in login_keys(kind, account, terminal) {
+        if key != SHARED_PIN_KEY {
+            clear_failures(conn, &key)?;
+        }
+    }
+    Ok(())
+}
+
+// Keys with failures on record, locked ones first
+pub fn get_lockouts(conn: &Connection) -> Result<Vec<Lockout>> {
+    let mut stmt = conn.prepare(
+        "SELECT key, failures, locked_until,
+            MAX(CAST(ROUND((julianday(locked_until) - julianday('now')) * 86400) AS INTEGER), 0)
+        FROM login_lockouts
+        ORDER BY locked_until > datetime('now') DESC, failures DESC",
+    )?;
+    let rows = stmt.query_map([], |row| {
+        Ok(Lockout {
+            key: row.get(0)?,
+            failures: row.get(1)?,
+            locked_until: row.get(2)?,
+            locked_for: row.get::<_, Option<i64>>(3)?.unwrap_or(0),
+        })
+    })?;
+
+    let mut lockouts = Vec::new();
+    for lockout in rows {
+        lockouts.push(lockout?);
+    }
+    Ok(lockouts)
+}
+
+pub fn get_login_failures(conn: &Connection, limit: i64) -> Result<Vec<LoginFailure>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, kind, account, terminal, locked, created_at
+        FROM login_failures ORDER BY id DESC LIMIT ?1",
+    )?;
+    let rows = stmt.query_map(params![limit], |row| {
+        Ok(LoginFailure {
+            id: row.get(0)?,
+            kind: row.get(1)?,
+            account: row.get(2)?,
+            terminal: row.get(3)?,
+            locked: row.get(4)?,
+            created_at: row.get(5)?,
+        })
+    })?;
+
+    let mut failures = Vec::new();
+    for failure in rows {
+        failures.push(failure?);
+    }
+    Ok(failures)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::migrate;
+    use crate::models::employee::{add_employee, login_with_pin, set_employee_pin};
+    use crate::models::permission::manager_override;
+
+    fn failures(conn: &Connection, key: &str) -> i64 {
+        conn.query_row("SELECT IFNULL(MAX(failures), 0) FROM login_lockouts WHERE key = ?1", params![key], |row| {
+            row.get(0)
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn valid_pin_does_not_reset_shared_pin_counter() {
+        let conn = Connection::open_in_memory().unwrap();
+        migrate(&conn).unwrap();
+        add_employee(&conn, "ana", "password").unwrap();
+        set_employee_pin(&conn, 1, "1234").unwrap();
+
+        for _ in 0..FREE_ATTEMPTS {
+            assert_eq!(login_with_pin(&conn, "0000", "T1").unwrap().locked_for, 0);
+        }
+        assert!(login_with_pin(&conn, "1234", "T1").unwrap().employee.is_some());
+        assert!(manager_override(&conn, "1234", "void_sale", 1, "T1").unwrap().manager_name.is_some());
+        assert_eq!(failures(&conn, "terminal:T1"), 0);
+        assert_eq!(failures(&conn, SHARED_PIN_KEY), FREE_ATTEMPTS);
+
+        // The next wrong PIN locks every terminal, whatever id it claims
+        assert_eq!(login_with_pin(&conn, "0000", "T2").unwrap().locked_for, BASE_LOCK_SECONDS);
+        let login = login_with_pin(&conn, "1234", "T3").unwrap();
+        assert!(login.employee.is_none() && login.locked_for > 0);
+    }
+
+    #[test]
+    fn failures_are_forgotten_after_an_hour() {
+        let conn = Connection::open_in_memory().unwrap();
+        migrate(&conn).unwrap();
+        for _ in 0..FREE_ATTEMPTS {
+            record_failure(&conn, SHARED_PIN_KEY).unwrap();
+        }
+        conn.execute("UPDATE login_lockouts SET last_failure_at = datetime('now', '-61 minutes')", []).unwrap();
+        assert_eq!(record_failure(&conn, SHARED_PIN_KEY).unwrap(), 0);
+        assert_eq!(failures(&conn, SHARED_PIN_KEY), 1);
+    }
+}
@@ -0,0 +1,171 @@
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+
+use crate::error::{AppError, Result};
+use crate::models::payment::add_payment;
+use crate::models::sale::update_sale_total;
+use crate::models::settings::get_setting_f64;
+use crate::validation;
+
+// Loyalty settings:
+// loyalty_earn_rate   points earned per 1.00 spent (default 1)
+// loyalty_point_value money value of one point when redeemed (default 0.01)
+
+#[derive(Debug, Serialize)]
+pub struct LoyaltyEntry {
+    pub id: i64,
+    pub customer_id: i64,
+    pub sale_id: Option<i64>,
+    pub points: i64,
+    pub reason: String,
+    pub note: String,
+    pub created_at: String,
+}
+
+pub fn get_points_balance(conn: &Connection, customer_id: i64) -> Result<i64> {
+    Ok(conn.query_row(
+        "SELECT IFNULL(SUM(points), 0) FROM loyalty_ledger WHERE customer_id = ?1",
+        params![customer_id],
+        |row| row.get(0),
+    )?)
+}
+
+pub fn get_loyalty_ledger(conn: &Connection, customer_id: i64) -> Result<Vec<LoyaltyEntry>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, customer_id, sale_id, points, reason, note, created_at
+        FROM loyalty_ledger WHERE customer_id = ?1 ORDER BY id DESC",
+    )?;
+    let rows = stmt.query_map(params![customer_id], |row| {
+        Ok(LoyaltyEntry {
+            id: row.get(0)?,
+            customer_id: row.get(1)?,
+            sale_id: row.get(2)?,
+            points: row.get(3)?,
+            reason: row.get(4)?,
+            note: row.get(5)?,
+            created_at: row.get(6)?,
+        })
+    })?;
+
+    let mut entries = Vec::new();
+    for entry in rows {
+        entries.push(entry?);
+    }
+    Ok(entries)
+}
+
+fn sale_customer(conn: &Connection, sale_id: i64) -> Result<Option<i64>> {
+    Ok(conn
+        .query_row(
+            "SELECT customer_id FROM sales WHERE id = ?1",
+            params![sale_id],
+            |row| row.get(0),
+        )
+        .optional()?
+        .flatten())
+}
+
+// Manual correction by staff; `points` may be negative
+pub fn adjust_points(conn: &Connection, customer_id: i64, points: i64, note: &str) -> Result<()> {
+    let note = validation::text("note", note)?;
+    conn.execute(
+        "INSERT INTO loyalty_ledger (customer_id, points, reason, note) VALUES (?1, ?2, 'adjust', ?3)",
+        params![customer_id, points, note],
+    )?;
+    Ok(())
+}
+
+// Credit points for a completed sale. The part paid with points earns
+// nothing. Called from `complete_sale` inside its transaction.
+pub fn award_points(conn: &Connection, sale_id: i64) -> Result<()> {
+    let Some(customer_id) = sale_customer(conn, sale_id)? else {
+        return Ok(());
+    };
+
+    let earn_rate = get_setting_f64(conn, "loyalty_earn_rate", 1.0)?;
+    let basis: f64 = conn.query_row(
+        "SELECT s.total - IFNULL((SELECT SUM(amount) FROM payments WHERE sale_id = s.id AND method = 'points'), 0)
+        FROM sales s WHERE s.id = ?1",
+        params![sale_id],
+        |row| row.get(0),
+    )?;
+    let points = (basis * earn_rate).floor() as i64;
+
+    if points > 0 {
+        conn.execute(
+            "INSERT INTO loyalty_ledger (customer_id, sale_id, points, reason) VALUES (?1, ?2, ?3, 'earn')",
+            params![customer_id, sale_id, points],
+        )?;
+    }
+    Ok(())
+}
+
+// Undo everything a sale did to the points balance (earned and redeemed),
+// booked against the refund, or against the sale itself when it is voided.
+// Called from `refund_sale` and `void_sale` inside their transactions.
+pub fn reverse_points(conn: &Connection, sale_id: i64, refund_id: i64) -> Result<()> {
+    conn.execute(
+        "INSERT INTO loyalty_ledger (customer_id, sale_id, points, reason)
+        SELECT customer_id, ?2, -SUM(points), 'refund'
+        FROM loyalty_ledger WHERE sale_id = ?1
+        GROUP BY customer_id HAVING SUM(points) != 0",
+        params![sale_id, refund_id],
+    )?;
+    Ok(())
+}
+
+// Spend points on an open sale, either as a tender (a "points" payment) or
+// as a discount off the total, and never for more than is left to pay.
+// Returns false if the sale has no customer, is already completed, or the
+// balance is too low.
+pub fn redeem_points(conn: &Connection, sale_id: i64, points: i64, as_tender: bool) -> Result<bool> {
+    if points <= 0 {
+        return Ok(false);
+    }
+
+    let tx = conn.unchecked_transaction()?;
+
+    let open: bool = tx.query_row(
+        "SELECT receipt_number IS NULL FROM sales WHERE id = ?1",
+        params![sale_id],
+        |row| row.get(0),
+    )?;
+    let Some(customer_id) = sale_customer(&tx, sale_id)? else {
+        return Ok(false);
+    };
+    if !open || get_points_balance(&tx, customer_id)? < points {
+        return Ok(false);
+    }
+
+    let value = points as f64 * get_setting_f64(&tx, "loyalty_point_value", 0.01)?;
+    update_sale_total(&tx, sale_id)?;
+    let due: f64 = tx.query_row(
+        "SELECT CASE WHEN ?2 THEN s.total + s.service_charge
+                - IFNULL((SELECT SUM(amount) FROM payments WHERE sale_id = s.id), 0)
+            ELSE s.total END
+        FROM sales s WHERE s.id = ?1",
+        params![sale_id, as_tender],
+        |row| row.get(0),
+    )?;
+    if value > due + 1e-9 {
+        return Err(AppError::validation("points", "out_of_range", "the points are worth more than is left to pay"));
+    }
+    if as_tender {
+        add_payment(&tx, sale_id, "points", value)?;
+    } else {
+        // Kept apart too, so a later manual discount does not replace it
+        tx.execute(
+            "UPDATE sales SET discount = discount + ?1, points_discount = points_discount + ?1 WHERE id = ?2",
+            params![value, sale_id],
+        )?;
+        update_sale_total(&tx, sale_id)?;
+    }
+
+    tx.execute(
+        "INSERT INTO loyalty_ledger (customer_id, sale_id, points, reason) VALUES (?1, ?2, ?3, 'redeem')",
+        params![customer_id, sale_id, -points],
+    )?;
+
+    tx.commit()?;
+    Ok(true)
+}
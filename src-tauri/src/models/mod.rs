@@ -2,4 +2,25 @@ pub mod products;
 pub mod employee;
 pub mod sale;
 pub mod report;
-pub mod admin;
\ No newline at end of file
+pub mod admin;
+pub mod payment;
+pub mod drawer;
+pub mod settings;
+pub mod shift_report;
+pub mod receipt_template;
+pub mod document;
+pub mod customer;
+pub mod loyalty;
+pub mod gift_card;
+pub mod timeclock;
+pub mod tip;
+pub mod performance;
+pub mod lockout;
+pub mod session;
+pub mod permission;
+pub mod product_barcode;
+pub mod stock;
+pub mod variant;
+pub mod bundle;
+pub mod price;
+pub mod availability;
@@ -0,0 +1,56 @@
+use rusqlite::{params, Connection};
+use serde::Serialize;
+
+use crate::error::Result;
+use crate::validation;
+
+#[derive(Debug, Serialize)]
+pub struct Payment {
+    pub id: i64,
+    pub sale_id: i64,
+    pub method: String,
+    pub amount: f64,
+    pub drawer_session_id: Option<i64>,
+    pub timestamp: String,
+}
+
+// Record a tender against a sale. The payment is attached to the open drawer
+// session of the sale's employee (if any) so cash can be reconciled at close.
+pub fn add_payment(conn: &Connection, sale_id: i64, method: &str, amount: f64) -> Result<i64> {
+    let method = validation::name("method", method)?;
+    // Refund tenders are negative
+    validation::price("amount", amount.abs())?;
+    conn.execute(
+        "INSERT INTO payments (sale_id, method, amount, drawer_session_id)
+        VALUES (?1, ?2, ?3, (
+            SELECT d.id FROM drawer_sessions d
+            JOIN sales s ON s.employee_id = d.employee_id
+            WHERE s.id = ?1 AND d.closed_at IS NULL
+        ))",
+        params![sale_id, method, amount],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+pub fn get_sale_payments(conn: &Connection, sale_id: i64) -> Result<Vec<Payment>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, sale_id, method, amount, drawer_session_id, timestamp
+        FROM payments WHERE sale_id = ?1 ORDER BY id",
+    )?;
+    let rows = stmt.query_map(params![sale_id], |row| {
+        Ok(Payment {
+            id: row.get(0)?,
+            sale_id: row.get(1)?,
+            method: row.get(2)?,
+            amount: row.get(3)?,
+            drawer_session_id: row.get(4)?,
+            timestamp: row.get(5)?,
+        })
+    })?;
+
+    let mut payments = Vec::new();
+    for payment in rows {
+        payments.push(payment?);
+    }
+    Ok(payments)
+}
@@ -0,0 +1,93 @@
+use rusqlite::{params, Connection};
+use serde::Serialize;
+
+use crate::error::Result;
+
+#[derive(Debug, Serialize)]
+pub struct EmployeePerformance {
+    pub employee_id: i32,
+    pub employee_name: String,
+    // Completed, non-voided sales after discounts
+    pub net_sales: f64,
+    pub transactions: i64,
+    pub average_ticket: f64,
+    pub items_per_sale: f64,
+    pub void_count: i64,
+    pub voids: f64,
+    pub refund_count: i64,
+    pub refunds: f64,
+    pub discount_count: i64,
+    pub discounts: f64,
+    pub tips: f64,
+}
+
+// Per-employee figures for sales completed between two dates. Refunds are
+// counted against the employee who issued them, and tips against the
+// employee they were given to, who need not have rung up the sale.
+pub fn get_employee_performance(
+    conn: &Connection,
+    start_date: Option<&str>,
+    end_date: Option<&str>,
+) -> Result<Vec<EmployeePerformance>> {
+    let start = start_date.unwrap_or("1970-01-01");
+    let end = end_date.unwrap_or("9999-12-31");
+
+    let mut stmt = conn.prepare(
+        "WITH period AS (
+            SELECT * FROM sales
+            WHERE receipt_number IS NOT NULL AND date(COALESCE(completed_at, timestamp)) BETWEEN ?1 AND ?2
+        ),
+        tipped AS (
+            SELECT t.employee_id, SUM(t.amount) AS amount
+            FROM tips t JOIN period p ON p.id = t.sale_id
+            WHERE p.voided = 0
+            GROUP BY t.employee_id
+        )
+        SELECT e.id, e.name,
+            IFNULL(SUM(CASE WHEN p.voided = 0 AND p.refund_of IS NULL THEN p.total END), 0),
+            COUNT(CASE WHEN p.voided = 0 AND p.refund_of IS NULL THEN 1 END),
+            IFNULL(SUM(CASE WHEN p.voided = 0 AND p.refund_of IS NULL THEN
+                (SELECT IFNULL(SUM(CASE WHEN unit = 'each' THEN quantity ELSE 1 END), 0)
+                    FROM sale_items WHERE sale_id = p.id) END), 0),
+            COUNT(CASE WHEN p.voided = 1 THEN 1 END),
+            IFNULL(SUM(CASE WHEN p.voided = 1 THEN p.total END), 0),
+            COUNT(CASE WHEN p.voided = 0 AND p.refund_of IS NOT NULL THEN 1 END),
+            IFNULL(-SUM(CASE WHEN p.voided = 0 AND p.refund_of IS NOT NULL THEN p.total END), 0),
+            COUNT(CASE WHEN p.voided = 0 AND p.refund_of IS NULL AND p.discount != 0 THEN 1 END),
+            IFNULL(SUM(CASE WHEN p.voided = 0 AND p.refund_of IS NULL THEN p.discount END), 0),
+            IFNULL(t.amount, 0)
+        FROM employees e
+        LEFT JOIN period p ON p.employee_id = e.id
+        LEFT JOIN tipped t ON t.employee_id = e.id
+        GROUP BY e.id
+        HAVING COUNT(p.id) > 0 OR t.amount IS NOT NULL
+        ORDER BY 3 DESC",
+    )?;
+    let rows = stmt.query_map(params![start, end], |row| {
+        let net_sales: f64 = row.get(2)?;
+        let transactions: i64 = row.get(3)?;
+        let items: f64 = row.get(4)?;
+        let per_sale = |value: f64| if transactions > 0 { value / transactions as f64 } else { 0.0 };
+        Ok(EmployeePerformance {
+            employee_id: row.get(0)?,
+            employee_name: row.get(1)?,
+            net_sales,
+            transactions,
+            average_ticket: per_sale(net_sales),
+            items_per_sale: per_sale(items),
+            void_count: row.get(5)?,
+            voids: row.get(6)?,
+            refund_count: row.get(7)?,
+            refunds: row.get(8)?,
+            discount_count: row.get(9)?,
+            discounts: row.get(10)?,
+            tips: row.get(11)?,
+        })
+    })?;
+
+    let mut performance = Vec::new();
+    for employee in rows {
+        performance.push(employee?);
+    }
+    Ok(performance)
+}
@@ -0,0 +1,281 @@
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+
+use crate::error::{AppError, Result};
+use crate::models::employee::find_pin_owner;
+use crate::models::lockout::{check_login, login_failed, login_succeeded};
+use crate::models::price::apply_due_prices;
+use crate::models::product_barcode::lookup_barcode;
+use crate::models::session::{session_actor, Actor};
+use crate::models::settings::get_setting_f64;
+use crate::validation;
+
+// Permission catalog. Roles are made of these; an employee without a role
+// has none of them. discount_over_limit covers discounts above the
+// `discount_limit_percent` setting (default 10) of the sale.
+pub const PERMISSIONS: &[(&str, &str)] = &[
+    ("void_item", "Void items and sales"),
+    ("refund", "Refund sales"),
+    ("discount_over_limit", "Apply discounts above the discount limit"),
+    ("open_drawer_no_sale", "Open the cash drawer without a sale"),
+    ("edit_prices", "Sell at a price other than the product price"),
+    ("view_reports", "View reports"),
+    ("manage_employees", "Manage employees"),
+];
+
+// A manager override can only be used once, within this many minutes
+const OVERRIDE_MINUTES: i64 = 5;
+
+#[derive(Debug, Serialize)]
+pub struct Permission {
+    pub code: String,
+    pub description: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Role {
+    pub id: i64,
+    pub name: String,
+    pub permissions: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ManagerOverride {
+    // Pass to the guarded command; None if the PIN was wrong or its owner
+    // lacks the permission
+    pub override_id: Option<i64>,
+    pub manager_name: Option<String>,
+    pub locked_for: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct OverrideEntry {
+    pub id: i64,
+    pub permission: String,
+    pub employee_id: i32,
+    pub employee_name: String,
+    pub manager_id: i32,
+    pub manager_name: String,
+    pub created_at: String,
+    pub used_at: Option<String>,
+}
+
+pub fn get_permission_catalog() -> Vec<Permission> {
+    PERMISSIONS
+        .iter()
+        .map(|(code, description)| Permission {
+            code: code.to_string(),
+            description: description.to_string(),
+        })
+        .collect()
+}
+
+// Create (id None) or update a role. Codes not in the catalog are ignored.
+pub fn save_role(conn: &Connection, id: Option<i64>, name: &str, permissions: &[String]) -> Result<i64> {
+    let name = validation::name("name", name)?;
+    let tx = conn.unchecked_transaction()?;
+    let id = match id {
+        Some(id) => {
+            tx.execute("UPDATE roles SET name = ?1 WHERE id = ?2", params![name, id])?;
+            tx.execute("DELETE FROM role_permissions WHERE role_id = ?1", params![id])?;
+            id
+        }
+        None => {
+            tx.execute("INSERT INTO roles (name) VALUES (?1)", params![name])?;
+            tx.last_insert_rowid()
+        }
+    };
+    for permission in permissions {
+        if PERMISSIONS.iter().any(|(code, _)| code == permission) {
+            tx.execute(
+                "INSERT OR IGNORE INTO role_permissions (role_id, permission) VALUES (?1, ?2)",
+                params![id, permission],
+            )?;
+        }
+    }
+    tx.commit()?;
+    Ok(id)
+}
+
+// Employees holding the role are left without one
+pub fn delete_role(conn: &Connection, id: i64) -> Result<()> {
+    let tx = conn.unchecked_transaction()?;
+    tx.execute("UPDATE employees SET role_id = NULL WHERE role_id = ?1", params![id])?;
+    tx.execute("DELETE FROM roles WHERE id = ?1", params![id])?;
+    Ok(tx.commit()?)
+}
+
+pub fn get_roles(conn: &Connection) -> Result<Vec<Role>> {
+    let mut stmt = conn.prepare("SELECT id, name FROM roles ORDER BY name")?;
+    let rows = stmt.query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)))?;
+
+    let mut roles = Vec::new();
+    for row in rows {
+        let (id, name) = row?;
+        let mut stmt = conn.prepare("SELECT permission FROM role_permissions WHERE role_id = ?1 ORDER BY permission")?;
+        let permissions = stmt
+            .query_map(params![id], |row| row.get(0))?
+            .collect::<rusqlite::Result<Vec<String>>>()?;
+        roles.push(Role { id, name, permissions });
+    }
+    Ok(roles)
+}
+
+pub fn set_employee_role(conn: &Connection, employee_id: i32, role_id: Option<i64>) -> Result<usize> {
+    Ok(conn.execute(
+        "UPDATE employees SET role_id = ?1 WHERE id = ?2",
+        params![role_id, employee_id],
+    )?)
+}
+
+pub fn get_employee_permissions(conn: &Connection, employee_id: i32) -> Result<Vec<String>> {
+    let mut stmt = conn.prepare(
+        "SELECT rp.permission FROM employees e JOIN role_permissions rp ON rp.role_id = e.role_id
+        WHERE e.id = ?1 ORDER BY rp.permission",
+    )?;
+    let rows = stmt.query_map(params![employee_id], |row| row.get(0))?;
+    Ok(rows.collect::<rusqlite::Result<_>>()?)
+}
+
+pub fn has_permission(conn: &Connection, employee_id: i32, permission: &str) -> Result<bool> {
+    Ok(conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM employees e JOIN role_permissions rp ON rp.role_id = e.role_id
+            WHERE e.id = ?1 AND rp.permission = ?2)",
+        params![employee_id, permission],
+        |row| row.get(0),
+    )?)
+}
+
+// A supervisor enters their PIN at the cashier's terminal to allow one
+// action for that cashier. Wrong PINs count towards the terminal and shared
+// PIN lockouts, and a correct one clears only the terminal's.
+pub fn manager_override(
+    conn: &Connection,
+    pin: &str,
+    permission: &str,
+    employee_id: i32,
+    terminal: &str,
+) -> Result<ManagerOverride> {
+    let locked = check_login(conn, "pin", None, terminal)?;
+    if locked > 0 {
+        return Ok(ManagerOverride { override_id: None, manager_name: None, locked_for: locked });
+    }
+
+    let Some(manager) = find_pin_owner(conn, pin)? else {
+        let locked = login_failed(conn, "pin", None, terminal)?;
+        return Ok(ManagerOverride { override_id: None, manager_name: None, locked_for: locked });
+    };
+    login_succeeded(conn, "pin", None, terminal)?;
+
+    if !has_permission(conn, manager.id, permission)? {
+        return Ok(ManagerOverride { override_id: None, manager_name: Some(manager.name), locked_for: 0 });
+    }
+    conn.execute(
+        "INSERT INTO overrides (permission, employee_id, manager_id) VALUES (?1, ?2, ?3)",
+        params![permission, employee_id, manager.id],
+    )?;
+    Ok(ManagerOverride {
+        override_id: Some(conn.last_insert_rowid()),
+        manager_name: Some(manager.name),
+        locked_for: 0,
+    })
+}
+
+// Whether the employee may perform the action: through their own role, or
+// by using up an unused, recent manager override given for it.
+pub fn authorize(conn: &Connection, employee_id: i32, permission: &str, override_id: Option<i64>) -> Result<bool> {
+    if has_permission(conn, employee_id, permission)? {
+        return Ok(true);
+    }
+    let Some(override_id) = override_id else { return Ok(false) };
+    let used = conn.execute(
+        "UPDATE overrides SET used_at = CURRENT_TIMESTAMP
+        WHERE id = ?1 AND permission = ?2 AND employee_id = ?3 AND used_at IS NULL
+            AND created_at > datetime('now', ?4)",
+        params![override_id, permission, employee_id, format!("-{} minutes", OVERRIDE_MINUTES)],
+    )?;
+    Ok(used > 0)
+}
+
+// Guard for commands that need a signed-in user: the admin may do anything,
+// an employee needs the permission through their role or an override.
+// Returns who is acting.
+pub fn require_permission(
+    conn: &Connection,
+    session: Option<&str>,
+    permission: &str,
+    override_id: Option<i64>,
+) -> Result<Actor> {
+    let actor = session_actor(conn, session)?;
+    if let Actor::Employee(employee_id) = actor {
+        if !authorize(conn, employee_id, permission, override_id)? {
+            return Err(AppError::unauthorized(permission));
+        }
+    }
+    Ok(actor)
+}
+
+// Same as `authorize`, for the employee who rang up the sale
+pub fn authorize_for_sale(conn: &Connection, sale_id: i64, permission: &str, override_id: Option<i64>) -> Result<bool> {
+    let employee_id: Option<i32> = conn
+        .query_row("SELECT employee_id FROM sales WHERE id = ?1", params![sale_id], |row| row.get(0))
+        .optional()?;
+    match employee_id {
+        Some(employee_id) => authorize(conn, employee_id, permission, override_id),
+        None => Ok(false),
+    }
+}
+
+// Whether a discount on the sale goes past `discount_limit_percent`
+pub fn discount_over_limit(conn: &Connection, sale_id: i64, discount: f64) -> Result<bool> {
+    let limit = get_setting_f64(conn, "discount_limit_percent", 10.0)?;
+    let subtotal: f64 = conn.query_row(
+        "SELECT IFNULL(SUM(ROUND(quantity * (price + extra_amount), 2)), 0) FROM sale_items WHERE sale_id = ?1",
+        params![sale_id],
+        |row| row.get(0),
+    )?;
+    Ok(discount > subtotal * limit / 100.0)
+}
+
+// Whether selling the product at `price` differs from its catalog price, or
+// from the price carried by the scanned `barcode` (price and weight codes).
+// Scheduled prices that have fallen due are applied first.
+pub fn is_price_edit(conn: &Connection, product_id: i32, price: f64, barcode: Option<&str>) -> Result<bool> {
+    apply_due_prices(conn)?;
+    let expected = match barcode.map(|code| lookup_barcode(conn, code)).transpose()?.flatten() {
+        Some(scanned) if scanned.product.id == product_id => Some(scanned.price),
+        _ => conn
+            .query_row("SELECT price FROM products WHERE id = ?1", params![product_id], |row| row.get(0))
+            .optional()?,
+    };
+    Ok(expected.is_some_and(|expected| (expected - price).abs() >= 0.005))
+}
+
+pub fn get_overrides(conn: &Connection, limit: i64) -> Result<Vec<OverrideEntry>> {
+    let mut stmt = conn.prepare(
+        "SELECT o.id, o.permission, o.employee_id, IFNULL(e.name, ''), o.manager_id, IFNULL(m.name, ''),
+            o.created_at, o.used_at
+        FROM overrides o
+        LEFT JOIN employees e ON o.employee_id = e.id
+        LEFT JOIN employees m ON o.manager_id = m.id
+        ORDER BY o.id DESC LIMIT ?1",
+    )?;
+    let rows = stmt.query_map(params![limit], |row| {
+        Ok(OverrideEntry {
+            id: row.get(0)?,
+            permission: row.get(1)?,
+            employee_id: row.get(2)?,
+            employee_name: row.get(3)?,
+            manager_id: row.get(4)?,
+            manager_name: row.get(5)?,
+            created_at: row.get(6)?,
+            used_at: row.get(7)?,
+        })
+    })?;
+
+    let mut overrides = Vec::new();
+    for entry in rows {
+        overrides.push(entry?);
+    }
+    Ok(overrides)
+}
@@ -0,0 +1,116 @@
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+
+use crate::error::{AppError, Result};
+use crate::validation;
+
+// Every price a product has had or is scheduled to have, each in force from
+// `effective_at` (UTC) until the next one. `products.price` is the current
+// price; scheduled prices are copied into it once they fall due.
+#[derive(Debug, Serialize)]
+pub struct PriceEntry {
+    pub id: i64,
+    pub product_id: i32,
+    pub price: f64,
+    pub effective_at: String,
+    // Not in force yet
+    pub scheduled: bool,
+}
+
+// Record the price set on a product at the current time
+pub(crate) fn record_price(conn: &Connection, product_id: i64, price: f64) -> Result<()> {
+    conn.execute(
+        "INSERT INTO product_prices (product_id, price, effective_at) VALUES (?1, ?2, CURRENT_TIMESTAMP)",
+        params![product_id, price],
+    )?;
+    Ok(())
+}
+
+// Schedule a price from `effective_at` (any date and time SQLite reads,
+// e.g. "2025-07-01 06:00:00" or an ISO string with a time zone)
+pub fn schedule_price(conn: &Connection, product_id: i32, price: f64, effective_at: &str) -> Result<i64> {
+    let price = validation::price("price", price)?;
+    let (effective_at, future): (Option<String>, bool) = conn.query_row(
+        "SELECT datetime(?1), IFNULL(datetime(?1) > datetime('now'), 0)",
+        params![effective_at],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )?;
+    let effective_at = effective_at
+        .ok_or_else(|| AppError::validation("effective_at", "invalid_format", "not a valid date and time"))?;
+    if !future {
+        return Err(AppError::validation("effective_at", "out_of_range", "scheduled prices must start in the future"));
+    }
+
+    conn.query_row("SELECT 1 FROM products WHERE id = ?1", params![product_id], |_| Ok(()))
+        .optional()?
+        .ok_or_else(|| AppError::not_found("product"))?;
+    conn.execute(
+        "INSERT INTO product_prices (product_id, price, effective_at) VALUES (?1, ?2, ?3)",
+        params![product_id, price, effective_at],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+// Only prices that have not taken effect can be cancelled
+pub fn cancel_scheduled_price(conn: &Connection, id: i64) -> Result<usize> {
+    Ok(conn.execute(
+        "DELETE FROM product_prices WHERE id = ?1 AND effective_at > datetime('now')",
+        params![id],
+    )?)
+}
+
+// Copy scheduled prices that have fallen due into `products`; returns the
+// number of products repriced. A price set by hand after the scheduled time
+// wins, as it is the later entry.
+pub fn apply_due_prices(conn: &Connection) -> Result<usize> {
+    Ok(conn.execute(
+        "UPDATE products SET
+            price = (SELECT price FROM product_prices
+                WHERE product_id = products.id AND effective_at <= datetime('now')
+                ORDER BY effective_at DESC, id DESC LIMIT 1),
+            price_updated_at = (SELECT MAX(effective_at) FROM product_prices
+                WHERE product_id = products.id AND effective_at <= datetime('now'))
+        WHERE EXISTS (SELECT 1 FROM product_prices
+            WHERE product_id = products.id AND effective_at <= datetime('now')
+                AND effective_at > IFNULL(products.price_updated_at, '1970-01-01 00:00:00'))",
+        [],
+    )?)
+}
+
+pub fn get_price_history(conn: &Connection, product_id: i32) -> Result<Vec<PriceEntry>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, product_id, price, effective_at, effective_at > datetime('now')
+        FROM product_prices WHERE product_id = ?1
+        ORDER BY effective_at DESC, id DESC",
+    )?;
+    let rows = stmt.query_map(params![product_id], |row| {
+        Ok(PriceEntry {
+            id: row.get(0)?,
+            product_id: row.get(1)?,
+            price: row.get(2)?,
+            effective_at: row.get(3)?,
+            scheduled: row.get(4)?,
+        })
+    })?;
+
+    let mut entries = Vec::new();
+    for entry in rows {
+        entries.push(entry?);
+    }
+    Ok(entries)
+}
+
+// Price in force at `at`; a bare date ("YYYY-MM-DD") means the end of that
+// day. None if the product had no price yet.
+pub fn price_at(conn: &Connection, product_id: i32, at: &str) -> Result<Option<f64>> {
+    Ok(conn
+        .query_row(
+            "SELECT price FROM product_prices
+            WHERE product_id = ?1
+                AND effective_at <= CASE WHEN length(?2) = 10 THEN ?2 || ' 23:59:59' ELSE datetime(?2) END
+            ORDER BY effective_at DESC, id DESC LIMIT 1",
+            params![product_id, at],
+            |row| row.get(0),
+        )
+        .optional()?)
+}
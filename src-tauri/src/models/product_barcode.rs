@@ -0,0 +1,183 @@
+use chrono::Local;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+
+use crate::barcode::{self, Embedded};
+use crate::error::{AppError, Result};
+use crate::models::availability::mark_available;
+use crate::models::price::apply_due_prices;
+use crate::models::products::{product_from_row, Product, PRODUCT_COLUMNS};
+use crate::models::settings::get_setting;
+use crate::unit::Unit;
+use crate::validation;
+
+// Extra codes for a product besides `products.barcode`, e.g. the code on a
+// six-pack selling six units
+#[derive(Debug, Serialize)]
+pub struct ProductBarcode {
+    pub id: i64,
+    pub product_id: i32,
+    pub barcode: String,
+    pub pack_quantity: i32,
+}
+
+// What a scanned code puts on the sale
+#[derive(Debug, Serialize)]
+pub struct BarcodeMatch {
+    pub product: Product,
+    pub barcode: String,
+    // "product" (the product's own code), "pack", "price" or "weight"
+    pub kind: String,
+    // In the product's unit; the weight itself for weight codes of products
+    // sold by weight
+    pub quantity: f64,
+    // Unit price for the sale line; for price codes, and weight codes of
+    // products sold by the item, the price printed on the label
+    pub price: f64,
+    // Kilograms, for weight codes
+    pub weight: Option<f64>,
+}
+
+// Two-digit prefixes of price-embedded and weight-embedded codes, from the
+// `price_barcode_prefixes` (default "22") and `weight_barcode_prefixes`
+// (default "21") settings, comma separated
+pub fn embedded_prefixes(conn: &Connection) -> Result<(Vec<String>, Vec<String>)> {
+    let list = |key: &str, default: &str| -> Result<Vec<String>> {
+        let value = get_setting(conn, key)?.unwrap_or_else(|| default.to_string());
+        Ok(value
+            .split(',')
+            .map(str::trim)
+            .filter(|p| p.len() == 2 && p.bytes().all(|b| b.is_ascii_digit()))
+            .map(str::to_string)
+            .collect())
+    };
+    Ok((list("price_barcode_prefixes", "22")?, list("weight_barcode_prefixes", "21")?))
+}
+
+// Whether a product other than `product_id` already uses the code
+pub(crate) fn barcode_taken(conn: &Connection, code: &str, product_id: Option<i32>) -> Result<bool> {
+    Ok(conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM products WHERE barcode = ?1 AND id IS NOT ?2)
+            OR EXISTS(SELECT 1 FROM product_barcodes WHERE barcode = ?1 AND product_id IS NOT ?2)",
+        params![code, product_id],
+        |row| row.get(0),
+    )?)
+}
+
+pub(crate) fn duplicate_barcode() -> AppError {
+    AppError::Conflict {
+        field: Some("barcode".to_string()),
+        code: "duplicate".to_string(),
+        message: "barcode is already used by another product".to_string(),
+    }
+}
+
+pub fn add_product_barcode(conn: &Connection, product_id: i32, code: &str, pack_quantity: i32) -> Result<i64> {
+    let code = validation::barcode(code)?;
+    validation::quantity(f64::from(pack_quantity), Unit::Each)?;
+    if barcode_taken(conn, code, None)? {
+        return Err(duplicate_barcode());
+    }
+    conn.execute(
+        "INSERT INTO product_barcodes (product_id, barcode, pack_quantity) VALUES (?1, ?2, ?3)",
+        params![product_id, code, pack_quantity],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+pub fn delete_product_barcode(conn: &Connection, id: i64) -> Result<usize> {
+    Ok(conn.execute("DELETE FROM product_barcodes WHERE id = ?1", params![id])?)
+}
+
+pub fn get_product_barcodes(conn: &Connection, product_id: i32) -> Result<Vec<ProductBarcode>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, product_id, barcode, pack_quantity FROM product_barcodes
+        WHERE product_id = ?1 ORDER BY pack_quantity, id",
+    )?;
+    let rows = stmt.query_map(params![product_id], |row| {
+        Ok(ProductBarcode {
+            id: row.get(0)?,
+            product_id: row.get(1)?,
+            barcode: row.get(2)?,
+            pack_quantity: row.get(3)?,
+        })
+    })?;
+
+    let mut barcodes = Vec::new();
+    for barcode in rows {
+        barcodes.push(barcode?);
+    }
+    Ok(barcodes)
+}
+
+// The product and pack size for an exact code (both lookups use the unique
+// indexes), with the product marked available or not as in `get_products`
+fn find_code(conn: &Connection, code: &str) -> Result<Option<(Product, Option<i32>)>> {
+    let found = conn
+        .query_row(
+            &format!(
+                "SELECT {0}, NULL FROM products p WHERE p.barcode = ?1 AND p.archived_at IS NULL
+                UNION ALL
+                SELECT {0}, b.pack_quantity
+                FROM product_barcodes b JOIN products p ON p.id = b.product_id
+                WHERE b.barcode = ?1 AND p.archived_at IS NULL
+                LIMIT 1",
+                PRODUCT_COLUMNS
+            ),
+            params![code],
+            |row| Ok((product_from_row(row)?, row.get(11)?)),
+        )
+        .optional()?;
+    let Some((product, pack)) = found else {
+        return Ok(None);
+    };
+    let mut products = [product];
+    mark_available(conn, &mut products, Local::now().naive_local())?;
+    let [product] = products;
+    Ok(Some((product, pack)))
+}
+
+// Resolve a scanned code. Exact codes come first; otherwise a price or
+// weight code is matched to the product holding its template code (the same
+// code with a zero value). Returns None for unknown codes. Prices are
+// current, with scheduled prices that have fallen due applied.
+pub fn lookup_barcode(conn: &Connection, code: &str) -> Result<Option<BarcodeMatch>> {
+    let code = code.trim();
+    apply_due_prices(conn)?;
+    if let Some((product, pack)) = find_code(conn, code)? {
+        return Ok(Some(BarcodeMatch {
+            kind: if pack.is_some() { "pack" } else { "product" }.to_string(),
+            quantity: f64::from(pack.unwrap_or(1)),
+            price: product.price,
+            weight: None,
+            barcode: code.to_string(),
+            product,
+        }));
+    }
+
+    let (price_prefixes, weight_prefixes) = embedded_prefixes(conn)?;
+    let Some(embedded) = barcode::parse_embedded(code, &price_prefixes, &weight_prefixes) else {
+        return Ok(None);
+    };
+    let Some((product, _)) = find_code(conn, &embedded.template)? else {
+        return Ok(None);
+    };
+    let (kind, quantity, price, weight) = match embedded.kind {
+        Embedded::Price => ("price", 1.0, embedded.value as f64 / 100.0, None),
+        Embedded::Weight => {
+            let kg = embedded.value as f64 / 1000.0;
+            match Unit::parse(&product.unit).and_then(|unit| unit.quantity_from_kg(kg)) {
+                Some(quantity) => ("weight", quantity, product.price, Some(kg)),
+                None => ("weight", 1.0, (product.price * kg * 100.0).round() / 100.0, Some(kg)),
+            }
+        }
+    };
+    Ok(Some(BarcodeMatch {
+        product,
+        barcode: code.to_string(),
+        kind: kind.to_string(),
+        quantity,
+        price,
+        weight,
+    }))
+}
@@ -1,5 +1,13 @@
-use rusqlite::{params, Connection, Result};
-use chrono::Utc;
+use chrono::Local;
+use rusqlite::{params, Connection, OptionalExtension, Row};
+
+use crate::barcode;
+use crate::error::{AppError, Result};
+use crate::models::availability::mark_available;
+use crate::models::price::{apply_due_prices, record_price};
+use crate::models::product_barcode::{barcode_taken, duplicate_barcode, embedded_prefixes};
+use crate::models::settings::get_setting;
+use crate::validation;
 
 #[derive(Debug, serde::Serialize)]
 pub struct Product {
@@ -7,6 +15,43 @@ pub struct Product {
     pub name: String,
     pub price: f64,
     pub barcode: String,
+    pub net_content: Option<f64>,
+    pub net_content_unit: Option<String>,
+    // "each", "kg", "g" or "l"; the price is per one of these
+    pub unit: String,
+    // Set on variants (sizes, flavours...) to the product they belong to
+    pub parent_id: Option<i32>,
+    pub category: Option<String>,
+    // 86'd for today, and sellable right now; worked out when products are
+    // listed for sale (see `availability::mark_available`)
+    pub sold_out: bool,
+    pub available: bool,
+    #[serde(skip)]
+    pub(crate) sold_out_on: Option<String>,
+    // Archived products are kept for the sales that reference them but are
+    // no longer sold
+    pub archived: bool,
+}
+
+pub(crate) const PRODUCT_COLUMNS: &str = "p.id, p.name, p.price, p.barcode, p.net_content, p.net_content_unit, p.unit, \
+    p.parent_id, p.category, p.sold_out_on, p.archived_at IS NOT NULL";
+
+pub(crate) fn product_from_row(row: &Row) -> rusqlite::Result<Product> {
+    Ok(Product {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        price: row.get(2)?,
+        barcode: row.get(3)?,
+        net_content: row.get(4)?,
+        net_content_unit: row.get(5)?,
+        unit: row.get(6)?,
+        parent_id: row.get(7)?,
+        category: row.get(8)?,
+        sold_out: false,
+        available: true,
+        sold_out_on: row.get(9)?,
+        archived: row.get(10)?,
+    })
 }
 
 pub fn create_product_table(conn: &Connection) -> Result<()> {
@@ -22,53 +67,229 @@ pub fn create_product_table(conn: &Connection) -> Result<()> {
     Ok(())
 }
 
-pub fn add_product(conn: &Connection, name: &str, price: f64, barcode: Option<&str>) -> Result<usize> {
+pub fn add_product(
+    conn: &Connection,
+    name: &str,
+    price: f64,
+    barcode: Option<&str>,
+    unit: Option<&str>,
+) -> Result<i64> {
+    let name = validation::name("name", name)?;
+    let price = validation::price("price", price)?;
+    let unit = validation::unit(unit.unwrap_or("each"))?;
     let barcode_val = if let Some(bc) = barcode {
         if !bc.trim().is_empty() {
+            let bc = validation::barcode(bc)?;
+            if barcode_taken(conn, bc, None)? {
+                return Err(duplicate_barcode());
+            }
             bc.to_string()
         } else {
-            generate_barcode()
+            generate_barcode(conn)?
         }
     } else {
-        generate_barcode()
+        generate_barcode(conn)?
     };
 
     conn.execute(
-        "INSERT INTO products (name, price, barcode) VALUES (?1, ?2, ?3)",
-        params![name, price, barcode_val],
-    )
+        "INSERT INTO products (name, price, barcode, unit, price_updated_at)
+        VALUES (?1, ?2, ?3, ?4, CURRENT_TIMESTAMP)",
+        params![name, price, barcode_val, unit.as_str()],
+    )?;
+    let id = conn.last_insert_rowid();
+    record_price(conn, id, price)?;
+    Ok(id)
 }
 
-fn generate_barcode() -> String {
-    let timestamp = Utc::now().timestamp_millis();
-    format!("P{}", timestamp)
+// Next in-store EAN-13 code: a two-digit prefix from the
+// `barcode_prefix_from`..`barcode_prefix_to` settings (GS1 keeps 20-29 for
+// in-store use; default 20-20), a 10-digit item number and the check digit.
+// Prefixes set aside for price and weight codes are skipped. Item numbers
+// follow the highest one already used under the prefix, so a new code never
+// repeats an existing one.
+pub fn generate_barcode(conn: &Connection) -> Result<String> {
+    let setting = |key: &str| -> Result<Option<u32>> {
+        Ok(get_setting(conn, key)?
+            .and_then(|v| v.trim().parse().ok())
+            .filter(|prefix| (20..=29).contains(prefix)))
+    };
+    let from = setting("barcode_prefix_from")?.unwrap_or(20);
+    let to = setting("barcode_prefix_to")?.unwrap_or(from).max(from);
+
+    let (price_prefixes, weight_prefixes) = embedded_prefixes(conn)?;
+    for prefix in from..=to {
+        let reserved = prefix.to_string();
+        if price_prefixes.contains(&reserved) || weight_prefixes.contains(&reserved) {
+            continue;
+        }
+        let last: Option<String> = conn.query_row(
+            "SELECT MAX(barcode) FROM (
+                SELECT barcode FROM products UNION ALL SELECT barcode FROM product_barcodes
+            ) WHERE barcode GLOB ?1",
+            params![format!("{}{}", prefix, "[0-9]".repeat(11))],
+            |row| row.get(0),
+        )?;
+        let next = match last {
+            Some(code) => code[2..12].parse::<u64>().unwrap_or(0) + 1,
+            None => 1,
+        };
+        if next <= 9_999_999_999 {
+            if let Some(code) = barcode::ean13(&format!("{}{:010}", prefix, next)) {
+                return Ok(code);
+            }
+        }
+    }
+    Err(AppError::Conflict {
+        field: Some("barcode".to_string()),
+        code: "exhausted".to_string(),
+        message: format!("no in-store barcodes left under prefixes {}-{}", from, to),
+    })
 }
 
-pub fn get_products(conn: &Connection) -> Result<Vec<Product>> {
-    let mut stmt = conn.prepare("SELECT id, name, price, barcode FROM products")?;
-    let rows = stmt.query_map([], |row| {
-        Ok(Product {
-            id: row.get(0)?,
-            name: row.get(1)?,
-            price: row.get(2)?,
-            barcode: row.get(3)?,
-        })
-    })?;
+// Products as the POS lists them: scheduled prices that have fallen due are
+// applied first, and each product says whether it can be sold right now.
+// Archived products are left out unless `include_archived` is set.
+pub fn get_products(conn: &Connection, include_archived: bool) -> Result<Vec<Product>> {
+    apply_due_prices(conn)?;
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM products p WHERE ?1 OR p.archived_at IS NULL",
+        PRODUCT_COLUMNS
+    ))?;
+    let rows = stmt.query_map(params![include_archived], product_from_row)?;
 
     let mut products = Vec::new();
     for product in rows {
         products.push(product?);
     }
+    mark_available(conn, &mut products, Local::now().naive_local())?;
     Ok(products)
 }
 
-pub fn update_product(conn: &Connection, id: i32, name: &str, price: f64, barcode: &str) -> Result<usize> {
-    conn.execute(
-        "UPDATE products SET name = ?1, price = ?2, barcode = ?3 WHERE id = ?4",
-        params![name, price, barcode, id],
-    )
+pub fn update_product(conn: &Connection, id: i32, name: &str, price: f64, barcode: &str, unit: &str) -> Result<usize> {
+    let name = validation::name("name", name)?;
+    let price = validation::price("price", price)?;
+    let unit = validation::unit(unit)?;
+    let barcode = validation::barcode(barcode)?;
+    if barcode_taken(conn, barcode, Some(id))? {
+        return Err(duplicate_barcode());
+    }
+    // Shelf labels are reprinted for products whose price moved, so only a
+    // real price change touches `price_updated_at` and the price history
+    let tx = conn.unchecked_transaction()?;
+    let old_price: Option<f64> = tx
+        .query_row("SELECT price FROM products WHERE id = ?1", params![id], |row| row.get(0))
+        .optional()?;
+    let updated = tx.execute(
+        "UPDATE products SET name = ?1, price = ?2, barcode = ?3, unit = ?4,
+            price_updated_at = CASE WHEN price = ?2 THEN price_updated_at ELSE CURRENT_TIMESTAMP END
+        WHERE id = ?5",
+        params![name, price, barcode, unit.as_str(), id],
+    )?;
+    if old_price.is_some_and(|old| old != price) {
+        record_price(&tx, i64::from(id), price)?;
+    }
+    tx.commit()?;
+    Ok(updated)
 }
 
+// Net content of a packed product (e.g. 400 g), used for the unit price on
+// shelf labels. `None` clears it.
+pub fn set_product_net_content(conn: &Connection, id: i32, net_content: Option<f64>, unit: Option<&str>) -> Result<usize> {
+    let content = match (net_content, unit) {
+        (Some(amount), Some(unit)) => {
+            if !["g", "kg", "ml", "l"].contains(&unit) {
+                return Err(AppError::validation("net_content_unit", "invalid_format", "unit must be g, kg, ml or l"));
+            }
+            Some((validation::amount("net_content", amount)?, unit))
+        }
+        _ => None,
+    };
+    Ok(conn.execute(
+        "UPDATE products SET net_content = ?1, net_content_unit = ?2 WHERE id = ?3",
+        params![content.map(|c| c.0), content.map(|c| c.1), id],
+    )?)
+}
+
+// Products whose price changed at or after `since` ("YYYY-MM-DD" or a full
+// timestamp), for reprinting their shelf labels
+pub fn get_price_changed_products(conn: &Connection, since: &str) -> Result<Vec<Product>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM products p WHERE p.price_updated_at >= ?1 AND p.archived_at IS NULL ORDER BY p.name",
+        PRODUCT_COLUMNS
+    ))?;
+    let rows = stmt.query_map(params![since], product_from_row)?;
+
+    let mut products = Vec::new();
+    for product in rows {
+        products.push(product?);
+    }
+    Ok(products)
+}
+
+// Archive a product and its variants: they drop out of the POS but stay
+// linked to their past sales
+pub fn archive_product(conn: &Connection, id: i32) -> Result<usize> {
+    Ok(conn.execute(
+        "UPDATE products SET archived_at = CURRENT_TIMESTAMP WHERE (id = ?1 OR parent_id = ?1) AND archived_at IS NULL",
+        params![id],
+    )?)
+}
+
+pub fn restore_product(conn: &Connection, id: i32) -> Result<usize> {
+    Ok(conn.execute(
+        "UPDATE products SET archived_at = NULL WHERE id = ?1 OR parent_id = ?1",
+        params![id],
+    )?)
+}
+
+// Tables holding a product's own configuration, removed along with it
+const PRODUCT_DEPENDENTS: [&str; 6] = [
+    "variant_attributes",
+    "stock_movements",
+    "bundle_options",
+    "product_prices",
+    "availability_rules",
+    "product_barcodes",
+];
+
+// Only products never sold, alone, as a variant or in a bundle, can be
+// deleted; the others are archived instead, so reports keep the link.
+// The product goes together with its variants, barcodes, prices, bundle
+// groups, availability rules and stock adjustments, and is dropped from the
+// bundles that offer it.
 pub fn delete_product(conn: &Connection, id: i32) -> Result<usize> {
-    conn.execute("DELETE FROM products WHERE id = ?1", params![id])
+    let tx = conn.unchecked_transaction()?;
+    let sold: bool = tx.query_row(
+        "SELECT EXISTS(
+            SELECT 1 FROM products p
+            WHERE (p.id = ?1 OR p.parent_id = ?1)
+                AND (EXISTS(SELECT 1 FROM sale_items WHERE product_id = p.id)
+                    OR EXISTS(SELECT 1 FROM sale_item_components WHERE product_id = p.id))
+        )",
+        params![id],
+        |row| row.get(0),
+    )?;
+    if sold {
+        return Err(AppError::Conflict {
+            field: Some("product".to_string()),
+            code: "in_use".to_string(),
+            message: "the product has sales; archive it instead".to_string(),
+        });
+    }
+
+    let family = "SELECT id FROM products WHERE id = ?1 OR parent_id = ?1";
+    tx.execute(
+        &format!(
+            "DELETE FROM bundle_options WHERE group_id IN (SELECT id FROM bundle_groups WHERE bundle_id IN ({}))",
+            family
+        ),
+        params![id],
+    )?;
+    tx.execute(&format!("DELETE FROM bundle_groups WHERE bundle_id IN ({})", family), params![id])?;
+    for table in PRODUCT_DEPENDENTS {
+        tx.execute(&format!("DELETE FROM {} WHERE product_id IN ({})", table, family), params![id])?;
+    }
+    let deleted = tx.execute("DELETE FROM products WHERE id = ?1 OR parent_id = ?1", params![id])?;
+    tx.commit()?;
+    Ok(deleted)
 }
\ No newline at end of file
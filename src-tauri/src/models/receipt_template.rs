@@ -0,0 +1,78 @@
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+
+use crate::error::Result;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReceiptTemplate {
+    // "receipt" or "kitchen"
+    pub kind: String,
+    // "en", "ar", ...
+    pub language: String,
+    pub rtl: bool,
+    pub header: String,
+    pub body: String,
+    pub footer: String,
+}
+
+fn template_from_row(row: &Row) -> rusqlite::Result<ReceiptTemplate> {
+    Ok(ReceiptTemplate {
+        kind: row.get(0)?,
+        language: row.get(1)?,
+        rtl: row.get(2)?,
+        header: row.get(3)?,
+        body: row.get(4)?,
+        footer: row.get(5)?,
+    })
+}
+
+pub fn get_receipt_template(conn: &Connection, kind: &str, language: &str) -> Result<Option<ReceiptTemplate>> {
+    Ok(conn.query_row(
+        "SELECT kind, language, rtl, header, body, footer
+        FROM receipt_templates WHERE kind = ?1 AND language = ?2",
+        params![kind, language],
+        template_from_row,
+    )
+    .optional()?)
+}
+
+pub fn get_receipt_templates(conn: &Connection) -> Result<Vec<ReceiptTemplate>> {
+    let mut stmt = conn.prepare(
+        "SELECT kind, language, rtl, header, body, footer
+        FROM receipt_templates ORDER BY kind, language",
+    )?;
+    let rows = stmt.query_map([], template_from_row)?;
+
+    let mut templates = Vec::new();
+    for template in rows {
+        templates.push(template?);
+    }
+    Ok(templates)
+}
+
+pub fn save_receipt_template(conn: &Connection, template: &ReceiptTemplate) -> Result<()> {
+    conn.execute(
+        "INSERT INTO receipt_templates (kind, language, rtl, header, body, footer)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+        ON CONFLICT(kind, language) DO UPDATE SET
+            rtl = excluded.rtl, header = excluded.header, body = excluded.body,
+            footer = excluded.footer, updated_at = CURRENT_TIMESTAMP",
+        params![
+            template.kind,
+            template.language,
+            template.rtl,
+            template.header,
+            template.body,
+            template.footer
+        ],
+    )?;
+    Ok(())
+}
+
+// Removing the stored row falls back to the built-in default template
+pub fn delete_receipt_template(conn: &Connection, kind: &str, language: &str) -> Result<usize> {
+    Ok(conn.execute(
+        "DELETE FROM receipt_templates WHERE kind = ?1 AND language = ?2",
+        params![kind, language],
+    )?)
+}
@@ -1,10 +1,12 @@
-use rusqlite::{params, Connection, Result};
+use rusqlite::{params, Connection};
 use serde::Serialize;
 
+use crate::error::Result;
+
 #[derive(Serialize)]
 pub struct SaleDetail {
     pub product_name: String,
-    pub quantity: i32,
+    pub quantity: f64,
     pub employee_name: String,
     pub total_price: f64,
     pub timestamp: String,
@@ -27,11 +29,12 @@ pub fn get_report(
 
     // Fetch total sales and transactions
     let mut stmt = conn.prepare(
-        "SELECT IFNULL(SUM((price + extra_amount) * quantity), 0) as total_sales,
+        "SELECT IFNULL(SUM(ROUND((price + extra_amount) * quantity, 2)), 0) as total_sales,
                 COUNT(DISTINCT sale_id) as total_transactions
             FROM sale_items
             JOIN sales ON sale_items.sale_id = sales.id
-            WHERE date(sales.timestamp) BETWEEN ?1 AND ?2"
+            WHERE date(sales.timestamp) BETWEEN ?1 AND ?2
+                AND sales.voided = 0 AND sales.receipt_number IS NOT NULL"
     )?;
 
     let (total_sales, total_transactions): (f64, i64) =
@@ -40,12 +43,13 @@ pub fn get_report(
     // Fetch detailed sales
     let mut stmt_details = conn.prepare(
         "SELECT sale_items.product_name, sale_items.quantity, employees.name, 
-                ((sale_items.price + sale_items.extra_amount) * sale_items.quantity) as total_price, 
+                ROUND((sale_items.price + sale_items.extra_amount) * sale_items.quantity, 2) as total_price, 
                 sales.timestamp
         FROM sale_items
         JOIN sales ON sale_items.sale_id = sales.id
         JOIN employees ON sales.employee_id = employees.id
         WHERE date(sales.timestamp) BETWEEN ?1 AND ?2
+            AND sales.voided = 0 AND sales.receipt_number IS NOT NULL
         ORDER BY sales.timestamp DESC"
     )?;
 
@@ -69,4 +73,149 @@ pub fn get_report(
         total_transactions,
         sales,
     })
-}
\ No newline at end of file
+}
+// Sales per product. Rolled up, variants count towards their parent
+// product; broken down, each variant is its own line.
+#[derive(Serialize)]
+pub struct ProductSales {
+    // None for lines whose product has since been deleted
+    pub product_id: Option<i32>,
+    pub name: String,
+    pub quantity: f64,
+    pub total: f64,
+}
+
+pub fn get_product_sales(
+    conn: &Connection,
+    start_date: Option<&str>,
+    end_date: Option<&str>,
+    by_variant: bool,
+) -> Result<Vec<ProductSales>> {
+    let start = start_date.unwrap_or("1970-01-01");
+    let end = end_date.unwrap_or("9999-12-31");
+    let key = if by_variant { "p.id" } else { "COALESCE(p.parent_id, p.id)" };
+
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {key}, IFNULL(k.name, si.product_name),
+                SUM(si.quantity), SUM(ROUND((si.price + si.extra_amount) * si.quantity, 2)) AS total
+        FROM sale_items si
+        JOIN sales s ON si.sale_id = s.id
+        LEFT JOIN products p ON p.id = si.product_id
+        LEFT JOIN products k ON k.id = {key}
+        WHERE s.voided = 0 AND s.receipt_number IS NOT NULL
+            AND date(s.timestamp) BETWEEN ?1 AND ?2
+        GROUP BY {key}, CASE WHEN {key} IS NULL THEN si.product_name END
+        ORDER BY total DESC",
+        key = key
+    ))?;
+    let rows = stmt.query_map(params![start, end], |row| {
+        Ok(ProductSales {
+            product_id: row.get(0)?,
+            name: row.get(1)?,
+            quantity: row.get(2)?,
+            total: row.get(3)?,
+        })
+    })?;
+
+    let mut products = Vec::new();
+    for product in rows {
+        products.push(product?);
+    }
+    Ok(products)
+}
+
+// Sales per product with bundles broken into their components, each at its
+// allocated share of the bundle price
+pub fn get_component_sales(
+    conn: &Connection,
+    start_date: Option<&str>,
+    end_date: Option<&str>,
+) -> Result<Vec<ProductSales>> {
+    let start = start_date.unwrap_or("1970-01-01");
+    let end = end_date.unwrap_or("9999-12-31");
+
+    let mut stmt = conn.prepare(
+        "SELECT m.product_id, IFNULL(p.name, m.product_name), SUM(m.quantity), SUM(m.total) AS total
+        FROM (
+            SELECT si.product_id, si.product_name, si.quantity,
+                ROUND((si.price + si.extra_amount) * si.quantity, 2) AS total
+            FROM sale_items si JOIN sales s ON si.sale_id = s.id
+            WHERE s.voided = 0 AND s.receipt_number IS NOT NULL
+                AND date(s.timestamp) BETWEEN ?1 AND ?2
+                AND NOT EXISTS (SELECT 1 FROM sale_item_components WHERE sale_item_id = si.id)
+            UNION ALL
+            SELECT c.product_id, c.product_name, c.quantity * si.quantity, ROUND(c.price * si.quantity, 2)
+            FROM sale_item_components c
+            JOIN sale_items si ON si.id = c.sale_item_id
+            JOIN sales s ON si.sale_id = s.id
+            WHERE s.voided = 0 AND s.receipt_number IS NOT NULL
+                AND date(s.timestamp) BETWEEN ?1 AND ?2
+        ) m
+        LEFT JOIN products p ON p.id = m.product_id
+        GROUP BY m.product_id, CASE WHEN m.product_id IS NULL THEN m.product_name END
+        ORDER BY total DESC",
+    )?;
+    let rows = stmt.query_map(params![start, end], |row| {
+        Ok(ProductSales {
+            product_id: row.get(0)?,
+            name: row.get(1)?,
+            quantity: row.get(2)?,
+            total: row.get(3)?,
+        })
+    })?;
+
+    let mut products = Vec::new();
+    for product in rows {
+        products.push(product?);
+    }
+    Ok(products)
+}
+
+// A price change that took effect in the report period, to explain
+// revenue moving with unchanged quantities
+#[derive(Serialize)]
+pub struct PriceChange {
+    pub product_id: i32,
+    pub name: String,
+    pub old_price: f64,
+    pub new_price: f64,
+    pub effective_at: String,
+}
+
+pub fn get_price_changes(
+    conn: &Connection,
+    start_date: Option<&str>,
+    end_date: Option<&str>,
+) -> Result<Vec<PriceChange>> {
+    let start = start_date.unwrap_or("1970-01-01");
+    let end = end_date.unwrap_or("9999-12-31");
+
+    let mut stmt = conn.prepare(
+        "SELECT * FROM (
+            SELECT pp.product_id, p.name,
+                (SELECT price FROM product_prices o
+                    WHERE o.product_id = pp.product_id
+                        AND (o.effective_at < pp.effective_at OR (o.effective_at = pp.effective_at AND o.id < pp.id))
+                    ORDER BY o.effective_at DESC, o.id DESC LIMIT 1) AS old_price,
+                pp.price, pp.effective_at
+            FROM product_prices pp JOIN products p ON p.id = pp.product_id
+            WHERE date(pp.effective_at) BETWEEN ?1 AND ?2 AND pp.effective_at <= datetime('now')
+        ) WHERE old_price IS NOT NULL AND old_price != price
+        ORDER BY effective_at, name",
+    )?;
+    let rows = stmt.query_map(params![start, end], |row| {
+        Ok(PriceChange {
+            product_id: row.get(0)?,
+            name: row.get(1)?,
+            old_price: row.get(2)?,
+            new_price: row.get(3)?,
+            effective_at: row.get(4)?,
+        })
+    })?;
+
+    let mut changes = Vec::new();
+    for change in rows {
+        changes.push(change?);
+    }
+    Ok(changes)
+}
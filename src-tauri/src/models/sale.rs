@@ -1,59 +1,271 @@
-use rusqlite::{params, Connection, Result};
+use chrono::Local;
+use rusqlite::{params, Connection, OptionalExtension};
 use serde::Serialize;
 
+use crate::error::{AppError, Result};
+use crate::models::availability::check_available;
+use crate::models::bundle::{add_sale_components, copy_sale_components};
+use crate::models::document::allocate_document_number;
+use crate::models::gift_card::{activate_gift_cards, refund_gift_cards, reverse_gift_cards};
+use crate::models::loyalty::{award_points, reverse_points};
+use crate::models::settings::get_setting_f64;
+use crate::models::stock::{record_sale_stock, reverse_sale_stock};
+use crate::models::tip::{record_service_charge, reverse_tips, service_charge_percent};
+use crate::validation;
+
 #[derive(Debug, Serialize)]
 pub struct SaleReport {
     pub id: i64,
     pub product_name: String,
-    pub quantity: i32,
+    pub quantity: f64,
+    pub unit: String,
     pub employee_name: String,
     pub total: f64,
     pub timestamp: String,
 }
 
-// Start a sale: (snapshot)
+// Start a sale: (snapshot of the cashier's name)
 pub fn start_sale(conn: &Connection, employee_id: i32) -> Result<i64> {
     conn.execute(
-        "INSERT INTO sales (employee_id, total) VALUES (?1, 0)",
+        "INSERT INTO sales (employee_id, employee_name, total)
+        VALUES (?1, (SELECT name FROM employees WHERE id = ?1), 0)",
         params![employee_id],
     )?;
     Ok(conn.last_insert_rowid())
 }
 
-// Add an item: (snapshot)
+// Add an item: (snapshot of the name and unit). The quantity is in the
+// product's unit, e.g. 0.35 for 350 g of a product sold by the kilogram.
+// For a bundle, `choices` are the products picked from its choice groups.
+// Products outside their availability window, 86'd today or archived are
+// refused through `check_available`, as are completed and voided sales.
 pub fn add_sale_item(
     conn: &Connection,
     sale_id: i64,
     product_id: i32,
-    quantity: i32,
+    quantity: f64,
     price: f64,
     extra_amount: f64,
+    choices: &[i32],
 ) -> Result<()> {
-    let mut stmt = conn.prepare("SELECT name FROM products WHERE id = ?1")?;
-    let product_name: String = stmt.query_row(params![product_id], |row| row.get(0))?;
+    let price = validation::price("price", price)?;
+    let extra_amount = validation::price("extra_amount", extra_amount)?;
+    check_available(conn, product_id, Local::now().naive_local())?;
 
-    conn.execute(
-        "INSERT INTO sale_items (sale_id, product_id, product_name, quantity, price, extra_amount) 
-        VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
-        params![sale_id, product_id, product_name, quantity, price, extra_amount],
+    let mut stmt = conn.prepare("SELECT name, unit FROM products WHERE id = ?1")?;
+    let (product_name, unit): (String, String) =
+        stmt.query_row(params![product_id], |row| Ok((row.get(0)?, row.get(1)?)))?;
+    let quantity = validation::quantity(quantity, validation::unit(&unit)?)?;
+
+    let tx = conn.unchecked_transaction()?;
+    tx.execute(
+        "INSERT INTO sale_items (sale_id, product_id, product_name, quantity, unit, price, extra_amount) 
+        SELECT id, ?2, ?3, ?4, ?5, ?6, ?7 FROM sales WHERE id = ?1 AND receipt_number IS NULL AND voided = 0",
+        params![sale_id, product_id, product_name, quantity, unit, price, extra_amount],
     )?;
+    if tx.changes() == 0 {
+        return Err(AppError::Conflict {
+            field: Some("sale".to_string()),
+            code: "completed".to_string(),
+            message: "the sale is already completed".to_string(),
+        });
+    }
+    add_sale_components(&tx, tx.last_insert_rowid(), choices)?;
+    tx.commit()?;
     Ok(())
 }
 
-// Update the sale total based on sum of items
+// Update the sale total based on sum of items, less any discount.
+// Each line is rounded to cents, as weighed quantities give fractions of one.
+// Prices are tax-inclusive, so the tax portion is derived from the `tax_rate` setting (percent),
+// which is stored with the sale for reprints. Gift card lines are not taxed.
+// The service charge for the party size is kept apart from the total.
 pub fn update_sale_total(conn: &Connection, sale_id: i64) -> Result<()> {
     let mut stmt = conn.prepare(
-        "SELECT SUM(quantity * (price + extra_amount)) FROM sale_items WHERE sale_id = ?1"
+        "SELECT IFNULL(SUM(ROUND(quantity * (price + extra_amount), 2)), 0) - s.discount,
+                IFNULL(SUM(CASE WHEN si.gift_card_id IS NOT NULL THEN quantity * price END), 0),
+                s.guests
+        FROM sales s LEFT JOIN sale_items si ON si.sale_id = s.id
+        WHERE s.id = ?1"
     )?;
-    let total: f64 = stmt.query_row(params![sale_id], |row| row.get(0))?;
+    let (total, gift_cards, guests): (f64, f64, Option<i64>) =
+        stmt.query_row(params![sale_id], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?;
+
+    let tax_rate = get_setting_f64(conn, "tax_rate", 0.0)?;
+    let tax = (total - gift_cards) * tax_rate / (100.0 + tax_rate);
+    let service_charge = (total - gift_cards) * service_charge_percent(conn, guests)? / 100.0;
 
     conn.execute(
-        "UPDATE sales SET total = ?1 WHERE id = ?2",
-        params![total, sale_id],
+        "UPDATE sales SET total = ?1, tax = ?2, tax_rate = ?3, service_charge = ?4 WHERE id = ?5",
+        params![total, tax, tax_rate, service_charge, sale_id],
     )?;
     Ok(())
 }
 
+// Complete a sale: recompute the total, assign the next receipt number for
+// the terminal, credit the customer's loyalty points, activate gift cards
+// sold on it and book the service charge to the server, all in one transaction.
+// Sales that are abandoned never reach this point and so never consume a
+// number. Completing twice returns the number already issued; a voided sale
+// is refused.
+pub fn complete_sale(conn: &Connection, sale_id: i64, terminal: &str) -> Result<String> {
+    let tx = conn.unchecked_transaction()?;
+
+    let (existing, voided): (Option<String>, bool) = tx
+        .query_row(
+            "SELECT receipt_number, voided FROM sales WHERE id = ?1",
+            params![sale_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()?
+        .ok_or_else(|| AppError::not_found("sale"))?;
+    if voided {
+        return Err(AppError::Conflict {
+            field: Some("sale".to_string()),
+            code: "voided".to_string(),
+            message: "a voided sale cannot be completed".to_string(),
+        });
+    }
+    if let Some(number) = existing {
+        return Ok(number);
+    }
+
+    update_sale_total(&tx, sale_id)?;
+    let number = allocate_document_number(&tx, terminal, "sale")?;
+    tx.execute(
+        "UPDATE sales SET receipt_number = ?1, terminal = ?2, completed_at = strftime('%Y-%m-%d %H:%M:%f', 'now')
+        WHERE id = ?3",
+        params![number, terminal, sale_id],
+    )?;
+    award_points(&tx, sale_id)?;
+    activate_gift_cards(&tx, sale_id)?;
+    record_service_charge(&tx, sale_id)?;
+    record_sale_stock(&tx, sale_id, "sale")?;
+
+    tx.commit()?;
+    Ok(number)
+}
+
+// Set the party size of an open sale and return the service charge that now
+// applies, or None if the sale is already completed.
+pub fn set_sale_guests(conn: &Connection, sale_id: i64, guests: Option<i64>) -> Result<Option<f64>> {
+    let guests = validation::guests(guests)?;
+    let updated = conn.execute(
+        "UPDATE sales SET guests = ?1 WHERE id = ?2 AND receipt_number IS NULL",
+        params![guests, sale_id],
+    )?;
+    if updated == 0 {
+        return Ok(None);
+    }
+    update_sale_total(conn, sale_id)?;
+    let service_charge = conn.query_row(
+        "SELECT service_charge FROM sales WHERE id = ?1",
+        params![sale_id],
+        |row| row.get(0),
+    )?;
+    Ok(Some(service_charge))
+}
+
+// Apply a flat discount to an open sale and recompute the total. It replaces
+// any earlier manual discount; points redeemed as a discount stay on top.
+pub fn apply_sale_discount(conn: &Connection, sale_id: i64, discount: f64) -> Result<()> {
+    let discount = validation::price("discount", discount)?;
+    let updated = conn.execute(
+        "UPDATE sales SET discount = points_discount + ?1 WHERE id = ?2 AND receipt_number IS NULL",
+        params![discount, sale_id],
+    )?;
+    if updated == 0 {
+        return Err(AppError::Conflict {
+            field: Some("sale".to_string()),
+            code: "completed".to_string(),
+            message: "the sale is already completed".to_string(),
+        });
+    }
+    update_sale_total(conn, sale_id)
+}
+
+// Void a sale. Voided sales stay in the database but are excluded from
+// revenue and tenders; gift card, loyalty point, tip and stock movements on
+// the sale are reversed.
+// Refunds, and sales that have been refunded, cannot be voided as the refund
+// already reversed them. Returns false if the sale was already voided.
+pub fn void_sale(conn: &Connection, sale_id: i64) -> Result<bool> {
+    let tx = conn.unchecked_transaction()?;
+    let refunded: bool = tx
+        .query_row(
+            "SELECT refund_of IS NOT NULL OR EXISTS(SELECT 1 FROM sales WHERE refund_of = ?1)
+            FROM sales WHERE id = ?1",
+            params![sale_id],
+            |row| row.get(0),
+        )
+        .optional()?
+        .ok_or_else(|| AppError::not_found("sale"))?;
+    if refunded {
+        return Err(AppError::Conflict {
+            field: Some("sale".to_string()),
+            code: "refunded".to_string(),
+            message: "refunds and refunded sales cannot be voided".to_string(),
+        });
+    }
+    let updated = tx.execute(
+        "UPDATE sales SET voided = 1 WHERE id = ?1 AND voided = 0",
+        params![sale_id],
+    )?;
+    if updated > 0 {
+        reverse_gift_cards(&tx, sale_id)?;
+        reverse_points(&tx, sale_id, sale_id)?;
+        reverse_tips(&tx, sale_id, sale_id)?;
+        reverse_sale_stock(&tx, sale_id)?;
+    }
+    tx.commit()?;
+    Ok(updated > 0)
+}
+
+// Full refund of a completed sale: a new sale referencing the original, with
+// every line negated. A sale can only be refunded once. The refund is complete on
+// creation and takes its number from the terminal's refund sequence. Gift
+// cards sold on the sale are emptied, so they must not have been spent.
+pub fn refund_sale(conn: &Connection, sale_id: i64, employee_id: i32, terminal: &str) -> Result<i64> {
+    let tx = conn.unchecked_transaction()?;
+
+    let number = allocate_document_number(&tx, terminal, "refund")?;
+    tx.execute(
+        "INSERT INTO sales
+            (employee_id, employee_name, customer_id, total, discount, tax, tax_rate, guests, service_charge,
+             refund_of, receipt_number, terminal, completed_at)
+        SELECT ?1, (SELECT name FROM employees WHERE id = ?1), customer_id, -total, -discount, -tax, tax_rate,
+            guests, -service_charge, id, ?3, ?4, strftime('%Y-%m-%d %H:%M:%f', 'now')
+        FROM sales WHERE id = ?2 AND voided = 0 AND refund_of IS NULL AND receipt_number IS NOT NULL",
+        params![employee_id, sale_id, number, terminal],
+    )?;
+    if tx.changes() == 0 {
+        return Err(AppError::not_found("sale"));
+    }
+    let refund_id = tx.last_insert_rowid();
+
+    let item_ids = {
+        let mut stmt = tx.prepare("SELECT id FROM sale_items WHERE sale_id = ?1 ORDER BY id")?;
+        let rows = stmt.query_map(params![sale_id], |row| row.get::<_, i64>(0))?;
+        rows.collect::<rusqlite::Result<Vec<_>>>()?
+    };
+    for item_id in item_ids {
+        tx.execute(
+            "INSERT INTO sale_items (sale_id, product_id, product_name, quantity, unit, price, extra_amount, gift_card_id)
+            SELECT ?1, product_id, product_name, -quantity, unit, price, extra_amount, gift_card_id
+            FROM sale_items WHERE id = ?2",
+            params![refund_id, item_id],
+        )?;
+        copy_sale_components(&tx, item_id, tx.last_insert_rowid())?;
+    }
+    reverse_points(&tx, sale_id, refund_id)?;
+    refund_gift_cards(&tx, sale_id, refund_id)?;
+    reverse_tips(&tx, sale_id, refund_id)?;
+    record_sale_stock(&tx, refund_id, "refund")?;
+
+    tx.commit()?;
+    Ok(refund_id)
+}
+
 // Fetch all sales for report
 pub fn get_all_sales(conn: &Connection) -> Result<Vec<SaleReport>> {
     let mut stmt = conn.prepare(
@@ -62,8 +274,9 @@ pub fn get_all_sales(conn: &Connection) -> Result<Vec<SaleReport>> {
             si.id,
             si.product_name,
             si.quantity,
+            si.unit,
             s.employee_name,
-            (si.price * si.quantity) as total,
+            ROUND(si.price * si.quantity, 2) as total,
             s.timestamp
         FROM sale_items si
         JOIN sales s ON si.sale_id = s.id
@@ -76,9 +289,10 @@ pub fn get_all_sales(conn: &Connection) -> Result<Vec<SaleReport>> {
             id: row.get(0)?,
             product_name: row.get(1)?,
             quantity: row.get(2)?,
-            employee_name: row.get(3)?,
-            total: row.get(4)?,
-            timestamp: row.get(5)?,
+            unit: row.get(3)?,
+            employee_name: row.get(4)?,
+            total: row.get(5)?,
+            timestamp: row.get(6)?,
         })
     })?;
 
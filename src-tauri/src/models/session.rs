@@ -0,0 +1,57 @@
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::error::{AppError, Result};
+
+// Signed-in sessions. A successful login returns a token that the frontend
+// passes back to guarded commands, so they act for whoever signed in rather
+// than for an id the caller supplies. A session ends on logout or after
+// SESSION_IDLE without use.
+const SESSION_IDLE: &str = "-12 hours";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Actor {
+    Admin,
+    Employee(i32),
+}
+
+impl Actor {
+    pub fn employee_id(self) -> Option<i32> {
+        match self {
+            Actor::Admin => None,
+            Actor::Employee(id) => Some(id),
+        }
+    }
+}
+
+pub fn start_session(conn: &Connection, actor: Actor) -> Result<String> {
+    let token: String = rand::random::<[u8; 32]>().iter().map(|b| format!("{:02x}", b)).collect();
+    conn.execute(
+        "INSERT INTO sessions (token, employee_id) VALUES (?1, ?2)",
+        params![token, actor.employee_id()],
+    )?;
+    Ok(token)
+}
+
+pub fn end_session(conn: &Connection, token: &str) -> Result<()> {
+    conn.execute("DELETE FROM sessions WHERE token = ?1", params![token])?;
+    Ok(())
+}
+
+// Who the session belongs to; a missing, ended or expired session is
+// refused as "session"
+pub fn session_actor(conn: &Connection, token: Option<&str>) -> Result<Actor> {
+    let employee_id: Option<Option<i32>> = conn
+        .query_row(
+            "UPDATE sessions SET last_used_at = CURRENT_TIMESTAMP
+            WHERE token = ?1 AND last_used_at > datetime('now', ?2)
+            RETURNING employee_id",
+            params![token, SESSION_IDLE],
+            |row| row.get(0),
+        )
+        .optional()?;
+    match employee_id {
+        Some(Some(id)) => Ok(Actor::Employee(id)),
+        Some(None) => Ok(Actor::Admin),
+        None => Err(AppError::unauthorized("session")),
+    }
+}
@@ -0,0 +1,47 @@
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+
+use crate::error::Result;
+
+#[derive(Debug, Serialize)]
+pub struct Setting {
+    pub key: String,
+    pub value: String,
+}
+
+pub fn get_setting(conn: &Connection, key: &str) -> Result<Option<String>> {
+    Ok(conn.query_row("SELECT value FROM settings WHERE key = ?1", params![key], |row| row.get(0))
+        .optional()?)
+}
+
+// Numeric settings fall back to the default when missing or unparsable.
+pub fn get_setting_f64(conn: &Connection, key: &str, default: f64) -> Result<f64> {
+    Ok(get_setting(conn, key)?
+        .and_then(|v| v.trim().parse().ok())
+        .unwrap_or(default))
+}
+
+pub fn set_setting(conn: &Connection, key: &str, value: &str) -> Result<()> {
+    conn.execute(
+        "INSERT INTO settings (key, value) VALUES (?1, ?2)
+        ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+        params![key, value],
+    )?;
+    Ok(())
+}
+
+pub fn get_settings(conn: &Connection) -> Result<Vec<Setting>> {
+    let mut stmt = conn.prepare("SELECT key, value FROM settings ORDER BY key")?;
+    let rows = stmt.query_map([], |row| {
+        Ok(Setting {
+            key: row.get(0)?,
+            value: row.get(1)?,
+        })
+    })?;
+
+    let mut settings = Vec::new();
+    for setting in rows {
+        settings.push(setting?);
+    }
+    Ok(settings)
+}
@@ -0,0 +1,271 @@
+use chrono::NaiveDate;
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+use crate::error::Result;
+use crate::models::document::allocate_document_number;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TenderTotal {
+    pub method: String,
+    pub amount: f64,
+    pub count: i64,
+}
+
+// Shared shape of X and Z reports. Z reports are stored as JSON in this shape
+// so a reprint shows exactly what was issued.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ShiftReport {
+    pub kind: String,
+    pub z_number: Option<i64>,
+    #[serde(default)]
+    pub document_number: Option<String>,
+    pub period_start: String,
+    pub period_end: String,
+    pub transactions: i64,
+    pub gross_sales: f64,
+    pub discounts: f64,
+    pub refunds: f64,
+    pub refund_count: i64,
+    pub voids: f64,
+    pub void_count: i64,
+    pub net_sales: f64,
+    pub taxes: f64,
+    // Collected for staff and not part of net sales; tenders include them
+    #[serde(default)]
+    pub tips: f64,
+    #[serde(default)]
+    pub service_charges: f64,
+    pub tenders: Vec<TenderTotal>,
+    pub drawer_variance: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ZReportSummary {
+    pub z_number: i64,
+    pub document_number: Option<String>,
+    pub business_date: String,
+    pub period_start: String,
+    pub period_end: String,
+    pub net_sales: f64,
+    pub created_at: String,
+}
+
+// Sales (alias `s`) completed in (?1, ?2]. Open sales belong to the period
+// they are completed in; sales completed before `completed_at` existed fall
+// back to their start time.
+const COMPLETED_IN_PERIOD: &str = "s.receipt_number IS NOT NULL
+    AND COALESCE(s.completed_at, s.timestamp) > ?1 AND COALESCE(s.completed_at, s.timestamp) <= ?2";
+
+// Where the current (not yet Z-closed) business day starts: the last Z's
+// end time, or the beginning of time.
+fn current_period_start(conn: &Connection) -> Result<String> {
+    Ok(conn
+        .query_row("SELECT period_end FROM z_reports ORDER BY z_number DESC LIMIT 1", [], |row| row.get(0))
+        .optional()?
+        .unwrap_or_else(|| "1970-01-01 00:00:00".to_string()))
+}
+
+// Totals for sales completed and drawers closed in (period_start, period_end].
+fn build_report(conn: &Connection, kind: &str, period_start: &str, period_end: &str) -> Result<ShiftReport> {
+    let range = params![period_start, period_end];
+
+    let (transactions, gross_sales, discounts, taxes): (i64, f64, f64, f64) = conn.query_row(
+        &format!(
+            "SELECT COUNT(*),
+                IFNULL(SUM((SELECT IFNULL(SUM(ROUND(quantity * (price + extra_amount), 2)), 0)
+                    FROM sale_items WHERE sale_id = s.id)), 0),
+                IFNULL(SUM(s.discount), 0),
+                IFNULL(SUM(s.tax), 0)
+            FROM sales s
+            WHERE {} AND s.voided = 0 AND s.refund_of IS NULL",
+            COMPLETED_IN_PERIOD
+        ),
+        range,
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+    )?;
+
+    let (refund_count, refunds, refund_taxes): (i64, f64, f64) = conn.query_row(
+        &format!(
+            "SELECT COUNT(*), IFNULL(-SUM(s.total), 0), IFNULL(SUM(s.tax), 0)
+            FROM sales s
+            WHERE {} AND s.voided = 0 AND s.refund_of IS NOT NULL",
+            COMPLETED_IN_PERIOD
+        ),
+        range,
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+    )?;
+
+    let (void_count, voids): (i64, f64) = conn.query_row(
+        &format!(
+            "SELECT COUNT(*), IFNULL(SUM(s.total), 0)
+            FROM sales s WHERE {} AND s.voided = 1",
+            COMPLETED_IN_PERIOD
+        ),
+        range,
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )?;
+
+    let (tips, service_charges): (f64, f64) = conn.query_row(
+        &format!(
+            "SELECT IFNULL(SUM(CASE WHEN t.kind = 'tip' THEN t.amount END), 0),
+                IFNULL(SUM(CASE WHEN t.kind = 'service_charge' THEN t.amount END), 0)
+            FROM tips t JOIN sales s ON t.sale_id = s.id
+            WHERE {} AND s.voided = 0",
+            COMPLETED_IN_PERIOD
+        ),
+        range,
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )?;
+
+    let mut stmt = conn.prepare(&format!(
+        "SELECT p.method, SUM(p.amount), COUNT(*)
+        FROM payments p JOIN sales s ON p.sale_id = s.id
+        WHERE {} AND s.voided = 0
+        GROUP BY p.method ORDER BY p.method",
+        COMPLETED_IN_PERIOD
+    ))?;
+    let rows = stmt.query_map(range, |row| {
+        Ok(TenderTotal {
+            method: row.get(0)?,
+            amount: row.get(1)?,
+            count: row.get(2)?,
+        })
+    })?;
+    let mut tenders = Vec::new();
+    for tender in rows {
+        tenders.push(tender?);
+    }
+
+    let drawer_variance: f64 = conn.query_row(
+        "SELECT IFNULL(SUM(over_short), 0) FROM drawer_sessions
+        WHERE closed_at > ?1 AND closed_at <= ?2",
+        params![period_start, period_end],
+        |row| row.get(0),
+    )?;
+
+    Ok(ShiftReport {
+        kind: kind.to_string(),
+        z_number: None,
+        document_number: None,
+        period_start: period_start.to_string(),
+        period_end: period_end.to_string(),
+        transactions,
+        gross_sales,
+        discounts,
+        refunds,
+        refund_count,
+        voids,
+        void_count,
+        net_sales: gross_sales - discounts - refunds,
+        taxes: taxes + refund_taxes,
+        tips,
+        service_charges,
+        tenders,
+        drawer_variance,
+    })
+}
+
+// Sales are stamped with millisecond completion times, so the period end is
+// taken to the millisecond too and a sale completed just after a Z falls in
+// the next period. The last sale id is kept with the Z for reference.
+fn now_and_last_sale(conn: &Connection) -> Result<(String, i64)> {
+    Ok(conn.query_row(
+        "SELECT strftime('%Y-%m-%d %H:%M:%f', 'now'), IFNULL(MAX(id), 0) FROM sales",
+        [],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )?)
+}
+
+// X report: everything since the last Z, without closing the day.
+pub fn get_x_report(conn: &Connection) -> Result<ShiftReport> {
+    let start = current_period_start(conn)?;
+    let (now, _) = now_and_last_sale(conn)?;
+    build_report(conn, "X", &start, &now)
+}
+
+// Z report: closes the business day, takes the next sequential number (plus a
+// document number from the terminal's z_report sequence) and is stored
+// permanently (triggers reject any later update or delete). `business_date`
+// is the local date of the day being closed.
+pub fn issue_z_report(
+    conn: &Connection,
+    employee_id: Option<i32>,
+    terminal: &str,
+    business_date: NaiveDate,
+) -> Result<ShiftReport> {
+    let tx = conn.unchecked_transaction()?;
+
+    let start = current_period_start(&tx)?;
+    let (now, last_id) = now_and_last_sale(&tx)?;
+    let z_number: i64 =
+        tx.query_row("SELECT IFNULL(MAX(z_number), 0) + 1 FROM z_reports", [], |row| row.get(0))?;
+
+    let mut report = build_report(&tx, "Z", &start, &now)?;
+    report.z_number = Some(z_number);
+    report.document_number = Some(allocate_document_number(&tx, terminal, "z_report")?);
+    let data = serde_json::to_string(&report)
+        .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+
+    tx.execute(
+        "INSERT INTO z_reports
+            (z_number, document_number, business_date, period_start, period_end, last_sale_id, employee_id, data)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        params![
+            z_number,
+            report.document_number,
+            business_date.format("%Y-%m-%d").to_string(),
+            start,
+            now,
+            last_id,
+            employee_id,
+            data
+        ],
+    )?;
+
+    tx.commit()?;
+    Ok(report)
+}
+
+// Stored Z report for reprinting
+pub fn get_z_report(conn: &Connection, z_number: i64) -> Result<Option<ShiftReport>> {
+    let data: Option<String> = conn
+        .query_row(
+            "SELECT data FROM z_reports WHERE z_number = ?1",
+            params![z_number],
+            |row| row.get(0),
+        )
+        .optional()?;
+
+    match data {
+        Some(data) => Ok(serde_json::from_str(&data)
+            .map(Some)
+            .map_err(|e| rusqlite::Error::FromSqlConversionFailure(0, rusqlite::types::Type::Text, Box::new(e)))?),
+        None => Ok(None),
+    }
+}
+
+pub fn get_z_reports(conn: &Connection) -> Result<Vec<ZReportSummary>> {
+    let mut stmt = conn.prepare(
+        "SELECT z_number, document_number, business_date, period_start, period_end,
+                json_extract(data, '$.net_sales'), created_at
+        FROM z_reports ORDER BY z_number DESC",
+    )?;
+    let rows = stmt.query_map([], |row| {
+        Ok(ZReportSummary {
+            z_number: row.get(0)?,
+            document_number: row.get(1)?,
+            business_date: row.get(2)?,
+            period_start: row.get(3)?,
+            period_end: row.get(4)?,
+            net_sales: row.get(5)?,
+            created_at: row.get(6)?,
+        })
+    })?;
+
+    let mut reports = Vec::new();
+    for report in rows {
+        reports.push(report?);
+    }
+    Ok(reports)
+}
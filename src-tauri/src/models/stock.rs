@@ -0,0 +1,145 @@
+use rusqlite::{params, Connection};
+use serde::Serialize;
+
+use crate::error::{AppError, Result};
+use crate::validation;
+
+// Stock on hand is the sum of a product's movements. Only products with
+// `track_stock` set get movements: completed sales take their quantities
+// off, refunds put them back and voids reverse whatever the sale moved.
+pub(crate) const STOCK_LEVEL: &str = "CASE WHEN p.track_stock = 1
+    THEN IFNULL((SELECT SUM(quantity) FROM stock_movements WHERE product_id = p.id), 0) END";
+
+#[derive(Debug, Serialize)]
+pub struct StockLevel {
+    pub product_id: i32,
+    pub name: String,
+    pub parent_id: Option<i32>,
+    pub unit: String,
+    pub stock: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StockMovement {
+    pub id: i64,
+    pub product_id: i32,
+    pub sale_id: Option<i64>,
+    pub quantity: f64,
+    // "sale", "refund", "void" or "adjust"
+    pub kind: String,
+    pub note: String,
+    pub created_at: String,
+}
+
+pub fn set_stock_tracking(conn: &Connection, product_id: i32, track: bool) -> Result<usize> {
+    Ok(conn.execute(
+        "UPDATE products SET track_stock = ?1 WHERE id = ?2",
+        params![track, product_id],
+    )?)
+}
+
+// Book a delivery (positive) or a count correction, breakage... (negative).
+// Adjusting a product starts tracking its stock.
+pub fn adjust_stock(conn: &Connection, product_id: i32, quantity: f64, note: &str) -> Result<i64> {
+    if !quantity.is_finite() || quantity == 0.0 || quantity.abs() > validation::MAX_QUANTITY {
+        return Err(AppError::validation(
+            "quantity",
+            "out_of_range",
+            format!("quantity must be non-zero and at most {}", validation::MAX_QUANTITY),
+        ));
+    }
+    let note = validation::text("note", note)?;
+
+    let tx = conn.unchecked_transaction()?;
+    if set_stock_tracking(&tx, product_id, true)? == 0 {
+        return Err(AppError::not_found("product"));
+    }
+    tx.execute(
+        "INSERT INTO stock_movements (product_id, quantity, kind, note) VALUES (?1, ?2, 'adjust', ?3)",
+        params![product_id, quantity, note],
+    )?;
+    let id = tx.last_insert_rowid();
+    tx.commit()?;
+    Ok(id)
+}
+
+// Take the lines of a completed sale off stock; `kind` is "sale" or
+// "refund" (refund lines are negative, so they go back on). Bundles take
+// their components off stock.
+pub fn record_sale_stock(conn: &Connection, sale_id: i64, kind: &str) -> Result<()> {
+    conn.execute(
+        "INSERT INTO stock_movements (product_id, sale_id, quantity, kind)
+        SELECT m.product_id, ?1, -SUM(m.quantity), ?2
+        FROM (
+            SELECT product_id, quantity FROM sale_items WHERE sale_id = ?1
+            UNION ALL
+            SELECT c.product_id, si.quantity * c.quantity
+            FROM sale_item_components c JOIN sale_items si ON si.id = c.sale_item_id
+            WHERE si.sale_id = ?1
+        ) m JOIN products p ON p.id = m.product_id
+        WHERE p.track_stock = 1
+        GROUP BY m.product_id",
+        params![sale_id, kind],
+    )?;
+    Ok(())
+}
+
+pub fn reverse_sale_stock(conn: &Connection, sale_id: i64) -> Result<()> {
+    conn.execute(
+        "INSERT INTO stock_movements (product_id, sale_id, quantity, kind)
+        SELECT product_id, ?1, -SUM(quantity), 'void'
+        FROM stock_movements WHERE sale_id = ?1
+        GROUP BY product_id HAVING SUM(quantity) != 0",
+        params![sale_id],
+    )?;
+    Ok(())
+}
+
+// Tracked products, variants listed under their parent
+pub fn get_stock_levels(conn: &Connection) -> Result<Vec<StockLevel>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT p.id, p.name, p.parent_id, p.unit, {} FROM products p
+        WHERE p.track_stock = 1
+        ORDER BY COALESCE(p.parent_id, p.id), p.parent_id IS NOT NULL, p.name",
+        STOCK_LEVEL
+    ))?;
+    let rows = stmt.query_map([], |row| {
+        Ok(StockLevel {
+            product_id: row.get(0)?,
+            name: row.get(1)?,
+            parent_id: row.get(2)?,
+            unit: row.get(3)?,
+            stock: row.get(4)?,
+        })
+    })?;
+
+    let mut levels = Vec::new();
+    for level in rows {
+        levels.push(level?);
+    }
+    Ok(levels)
+}
+
+pub fn get_stock_movements(conn: &Connection, product_id: i32, limit: i64) -> Result<Vec<StockMovement>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, product_id, sale_id, quantity, kind, note, created_at FROM stock_movements
+        WHERE product_id = ?1 ORDER BY id DESC LIMIT ?2",
+    )?;
+    let rows = stmt.query_map(params![product_id, limit], |row| {
+        Ok(StockMovement {
+            id: row.get(0)?,
+            product_id: row.get(1)?,
+            sale_id: row.get(2)?,
+            quantity: row.get(3)?,
+            kind: row.get(4)?,
+            note: row.get(5)?,
+            created_at: row.get(6)?,
+        })
+    })?;
+
+    let mut movements = Vec::new();
+    for movement in rows {
+        movements.push(movement?);
+    }
+    Ok(movements)
+}
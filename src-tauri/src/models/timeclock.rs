@@ -0,0 +1,335 @@
+use chrono::{Datelike, NaiveDate};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::Serialize;
+
+use crate::error::{AppError, Result};
+use crate::models::settings::get_setting_f64;
+use crate::validation;
+
+// Overtime settings (hours; 0 turns the rule off):
+// overtime_daily_hours  hours per day before overtime (default 8)
+// overtime_weekly_hours regular hours per Monday-Sunday week before overtime (default 40)
+
+#[derive(Debug, Serialize)]
+pub struct TimeEntry {
+    pub id: i64,
+    pub employee_id: i32,
+    pub employee_name: String,
+    pub clock_in: String,
+    pub clock_out: Option<String>,
+    pub break_minutes: f64,
+    pub on_break: bool,
+    // Worked hours less unpaid breaks; None while still clocked in
+    pub hours: Option<f64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Shift {
+    pub id: i64,
+    pub employee_id: i32,
+    pub employee_name: String,
+    pub starts_at: String,
+    pub ends_at: String,
+    pub note: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TimesheetDay {
+    pub date: String,
+    pub hours: f64,
+    pub regular: f64,
+    pub overtime: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Timesheet {
+    pub employee_id: i32,
+    pub employee_name: String,
+    pub days: Vec<TimesheetDay>,
+    pub hours: f64,
+    pub regular: f64,
+    pub overtime: f64,
+    pub scheduled: f64,
+}
+
+const ENTRY_COLUMNS: &str = "t.id, t.employee_id, IFNULL(e.name, ''), t.clock_in, t.clock_out,
+    IFNULL((SELECT SUM(julianday(IFNULL(b.ended_at, IFNULL(t.clock_out, CURRENT_TIMESTAMP))) - julianday(b.started_at))
+        FROM time_breaks b WHERE b.entry_id = t.id AND b.paid = 0), 0) * 1440,
+    EXISTS(SELECT 1 FROM time_breaks b WHERE b.entry_id = t.id AND b.ended_at IS NULL),
+    (julianday(t.clock_out) - julianday(t.clock_in)) * 24";
+
+fn entry_from_row(row: &Row) -> rusqlite::Result<TimeEntry> {
+    let break_minutes: f64 = row.get(5)?;
+    let span: Option<f64> = row.get(7)?;
+    Ok(TimeEntry {
+        id: row.get(0)?,
+        employee_id: row.get(1)?,
+        employee_name: row.get(2)?,
+        clock_in: row.get(3)?,
+        clock_out: row.get(4)?,
+        break_minutes,
+        on_break: row.get(6)?,
+        hours: span.map(|span| (span - break_minutes / 60.0).max(0.0)),
+    })
+}
+
+// Start a time entry. Returns false if the employee is already clocked in.
+pub fn clock_in(conn: &Connection, employee_id: i32) -> Result<bool> {
+    let inserted = conn.execute(
+        "INSERT INTO time_entries (employee_id)
+        SELECT ?1 WHERE NOT EXISTS(SELECT 1 FROM time_entries WHERE employee_id = ?1 AND clock_out IS NULL)",
+        params![employee_id],
+    )?;
+    Ok(inserted > 0)
+}
+
+// Close the open entry (and any break still running). Returns false if the
+// employee was not clocked in.
+pub fn clock_out(conn: &Connection, employee_id: i32) -> Result<bool> {
+    let tx = conn.unchecked_transaction()?;
+    tx.execute(
+        "UPDATE time_breaks SET ended_at = CURRENT_TIMESTAMP
+        WHERE ended_at IS NULL AND entry_id IN
+            (SELECT id FROM time_entries WHERE employee_id = ?1 AND clock_out IS NULL)",
+        params![employee_id],
+    )?;
+    let updated = tx.execute(
+        "UPDATE time_entries SET clock_out = CURRENT_TIMESTAMP WHERE employee_id = ?1 AND clock_out IS NULL",
+        params![employee_id],
+    )?;
+    tx.commit()?;
+    Ok(updated > 0)
+}
+
+// Returns false if the employee is not clocked in or already on a break
+pub fn start_break(conn: &Connection, employee_id: i32, paid: bool) -> Result<bool> {
+    let inserted = conn.execute(
+        "INSERT INTO time_breaks (entry_id, paid)
+        SELECT t.id, ?2 FROM time_entries t
+        WHERE t.employee_id = ?1 AND t.clock_out IS NULL
+            AND NOT EXISTS(SELECT 1 FROM time_breaks WHERE entry_id = t.id AND ended_at IS NULL)",
+        params![employee_id, paid],
+    )?;
+    Ok(inserted > 0)
+}
+
+pub fn end_break(conn: &Connection, employee_id: i32) -> Result<bool> {
+    let updated = conn.execute(
+        "UPDATE time_breaks SET ended_at = CURRENT_TIMESTAMP
+        WHERE ended_at IS NULL AND entry_id IN
+            (SELECT id FROM time_entries WHERE employee_id = ?1 AND clock_out IS NULL)",
+        params![employee_id],
+    )?;
+    Ok(updated > 0)
+}
+
+// The employee's open entry, if clocked in
+pub fn get_clock_status(conn: &Connection, employee_id: i32) -> Result<Option<TimeEntry>> {
+    Ok(conn.query_row(
+        &format!(
+            "SELECT {} FROM time_entries t LEFT JOIN employees e ON t.employee_id = e.id
+            WHERE t.employee_id = ?1 AND t.clock_out IS NULL",
+            ENTRY_COLUMNS
+        ),
+        params![employee_id],
+        entry_from_row,
+    )
+    .optional()?)
+}
+
+// Entries that started between two dates (inclusive), optionally for one employee
+pub fn get_time_entries(
+    conn: &Connection,
+    start_date: &str,
+    end_date: &str,
+    employee_id: Option<i32>,
+) -> Result<Vec<TimeEntry>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM time_entries t LEFT JOIN employees e ON t.employee_id = e.id
+        WHERE date(t.clock_in) BETWEEN ?1 AND ?2 AND (?3 IS NULL OR t.employee_id = ?3)
+        ORDER BY t.employee_id, t.clock_in",
+        ENTRY_COLUMNS
+    ))?;
+    let rows = stmt.query_map(params![start_date, end_date, employee_id], entry_from_row)?;
+
+    let mut entries = Vec::new();
+    for entry in rows {
+        entries.push(entry?);
+    }
+    Ok(entries)
+}
+
+// Normalise a start and optional end (any date and time SQLite reads) to
+// "YYYY-MM-DD HH:MM:SS", the format punches are stored in, and make sure the
+// end comes after the start
+fn time_range(
+    conn: &Connection,
+    (start_field, start): (&str, &str),
+    (end_field, end): (&str, Option<&str>),
+) -> Result<(String, Option<String>)> {
+    let (start, normalised_end, ordered): (Option<String>, Option<String>, bool) = conn.query_row(
+        "SELECT datetime(?1), datetime(?2), IFNULL(datetime(?2) > datetime(?1), 1)",
+        params![start, end],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+    )?;
+    let start = start.ok_or_else(|| AppError::validation(start_field, "invalid_format", "not a valid date and time"))?;
+    if end.is_some() && normalised_end.is_none() {
+        return Err(AppError::validation(end_field, "invalid_format", "not a valid date and time"));
+    }
+    if !ordered {
+        return Err(AppError::validation(end_field, "out_of_range", format!("{} must be after {}", end_field, start_field)));
+    }
+    Ok((start, normalised_end))
+}
+
+// Manager correction of a punch
+pub fn update_time_entry(
+    conn: &Connection,
+    id: i64,
+    clock_in: &str,
+    clock_out: Option<&str>,
+    note: &str,
+) -> Result<usize> {
+    let note = validation::text("note", note)?;
+    let (clock_in, clock_out) = time_range(conn, ("clock_in", clock_in), ("clock_out", clock_out))?;
+    Ok(conn.execute(
+        "UPDATE time_entries SET clock_in = ?1, clock_out = ?2, note = ?3 WHERE id = ?4",
+        params![clock_in, clock_out, note, id],
+    )?)
+}
+
+pub fn add_shift(conn: &Connection, employee_id: i32, starts_at: &str, ends_at: &str, note: &str) -> Result<i64> {
+    let note = validation::text("note", note)?;
+    let (starts_at, ends_at) = time_range(conn, ("starts_at", starts_at), ("ends_at", Some(ends_at)))?;
+    conn.execute(
+        "INSERT INTO shifts (employee_id, starts_at, ends_at, note) VALUES (?1, ?2, ?3, ?4)",
+        params![employee_id, starts_at, ends_at, note],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+pub fn delete_shift(conn: &Connection, id: i64) -> Result<usize> {
+    Ok(conn.execute("DELETE FROM shifts WHERE id = ?1", params![id])?)
+}
+
+pub fn get_shifts(conn: &Connection, start_date: &str, end_date: &str) -> Result<Vec<Shift>> {
+    let mut stmt = conn.prepare(
+        "SELECT s.id, s.employee_id, IFNULL(e.name, ''), s.starts_at, s.ends_at, s.note
+        FROM shifts s LEFT JOIN employees e ON s.employee_id = e.id
+        WHERE date(s.starts_at) BETWEEN ?1 AND ?2
+        ORDER BY s.starts_at",
+    )?;
+    let rows = stmt.query_map(params![start_date, end_date], |row| {
+        Ok(Shift {
+            id: row.get(0)?,
+            employee_id: row.get(1)?,
+            employee_name: row.get(2)?,
+            starts_at: row.get(3)?,
+            ends_at: row.get(4)?,
+            note: row.get(5)?,
+        })
+    })?;
+
+    let mut shifts = Vec::new();
+    for shift in rows {
+        shifts.push(shift?);
+    }
+    Ok(shifts)
+}
+
+// Hours per employee and day for a pay period, split into regular and
+// overtime. Daily overtime is taken first; whatever regular time then goes
+// past the weekly limit is overtime too. Entries still open are left out.
+pub fn get_timesheets(conn: &Connection, start_date: &str, end_date: &str) -> Result<Vec<Timesheet>> {
+    let daily_limit = get_setting_f64(conn, "overtime_daily_hours", 8.0)?;
+    let weekly_limit = get_setting_f64(conn, "overtime_weekly_hours", 40.0)?;
+
+    let mut timesheets: Vec<Timesheet> = Vec::new();
+    for entry in get_time_entries(conn, start_date, end_date, None)? {
+        let Some(hours) = entry.hours else { continue };
+
+        if timesheets.last().map(|t| t.employee_id) != Some(entry.employee_id) {
+            timesheets.push(Timesheet {
+                employee_id: entry.employee_id,
+                employee_name: entry.employee_name.clone(),
+                days: Vec::new(),
+                hours: 0.0,
+                regular: 0.0,
+                overtime: 0.0,
+                scheduled: 0.0,
+            });
+        }
+        let sheet = timesheets.last_mut().unwrap();
+
+        let date = entry.clock_in.get(..10).unwrap_or_default().to_string();
+        match sheet.days.last_mut() {
+            Some(day) if day.date == date => day.hours += hours,
+            _ => sheet.days.push(TimesheetDay { date, hours, regular: 0.0, overtime: 0.0 }),
+        }
+    }
+
+    for sheet in &mut timesheets {
+        let mut week = None;
+        let mut week_regular = 0.0;
+        for day in &mut sheet.days {
+            let iso_week = NaiveDate::parse_from_str(&day.date, "%Y-%m-%d").map(|d| d.iso_week()).ok();
+            if iso_week != week {
+                week = iso_week;
+                week_regular = 0.0;
+            }
+
+            let daily_overtime = if daily_limit > 0.0 { (day.hours - daily_limit).max(0.0) } else { 0.0 };
+            let mut regular = day.hours - daily_overtime;
+            let weekly_overtime = if weekly_limit > 0.0 {
+                (regular - (weekly_limit - week_regular).max(0.0)).max(0.0)
+            } else {
+                0.0
+            };
+            regular -= weekly_overtime;
+            week_regular += regular;
+
+            day.regular = regular;
+            day.overtime = daily_overtime + weekly_overtime;
+            sheet.hours += day.hours;
+            sheet.regular += day.regular;
+            sheet.overtime += day.overtime;
+        }
+
+        sheet.scheduled = conn.query_row(
+            "SELECT IFNULL(SUM(julianday(ends_at) - julianday(starts_at)), 0) * 24
+            FROM shifts WHERE employee_id = ?1 AND date(starts_at) BETWEEN ?2 AND ?3",
+            params![sheet.employee_id, start_date, end_date],
+            |row| row.get(0),
+        )?;
+    }
+
+    Ok(timesheets)
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+// Timesheets as CSV for payroll: one row per employee and day, then a total
+// row per employee
+pub fn timesheet_csv(conn: &Connection, start_date: &str, end_date: &str) -> Result<String> {
+    let mut out = String::from("employee_id,employee,date,hours,regular,overtime\n");
+    for sheet in get_timesheets(conn, start_date, end_date)? {
+        let name = csv_field(&sheet.employee_name);
+        for day in &sheet.days {
+            out.push_str(&format!(
+                "{},{},{},{:.2},{:.2},{:.2}\n",
+                sheet.employee_id, name, day.date, day.hours, day.regular, day.overtime
+            ));
+        }
+        out.push_str(&format!(
+            "{},{},total,{:.2},{:.2},{:.2}\n",
+            sheet.employee_id, name, sheet.hours, sheet.regular, sheet.overtime
+        ));
+    }
+    Ok(out)
+}
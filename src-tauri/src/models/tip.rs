@@ -0,0 +1,243 @@
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+
+use crate::error::{AppError, Result};
+use crate::models::settings::{get_setting, get_setting_f64};
+use crate::validation;
+
+// Tip pooling settings:
+// tip_pool_mode    "none" (everyone keeps their own tips), "equal" (pool split
+//                  evenly) or "hours" (pool split by hours clocked)
+// tip_pool_percent share of each employee's tips that goes into the pool (default 100)
+//
+// Tips and service charges are not part of a sale's total (and so never
+// count as revenue); they are held in the `tips` table as money owed to
+// staff, and the payments on a sale cover total + service charge + tips.
+
+#[derive(Debug, Serialize)]
+pub struct ServiceChargeRule {
+    pub id: i64,
+    pub name: String,
+    pub min_guests: i64,
+    pub percent: f64,
+    pub active: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TipPayout {
+    pub employee_id: i32,
+    pub employee_name: String,
+    pub collected: f64,
+    pub hours: f64,
+    pub payout: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TipPayoutReport {
+    pub mode: String,
+    pub pool_percent: f64,
+    pub total_tips: f64,
+    pub pool: f64,
+    pub payouts: Vec<TipPayout>,
+}
+
+// Record a tip on a sale, credited to the server who rang it up. A tip added
+// to a card payment raises that payment by the tip (tip adjust); otherwise the
+// tip is expected to be part of the tenders the cashier enters. The tip is
+// tied to the server's open drawer for reconciliation. Only completed sales
+// that are neither voided nor refunds take tips.
+pub fn add_tip(conn: &Connection, sale_id: i64, amount: f64, payment_id: Option<i64>) -> Result<i64> {
+    let amount = validation::amount("amount", amount)?;
+    let tx = conn.unchecked_transaction()?;
+    let completed: bool = tx
+        .query_row(
+            "SELECT receipt_number IS NOT NULL AND voided = 0 AND refund_of IS NULL FROM sales WHERE id = ?1",
+            params![sale_id],
+            |row| row.get(0),
+        )
+        .optional()?
+        .ok_or_else(|| AppError::not_found("sale"))?;
+    if !completed {
+        return Err(AppError::Conflict {
+            field: Some("sale".to_string()),
+            code: "not_completed".to_string(),
+            message: "tips can only be added to completed sales".to_string(),
+        });
+    }
+    tx.execute(
+        "INSERT INTO tips (sale_id, employee_id, amount, payment_id, drawer_session_id)
+        SELECT s.id, s.employee_id, ?2, p.id, IFNULL(p.drawer_session_id,
+            (SELECT id FROM drawer_sessions WHERE employee_id = s.employee_id AND closed_at IS NULL))
+        FROM sales s LEFT JOIN payments p ON p.id = ?3 AND p.sale_id = s.id
+        WHERE s.id = ?1 AND (?3 IS NULL OR p.id IS NOT NULL)",
+        params![sale_id, amount, payment_id],
+    )?;
+    if tx.changes() == 0 {
+        return Err(AppError::not_found("sale"));
+    }
+    let id = tx.last_insert_rowid();
+
+    if let Some(payment_id) = payment_id {
+        tx.execute(
+            "UPDATE payments SET amount = amount + ?1 WHERE id = ?2",
+            params![amount, payment_id],
+        )?;
+    }
+
+    tx.commit()?;
+    Ok(id)
+}
+
+pub fn add_service_charge_rule(conn: &Connection, name: &str, min_guests: i64, percent: f64) -> Result<i64> {
+    let name = validation::name("name", name)?;
+    validation::guests(Some(min_guests))?;
+    let percent = validation::percent("percent", percent)?;
+    conn.execute(
+        "INSERT INTO service_charge_rules (name, min_guests, percent) VALUES (?1, ?2, ?3)",
+        params![name, min_guests, percent],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+pub fn set_service_charge_rule_active(conn: &Connection, id: i64, active: bool) -> Result<usize> {
+    Ok(conn.execute(
+        "UPDATE service_charge_rules SET active = ?1 WHERE id = ?2",
+        params![active, id],
+    )?)
+}
+
+pub fn delete_service_charge_rule(conn: &Connection, id: i64) -> Result<usize> {
+    Ok(conn.execute("DELETE FROM service_charge_rules WHERE id = ?1", params![id])?)
+}
+
+pub fn get_service_charge_rules(conn: &Connection) -> Result<Vec<ServiceChargeRule>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, name, min_guests, percent, active FROM service_charge_rules ORDER BY min_guests",
+    )?;
+    let rows = stmt.query_map([], |row| {
+        Ok(ServiceChargeRule {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            min_guests: row.get(2)?,
+            percent: row.get(3)?,
+            active: row.get(4)?,
+        })
+    })?;
+
+    let mut rules = Vec::new();
+    for rule in rows {
+        rules.push(rule?);
+    }
+    Ok(rules)
+}
+
+// Percent charged for a party of `guests`: the active rule with the largest
+// minimum the party reaches, or 0 if none applies
+pub fn service_charge_percent(conn: &Connection, guests: Option<i64>) -> Result<f64> {
+    let Some(guests) = guests else { return Ok(0.0) };
+    Ok(conn
+        .query_row(
+            "SELECT percent FROM service_charge_rules
+            WHERE active = 1 AND min_guests <= ?1
+            ORDER BY min_guests DESC LIMIT 1",
+            params![guests],
+            |row| row.get(0),
+        )
+        .optional()?
+        .unwrap_or(0.0))
+}
+
+// Called when a sale is completed: the service charge becomes a tip owed to
+// the server
+pub fn record_service_charge(conn: &Connection, sale_id: i64) -> Result<()> {
+    conn.execute(
+        "INSERT INTO tips (sale_id, employee_id, amount, kind, drawer_session_id)
+        SELECT s.id, s.employee_id, s.service_charge, 'service_charge',
+            (SELECT id FROM drawer_sessions WHERE employee_id = s.employee_id AND closed_at IS NULL)
+        FROM sales s
+        WHERE s.id = ?1 AND s.service_charge != 0
+            AND NOT EXISTS(SELECT 1 FROM tips WHERE sale_id = s.id AND kind = 'service_charge')",
+        params![sale_id],
+    )?;
+    Ok(())
+}
+
+// Called when a sale is refunded or voided: tips and service charge are given
+// back, still charged to the servers who received them. A void books the
+// reversal against the voided sale itself.
+pub fn reverse_tips(conn: &Connection, sale_id: i64, refund_id: i64) -> Result<()> {
+    conn.execute(
+        "INSERT INTO tips (sale_id, employee_id, amount, kind, drawer_session_id)
+        SELECT ?2, t.employee_id, -t.amount, t.kind,
+            (SELECT d.id FROM drawer_sessions d JOIN sales r ON r.employee_id = d.employee_id
+                WHERE r.id = ?2 AND d.closed_at IS NULL)
+        FROM tips t WHERE t.sale_id = ?1",
+        params![sale_id, refund_id],
+    )?;
+    Ok(())
+}
+
+// What each employee takes home in tips for a date range. Employees who
+// collected tips or clocked hours in the range take part in the pool.
+pub fn get_tip_payouts(conn: &Connection, start_date: Option<&str>, end_date: Option<&str>) -> Result<TipPayoutReport> {
+    let start = start_date.unwrap_or("1970-01-01");
+    let end = end_date.unwrap_or("9999-12-31");
+
+    let mode = get_setting(conn, "tip_pool_mode")?.unwrap_or_else(|| "none".to_string());
+    let pool_percent = if mode == "none" {
+        0.0
+    } else {
+        get_setting_f64(conn, "tip_pool_percent", 100.0)?.clamp(0.0, 100.0)
+    };
+
+    let mut stmt = conn.prepare(
+        "SELECT e.id, e.name,
+                IFNULL((SELECT SUM(t.amount) FROM tips t JOIN sales s ON t.sale_id = s.id
+                    WHERE t.employee_id = e.id AND s.voided = 0
+                        AND date(s.timestamp) BETWEEN ?1 AND ?2), 0),
+                IFNULL((SELECT SUM(julianday(clock_out) - julianday(clock_in)) * 24 FROM time_entries
+                    WHERE employee_id = e.id AND clock_out IS NOT NULL
+                        AND date(clock_in) BETWEEN ?1 AND ?2), 0)
+        FROM employees e
+        ORDER BY e.name",
+    )?;
+    let rows = stmt.query_map(params![start, end], |row| {
+        Ok(TipPayout {
+            employee_id: row.get(0)?,
+            employee_name: row.get(1)?,
+            collected: row.get(2)?,
+            hours: row.get(3)?,
+            payout: 0.0,
+        })
+    })?;
+
+    let mut payouts = Vec::new();
+    for payout in rows {
+        let payout = payout?;
+        if payout.collected != 0.0 || payout.hours > 0.0 {
+            payouts.push(payout);
+        }
+    }
+
+    let total_tips: f64 = payouts.iter().map(|p| p.collected).sum();
+    let pool = total_tips * pool_percent / 100.0;
+    let total_hours: f64 = payouts.iter().map(|p| p.hours).sum();
+    let participants = payouts.len() as f64;
+
+    for payout in &mut payouts {
+        let share = match mode.as_str() {
+            "hours" if total_hours > 0.0 => payout.hours / total_hours,
+            _ if participants > 0.0 => 1.0 / participants,
+            _ => 0.0,
+        };
+        payout.payout = payout.collected * (1.0 - pool_percent / 100.0) + pool * share;
+    }
+
+    Ok(TipPayoutReport {
+        mode,
+        pool_percent,
+        total_tips,
+        pool,
+        payouts,
+    })
+}
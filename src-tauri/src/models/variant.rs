@@ -0,0 +1,119 @@
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{AppError, Result};
+use crate::models::products::{add_product, product_from_row, Product, PRODUCT_COLUMNS};
+use crate::models::stock::STOCK_LEVEL;
+use crate::validation;
+
+// One attribute of a variant, e.g. size = Large
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VariantAttribute {
+    pub name: String,
+    pub value: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Variant {
+    pub product: Product,
+    pub attributes: Vec<VariantAttribute>,
+    // None when stock is not tracked for the variant
+    pub stock: Option<f64>,
+}
+
+// Attribute set compared case-insensitively and in any order
+fn attribute_key(attributes: &[VariantAttribute]) -> Vec<(String, String)> {
+    let mut key: Vec<_> = attributes
+        .iter()
+        .map(|a| (a.name.trim().to_lowercase(), a.value.trim().to_lowercase()))
+        .collect();
+    key.sort();
+    key
+}
+
+// Add a variant of `parent_id`. A variant is a product of its own with its
+// price, barcode and stock, named after the parent and its attribute values
+// and sold in the parent's unit. Variants cannot be nested, and no two
+// variants of a product may have the same attributes.
+pub fn add_variant(
+    conn: &Connection,
+    parent_id: i32,
+    attributes: &[VariantAttribute],
+    price: f64,
+    barcode: Option<&str>,
+) -> Result<i64> {
+    let (parent_name, unit, grandparent): (String, String, Option<i32>) = conn
+        .query_row(
+            "SELECT name, unit, parent_id FROM products WHERE id = ?1",
+            params![parent_id],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )
+        .optional()?
+        .ok_or_else(|| AppError::not_found("product"))?;
+    if grandparent.is_some() {
+        return Err(AppError::validation("parent_id", "invalid_format", "a variant cannot have variants"));
+    }
+    if attributes.is_empty() {
+        return Err(AppError::validation("attributes", "required", "a variant needs at least one attribute"));
+    }
+
+    let mut cleaned: Vec<VariantAttribute> = Vec::new();
+    for attribute in attributes {
+        let name = validation::name("attribute", &attribute.name)?;
+        let value = validation::name("value", &attribute.value)?;
+        if cleaned.iter().any(|a| a.name.to_lowercase() == name.to_lowercase()) {
+            return Err(AppError::validation("attribute", "duplicate", format!("attribute {} is given twice", name)));
+        }
+        cleaned.push(VariantAttribute { name: name.to_string(), value: value.to_string() });
+    }
+    let key = attribute_key(&cleaned);
+    if get_variants(conn, parent_id)?.iter().any(|v| attribute_key(&v.attributes) == key) {
+        return Err(AppError::Conflict {
+            field: Some("attributes".to_string()),
+            code: "duplicate".to_string(),
+            message: "the product already has a variant with these attributes".to_string(),
+        });
+    }
+
+    let values: Vec<&str> = cleaned.iter().map(|a| a.value.as_str()).collect();
+    let name = format!("{} ({})", parent_name, values.join(" / "));
+
+    let tx = conn.unchecked_transaction()?;
+    let id = add_product(&tx, &name, price, barcode, Some(&unit))?;
+    tx.execute(
+        "UPDATE products SET parent_id = ?1, category = (SELECT category FROM products WHERE id = ?1) WHERE id = ?2",
+        params![parent_id, id],
+    )?;
+    for attribute in &cleaned {
+        tx.execute(
+            "INSERT INTO variant_attributes (product_id, name, value) VALUES (?1, ?2, ?3)",
+            params![id, attribute.name, attribute.value],
+        )?;
+    }
+    tx.commit()?;
+    Ok(id)
+}
+
+pub fn get_variants(conn: &Connection, parent_id: i32) -> Result<Vec<Variant>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {}, {} FROM products p WHERE p.parent_id = ?1 ORDER BY p.id",
+        PRODUCT_COLUMNS, STOCK_LEVEL
+    ))?;
+    let rows = stmt.query_map(params![parent_id], |row| Ok((product_from_row(row)?, row.get(11)?)))?;
+
+    let mut attribute_stmt =
+        conn.prepare("SELECT name, value FROM variant_attributes WHERE product_id = ?1 ORDER BY rowid")?;
+    let mut variants = Vec::new();
+    for row in rows {
+        let (product, stock) = row?;
+        let attributes = attribute_stmt.query_map(params![product.id], |row| {
+            Ok(VariantAttribute { name: row.get(0)?, value: row.get(1)? })
+        })?;
+        let mut list = Vec::new();
+        for attribute in attributes {
+            list.push(attribute?);
+        }
+        variants.push(Variant { product, attributes: list, stock });
+    }
+    Ok(variants)
+}
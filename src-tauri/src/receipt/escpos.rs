@@ -0,0 +1,371 @@
+use super::{fit_columns, Align, Block, ReceiptOptions};
+
+const ESC: u8 = 0x1B;
+const GS: u8 = 0x1D;
+const LF: u8 = 0x0A;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CodePage {
+    Pc437,
+    Wpc1252,
+    Wpc1256,
+}
+
+impl CodePage {
+    pub fn parse(value: &str) -> CodePage {
+        match value.trim().to_lowercase().as_str() {
+            "wpc1252" | "cp1252" | "1252" => CodePage::Wpc1252,
+            "wpc1256" | "cp1256" | "1256" | "arabic" => CodePage::Wpc1256,
+            _ => CodePage::Pc437,
+        }
+    }
+
+    // Table number for `ESC t n` (Epson numbering, followed by most clones)
+    fn number(self) -> u8 {
+        match self {
+            CodePage::Pc437 => 0,
+            CodePage::Wpc1252 => 16,
+            CodePage::Wpc1256 => 50,
+        }
+    }
+
+    fn table(self) -> &'static [char; 128] {
+        match self {
+            CodePage::Pc437 => &PC437,
+            CodePage::Wpc1252 => &WPC1252,
+            CodePage::Wpc1256 => &WPC1256,
+        }
+    }
+
+    // Characters missing from the code page are printed as '?'
+    pub fn encode(self, text: &str) -> Vec<u8> {
+        let table = self.table();
+        text.chars()
+            .map(|c| {
+                if c.is_ascii() {
+                    c as u8
+                } else {
+                    table
+                        .iter()
+                        .position(|&t| t == c)
+                        .map(|i| 0x80 + i as u8)
+                        .unwrap_or(b'?')
+                }
+            })
+            .collect()
+    }
+}
+
+// 1-bit raster image, rows packed MSB first (the layout `GS v 0` expects)
+#[derive(Debug, Clone)]
+pub struct Bitmap {
+    pub width: usize,
+    pub height: usize,
+    pub data: Vec<u8>,
+}
+
+impl Bitmap {
+    // Parse a binary PBM (P4) file. The format is already packed exactly like
+    // ESC/POS raster data, which keeps image handling dependency free.
+    pub fn from_pbm(bytes: &[u8]) -> Option<Bitmap> {
+        let mut pos = 0;
+        let mut fields = Vec::new();
+        while fields.len() < 3 {
+            while pos < bytes.len() && (bytes[pos].is_ascii_whitespace() || bytes[pos] == b'#') {
+                if bytes[pos] == b'#' {
+                    while pos < bytes.len() && bytes[pos] != b'\n' {
+                        pos += 1;
+                    }
+                } else {
+                    pos += 1;
+                }
+            }
+            let start = pos;
+            while pos < bytes.len() && !bytes[pos].is_ascii_whitespace() {
+                pos += 1;
+            }
+            fields.push(std::str::from_utf8(&bytes[start..pos]).ok()?);
+        }
+        if fields[0] != "P4" {
+            return None;
+        }
+        let width: usize = fields[1].parse().ok()?;
+        let height: usize = fields[2].parse().ok()?;
+        let data = bytes.get(pos + 1..pos + 1 + width.div_ceil(8) * height)?.to_vec();
+
+        Some(Bitmap { width, height, data })
+    }
+
+    pub fn width_bytes(&self) -> usize {
+        self.width.div_ceil(8)
+    }
+}
+
+// Encode a laid out receipt as an ESC/POS byte stream
+pub fn encode(blocks: &[Block], options: &ReceiptOptions) -> Vec<u8> {
+    let columns = options.paper.columns();
+    let mut out = vec![ESC, b'@', ESC, b't', options.code_page.number()];
+
+    for block in blocks {
+        match block {
+            Block::Logo => {
+                if let Some(logo) = &options.logo {
+                    align(&mut out, &Align::Center);
+                    raster(&mut out, logo);
+                }
+            }
+            Block::Text { text, align: a, bold, large } => {
+                align(&mut out, a);
+                out.extend([ESC, b'E', *bold as u8]);
+                if *large {
+                    out.extend([GS, b'!', 0x11]);
+                }
+                out.extend(options.code_page.encode(&printable(text, options.rtl)));
+                out.push(LF);
+                if *large {
+                    out.extend([GS, b'!', 0x00]);
+                }
+                out.extend([ESC, b'E', 0]);
+            }
+            Block::Columns { left, right, bold } => {
+                align(&mut out, &Align::Left);
+                out.extend([ESC, b'E', *bold as u8]);
+                let line = fit_columns(&printable(left, options.rtl), &printable(right, options.rtl), columns);
+                out.extend(options.code_page.encode(&line));
+                out.push(LF);
+                out.extend([ESC, b'E', 0]);
+            }
+            Block::Rule => {
+                align(&mut out, &Align::Left);
+                out.extend("-".repeat(columns).bytes());
+                out.push(LF);
+            }
+            Block::Barcode(data) => {
+                align(&mut out, &Align::Center);
+                barcode(&mut out, data);
+            }
+            Block::Qr(data) => {
+                align(&mut out, &Align::Center);
+                qr(&mut out, data);
+            }
+            Block::Feed(lines) => out.extend([ESC, b'd', *lines]),
+            Block::Cut => out.extend([GS, b'V', 66, 3]),
+        }
+    }
+
+    out
+}
+
+// ESC p: pulse the cash drawer kick-out connector (pin 2)
+pub fn drawer_kick() -> Vec<u8> {
+    vec![ESC, b'p', 0, 25, 250]
+}
+
+fn align(out: &mut Vec<u8>, align: &Align) {
+    let n = match align {
+        Align::Left => 0,
+        Align::Center => 1,
+        Align::Right => 2,
+    };
+    out.extend([ESC, b'a', n]);
+}
+
+// GS v 0: print raster bit image
+fn raster(out: &mut Vec<u8>, bitmap: &Bitmap) {
+    let x = bitmap.width_bytes();
+    let y = bitmap.height;
+    out.extend([GS, b'v', b'0', 0, x as u8, (x >> 8) as u8, y as u8, (y >> 8) as u8]);
+    out.extend(&bitmap.data);
+    out.push(LF);
+}
+
+fn printable(text: &str, rtl: bool) -> String {
+    if rtl {
+        visual_order(text)
+    } else {
+        text.to_string()
+    }
+}
+
+fn is_rtl(c: char) -> bool {
+    matches!(c, '\u{0590}'..='\u{08FF}' | '\u{FB1D}'..='\u{FDFF}' | '\u{FE70}'..='\u{FEFF}')
+}
+
+fn mirror(c: char) -> char {
+    match c {
+        '(' => ')',
+        ')' => '(',
+        '[' => ']',
+        ']' => '[',
+        '<' => '>',
+        '>' => '<',
+        _ => c,
+    }
+}
+
+// Printers lay text out strictly left to right, so right-to-left text is sent
+// in visual order: runs of Latin letters and digits keep their order, every
+// thing else is reversed. Neutral characters between two left-to-right
+// characters stay with them (e.g. "12.50"). Letter shaping is left to the
+// printer's code page.
+pub fn visual_order(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let strong: Vec<Option<bool>> = chars
+        .iter()
+        .map(|&c| {
+            if is_rtl(c) {
+                Some(true)
+            } else if c.is_alphanumeric() {
+                Some(false)
+            } else {
+                None
+            }
+        })
+        .collect();
+
+    let rtl: Vec<bool> = (0..chars.len())
+        .map(|i| match strong[i] {
+            Some(dir) => dir,
+            None => {
+                let before = strong[..i].iter().rev().find_map(|d| *d);
+                let after = strong[i + 1..].iter().find_map(|d| *d);
+                !(before == Some(false) && after == Some(false))
+            }
+        })
+        .collect();
+
+    let mut runs: Vec<(bool, Vec<char>)> = Vec::new();
+    for (i, &c) in chars.iter().enumerate() {
+        match runs.last_mut() {
+            Some((dir, run)) if *dir == rtl[i] => run.push(c),
+            _ => runs.push((rtl[i], vec![c])),
+        }
+    }
+
+    runs.iter()
+        .rev()
+        .flat_map(|(dir, run)| {
+            if *dir {
+                run.iter().rev().map(|&c| mirror(c)).collect::<Vec<_>>()
+            } else {
+                run.clone()
+            }
+        })
+        .collect()
+}
+
+// Longest CODE128 data: its length goes in one byte with the two code set bytes
+const MAX_BARCODE: usize = 253;
+
+// CODE128 (code set B) with the human readable text below. Data too long for
+// a barcode is printed as plain text instead.
+fn barcode(out: &mut Vec<u8>, data: &str) {
+    let data: Vec<u8> = data.bytes().filter(|b| (0x20..0x7F).contains(b)).collect();
+    if data.len() > MAX_BARCODE {
+        out.extend(data);
+        out.push(LF);
+        return;
+    }
+    out.extend([GS, b'h', 80, GS, b'w', 2, GS, b'H', 2]);
+    out.extend([GS, b'k', 73, (data.len() + 2) as u8, b'{', b'B']);
+    out.extend(data);
+    out.push(LF);
+}
+
+// QR code, model 2, module size 6, error correction M
+fn qr(out: &mut Vec<u8>, data: &str) {
+    let len = data.len() + 3;
+    out.extend([GS, b'(', b'k', 4, 0, 49, 65, 50, 0]);
+    out.extend([GS, b'(', b'k', 3, 0, 49, 67, 6]);
+    out.extend([GS, b'(', b'k', 3, 0, 49, 69, 49]);
+    out.extend([GS, b'(', b'k', len as u8, (len >> 8) as u8, 49, 80, 48]);
+    out.extend(data.as_bytes());
+    out.extend([GS, b'(', b'k', 3, 0, 49, 81, 48]);
+    out.push(LF);
+}
+
+const PC437: [char; 128] = [
+    '\u{00C7}', '\u{00FC}', '\u{00E9}', '\u{00E2}', '\u{00E4}', '\u{00E0}', '\u{00E5}', '\u{00E7}',
+    '\u{00EA}', '\u{00EB}', '\u{00E8}', '\u{00EF}', '\u{00EE}', '\u{00EC}', '\u{00C4}', '\u{00C5}',
+    '\u{00C9}', '\u{00E6}', '\u{00C6}', '\u{00F4}', '\u{00F6}', '\u{00F2}', '\u{00FB}', '\u{00F9}',
+    '\u{00FF}', '\u{00D6}', '\u{00DC}', '\u{00A2}', '\u{00A3}', '\u{00A5}', '\u{20A7}', '\u{0192}',
+    '\u{00E1}', '\u{00ED}', '\u{00F3}', '\u{00FA}', '\u{00F1}', '\u{00D1}', '\u{00AA}', '\u{00BA}',
+    '\u{00BF}', '\u{2310}', '\u{00AC}', '\u{00BD}', '\u{00BC}', '\u{00A1}', '\u{00AB}', '\u{00BB}',
+    '\u{2591}', '\u{2592}', '\u{2593}', '\u{2502}', '\u{2524}', '\u{2561}', '\u{2562}', '\u{2556}',
+    '\u{2555}', '\u{2563}', '\u{2551}', '\u{2557}', '\u{255D}', '\u{255C}', '\u{255B}', '\u{2510}',
+    '\u{2514}', '\u{2534}', '\u{252C}', '\u{251C}', '\u{2500}', '\u{253C}', '\u{255E}', '\u{255F}',
+    '\u{255A}', '\u{2554}', '\u{2569}', '\u{2566}', '\u{2560}', '\u{2550}', '\u{256C}', '\u{2567}',
+    '\u{2568}', '\u{2564}', '\u{2565}', '\u{2559}', '\u{2558}', '\u{2552}', '\u{2553}', '\u{256B}',
+    '\u{256A}', '\u{2518}', '\u{250C}', '\u{2588}', '\u{2584}', '\u{258C}', '\u{2590}', '\u{2580}',
+    '\u{03B1}', '\u{00DF}', '\u{0393}', '\u{03C0}', '\u{03A3}', '\u{03C3}', '\u{00B5}', '\u{03C4}',
+    '\u{03A6}', '\u{0398}', '\u{03A9}', '\u{03B4}', '\u{221E}', '\u{03C6}', '\u{03B5}', '\u{2229}',
+    '\u{2261}', '\u{00B1}', '\u{2265}', '\u{2264}', '\u{2320}', '\u{2321}', '\u{00F7}', '\u{2248}',
+    '\u{00B0}', '\u{2219}', '\u{00B7}', '\u{221A}', '\u{207F}', '\u{00B2}', '\u{25A0}', '\u{00A0}',
+];
+
+const WPC1252: [char; 128] = [
+    '\u{20AC}', '\u{0000}', '\u{201A}', '\u{0192}', '\u{201E}', '\u{2026}', '\u{2020}', '\u{2021}',
+    '\u{02C6}', '\u{2030}', '\u{0160}', '\u{2039}', '\u{0152}', '\u{0000}', '\u{017D}', '\u{0000}',
+    '\u{0000}', '\u{2018}', '\u{2019}', '\u{201C}', '\u{201D}', '\u{2022}', '\u{2013}', '\u{2014}',
+    '\u{02DC}', '\u{2122}', '\u{0161}', '\u{203A}', '\u{0153}', '\u{0000}', '\u{017E}', '\u{0178}',
+    '\u{00A0}', '\u{00A1}', '\u{00A2}', '\u{00A3}', '\u{00A4}', '\u{00A5}', '\u{00A6}', '\u{00A7}',
+    '\u{00A8}', '\u{00A9}', '\u{00AA}', '\u{00AB}', '\u{00AC}', '\u{00AD}', '\u{00AE}', '\u{00AF}',
+    '\u{00B0}', '\u{00B1}', '\u{00B2}', '\u{00B3}', '\u{00B4}', '\u{00B5}', '\u{00B6}', '\u{00B7}',
+    '\u{00B8}', '\u{00B9}', '\u{00BA}', '\u{00BB}', '\u{00BC}', '\u{00BD}', '\u{00BE}', '\u{00BF}',
+    '\u{00C0}', '\u{00C1}', '\u{00C2}', '\u{00C3}', '\u{00C4}', '\u{00C5}', '\u{00C6}', '\u{00C7}',
+    '\u{00C8}', '\u{00C9}', '\u{00CA}', '\u{00CB}', '\u{00CC}', '\u{00CD}', '\u{00CE}', '\u{00CF}',
+    '\u{00D0}', '\u{00D1}', '\u{00D2}', '\u{00D3}', '\u{00D4}', '\u{00D5}', '\u{00D6}', '\u{00D7}',
+    '\u{00D8}', '\u{00D9}', '\u{00DA}', '\u{00DB}', '\u{00DC}', '\u{00DD}', '\u{00DE}', '\u{00DF}',
+    '\u{00E0}', '\u{00E1}', '\u{00E2}', '\u{00E3}', '\u{00E4}', '\u{00E5}', '\u{00E6}', '\u{00E7}',
+    '\u{00E8}', '\u{00E9}', '\u{00EA}', '\u{00EB}', '\u{00EC}', '\u{00ED}', '\u{00EE}', '\u{00EF}',
+    '\u{00F0}', '\u{00F1}', '\u{00F2}', '\u{00F3}', '\u{00F4}', '\u{00F5}', '\u{00F6}', '\u{00F7}',
+    '\u{00F8}', '\u{00F9}', '\u{00FA}', '\u{00FB}', '\u{00FC}', '\u{00FD}', '\u{00FE}', '\u{00FF}',
+];
+
+const WPC1256: [char; 128] = [
+    '\u{20AC}', '\u{067E}', '\u{201A}', '\u{0192}', '\u{201E}', '\u{2026}', '\u{2020}', '\u{2021}',
+    '\u{02C6}', '\u{2030}', '\u{0679}', '\u{2039}', '\u{0152}', '\u{0686}', '\u{0698}', '\u{0688}',
+    '\u{06AF}', '\u{2018}', '\u{2019}', '\u{201C}', '\u{201D}', '\u{2022}', '\u{2013}', '\u{2014}',
+    '\u{06A9}', '\u{2122}', '\u{0691}', '\u{203A}', '\u{0153}', '\u{200C}', '\u{200D}', '\u{06BA}',
+    '\u{00A0}', '\u{060C}', '\u{00A2}', '\u{00A3}', '\u{00A4}', '\u{00A5}', '\u{00A6}', '\u{00A7}',
+    '\u{00A8}', '\u{00A9}', '\u{06BE}', '\u{00AB}', '\u{00AC}', '\u{00AD}', '\u{00AE}', '\u{00AF}',
+    '\u{00B0}', '\u{00B1}', '\u{00B2}', '\u{00B3}', '\u{00B4}', '\u{00B5}', '\u{00B6}', '\u{00B7}',
+    '\u{00B8}', '\u{00B9}', '\u{061B}', '\u{00BB}', '\u{00BC}', '\u{00BD}', '\u{00BE}', '\u{061F}',
+    '\u{06C1}', '\u{0621}', '\u{0622}', '\u{0623}', '\u{0624}', '\u{0625}', '\u{0626}', '\u{0627}',
+    '\u{0628}', '\u{0629}', '\u{062A}', '\u{062B}', '\u{062C}', '\u{062D}', '\u{062E}', '\u{062F}',
+    '\u{0630}', '\u{0631}', '\u{0632}', '\u{0633}', '\u{0634}', '\u{0635}', '\u{0636}', '\u{00D7}',
+    '\u{0637}', '\u{0638}', '\u{0639}', '\u{063A}', '\u{0640}', '\u{0641}', '\u{0642}', '\u{0643}',
+    '\u{00E0}', '\u{0644}', '\u{00E2}', '\u{0645}', '\u{0646}', '\u{0647}', '\u{0648}', '\u{00E7}',
+    '\u{00E8}', '\u{00E9}', '\u{00EA}', '\u{00EB}', '\u{0649}', '\u{064A}', '\u{00EE}', '\u{00EF}',
+    '\u{064B}', '\u{064C}', '\u{064D}', '\u{064E}', '\u{00F4}', '\u{064F}', '\u{0650}', '\u{00F7}',
+    '\u{0651}', '\u{00F9}', '\u{0652}', '\u{00FB}', '\u{00FC}', '\u{200E}', '\u{200F}', '\u{06D2}',
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_arabic_in_wpc1256() {
+        assert_eq!(CodePage::Wpc1256.encode("اب 5"), vec![0xC7, 0xC8, b' ', b'5']);
+        assert_eq!(CodePage::Pc437.encode("ا"), b"?".to_vec());
+        assert_eq!(CodePage::parse("arabic"), CodePage::Wpc1256);
+    }
+
+    #[test]
+    fn orders_rtl_text_visually() {
+        assert_eq!(visual_order("مجموع 12.50"), "12.50 عومجم");
+        assert_eq!(visual_order("(ا)"), "(ا)");
+    }
+
+    #[test]
+    fn long_barcode_data_is_printed_as_text() {
+        let mut out = Vec::new();
+        barcode(&mut out, &"1".repeat(MAX_BARCODE));
+        assert_eq!(out[9..15], [GS, b'k', 73, 255, b'{', b'B']);
+        let mut out = Vec::new();
+        barcode(&mut out, &"1".repeat(MAX_BARCODE + 1));
+        assert!(!out.windows(2).any(|w| w == [GS, b'k']));
+        assert_eq!(out.len(), MAX_BARCODE + 2);
+    }
+}
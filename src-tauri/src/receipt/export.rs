@@ -0,0 +1,222 @@
+// Receipt documents for saving or emailing: a self-contained HTML page and a
+// single page PDF sized to the receipt paper.
+//
+// The PDF is written by hand with the built-in Courier fonts, so it stays
+// dependency free but can only show characters from Windows-1252; anything
+// else (Arabic included) would come out as '?', so right-to-left receipts are
+// refused as PDF and must be saved as HTML.
+
+use super::escpos::{Bitmap, CodePage};
+use super::{fit_columns, pad, Align, Block, ReceiptOptions};
+
+fn escape_html(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+// The logo as an inline SVG, one rect per run of black pixels
+fn logo_svg(bitmap: &Bitmap) -> String {
+    let row_bytes = bitmap.width_bytes();
+    let mut rects = String::new();
+    for y in 0..bitmap.height {
+        let mut x = 0;
+        while x < bitmap.width {
+            let black = |x: usize| bitmap.data[y * row_bytes + x / 8] & (0x80 >> (x % 8)) != 0;
+            if black(x) {
+                let start = x;
+                while x < bitmap.width && black(x) {
+                    x += 1;
+                }
+                rects.push_str(&format!(r#"<rect x="{}" y="{}" width="{}" height="1"/>"#, start, y, x - start));
+            } else {
+                x += 1;
+            }
+        }
+    }
+    format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 {w} {h}" width="{w}" height="{h}" shape-rendering="crispEdges">{}</svg>"#,
+        rects,
+        w = bitmap.width,
+        h = bitmap.height
+    )
+}
+
+// Layout positions are physical (a right-to-left template has already
+// mirrored its columns), so the page stays left-to-right and only the text
+// runs pick their own direction.
+pub fn render_html(blocks: &[Block], options: &ReceiptOptions, title: &str) -> String {
+    let mut body = String::new();
+
+    for block in blocks {
+        match block {
+            Block::Logo => {
+                if let Some(logo) = &options.logo {
+                    body.push_str(&format!("<div class=\"c\">{}</div>\n", logo_svg(logo)));
+                }
+            }
+            Block::Text { text, align, bold, large } => {
+                let mut class = match align {
+                    Align::Left => "l",
+                    Align::Center => "c",
+                    Align::Right => "r",
+                }
+                .to_string();
+                if *bold {
+                    class.push_str(" b");
+                }
+                if *large {
+                    class.push_str(" x");
+                }
+                body.push_str(&format!("<div class=\"{}\" dir=\"auto\">{}</div>\n", class, escape_html(text)));
+            }
+            Block::Columns { left, right, bold } => {
+                body.push_str(&format!(
+                    "<div class=\"row{}\"><span dir=\"auto\">{}</span><span dir=\"auto\">{}</span></div>\n",
+                    if *bold { " b" } else { "" },
+                    escape_html(left),
+                    escape_html(right)
+                ));
+            }
+            Block::Rule => body.push_str("<hr>\n"),
+            Block::Barcode(data) | Block::Qr(data) => {
+                body.push_str(&format!("<div class=\"c code\">{}</div>\n", escape_html(data)));
+            }
+            Block::Feed(_) | Block::Cut => {}
+        }
+    }
+
+    format!(
+        "<!DOCTYPE html>
+<html lang=\"{lang}\">
+<head>
+<meta charset=\"utf-8\">
+<title>{title}</title>
+<style>
+body {{ margin: 0; background: #fff; }}
+.receipt {{ width: {cols}ch; margin: 1em auto; font: 14px/1.4 monospace; color: #000; }}
+.l {{ text-align: left; }} .c {{ text-align: center; }} .r {{ text-align: right; }}
+.b {{ font-weight: bold; }} .x {{ font-size: 1.6em; }}
+.row {{ display: flex; justify-content: space-between; gap: 1ch; }}
+.code {{ letter-spacing: 0.2em; margin: 0.5em 0; }}
+hr {{ border: 0; border-top: 1px dashed #000; }}
+</style>
+</head>
+<body>
+<div class=\"receipt\">
+{body}</div>
+</body>
+</html>
+",
+        lang = if options.rtl { "ar" } else { "en" },
+        title = escape_html(title),
+        cols = options.paper.columns(),
+        body = body
+    )
+}
+
+// Points per millimetre
+pub const PT_PER_MM: f64 = 72.0 / 25.4;
+const MARGIN: f64 = 8.0;
+// Courier advances 0.6 em per character
+pub const COURIER_ADVANCE: f64 = 0.6;
+
+struct PdfLine {
+    text: Vec<u8>,
+    font: &'static str,
+    size: f64,
+}
+
+pub fn escape_pdf(bytes: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(bytes.len());
+    for &b in bytes {
+        if matches!(b, b'(' | b')' | b'\\') {
+            out.push(b'\\');
+        }
+        out.push(b);
+    }
+    out
+}
+
+pub fn render_pdf(blocks: &[Block], options: &ReceiptOptions) -> Vec<u8> {
+    let columns = options.paper.columns();
+    let paper_mm = match options.paper {
+        super::PaperWidth::Mm58 => 58.0,
+        super::PaperWidth::Mm80 => 80.0,
+    };
+    let page_width = paper_mm * PT_PER_MM;
+    let size = (page_width - 2.0 * MARGIN) / columns as f64 / COURIER_ADVANCE;
+    let encode = |text: &str| CodePage::Wpc1252.encode(text);
+
+    let mut lines = Vec::new();
+    for block in blocks {
+        let (text, bold, large, align) = match block {
+            Block::Text { text, align, bold, large } => (text.clone(), *bold, *large, align.clone()),
+            Block::Columns { left, right, bold } => (fit_columns(left, right, columns), *bold, false, Align::Left),
+            Block::Rule => ("-".repeat(columns), false, false, Align::Left),
+            Block::Barcode(data) | Block::Qr(data) => (data.clone(), false, false, Align::Center),
+            Block::Logo | Block::Feed(_) | Block::Cut => continue,
+        };
+        let (size, width) = if large { (size * 2.0, columns / 2) } else { (size, columns) };
+        let text = pad(&text, &align, width);
+        lines.push(PdfLine {
+            text: encode(text.trim_end()),
+            font: if bold { "F2" } else { "F1" },
+            size,
+        });
+    }
+
+    let leading = |line: &PdfLine| line.size * 1.2;
+    let page_height = lines.iter().map(leading).sum::<f64>() + 2.0 * MARGIN;
+
+    let mut content = Vec::new();
+    let mut y = page_height - MARGIN;
+    for line in &lines {
+        y -= leading(line);
+        content.extend(format!("BT /{} {:.2} Tf {:.2} {:.2} Td (", line.font, line.size, MARGIN, y + line.size * 0.2).bytes());
+        content.extend(escape_pdf(&line.text));
+        content.extend(b") Tj ET\n");
+    }
+
+    let objects: Vec<Vec<u8>> = vec![
+        b"<< /Type /Catalog /Pages 2 0 R >>".to_vec(),
+        b"<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_vec(),
+        format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {:.2} {:.2}] \
+            /Resources << /Font << /F1 4 0 R /F2 5 0 R >> >> /Contents 6 0 R >>",
+            page_width, page_height
+        )
+        .into_bytes(),
+        b"<< /Type /Font /Subtype /Type1 /BaseFont /Courier /Encoding /WinAnsiEncoding >>".to_vec(),
+        b"<< /Type /Font /Subtype /Type1 /BaseFont /Courier-Bold /Encoding /WinAnsiEncoding >>".to_vec(),
+        [format!("<< /Length {} >>\nstream\n", content.len()).into_bytes(), content, b"\nendstream".to_vec()].concat(),
+    ];
+
+    let mut out = b"%PDF-1.4\n".to_vec();
+    let mut offsets = Vec::new();
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(out.len());
+        out.extend(format!("{} 0 obj\n", i + 1).bytes());
+        out.extend(object);
+        out.extend(b"\nendobj\n");
+    }
+
+    let xref = out.len();
+    out.extend(format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).bytes());
+    for offset in offsets {
+        out.extend(format!("{:010} 00000 n \n", offset).bytes());
+    }
+    out.extend(
+        format!("trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n", objects.len() + 1, xref).bytes(),
+    );
+
+    out
+}
@@ -0,0 +1,302 @@
+pub mod escpos;
+pub mod export;
+pub mod printer;
+pub mod template;
+
+use rusqlite::{params, Connection};
+use serde::Serialize;
+
+use crate::error::{AppError, Result};
+use crate::models::bundle::{get_sale_components, SaleComponent};
+use crate::models::payment::{get_sale_payments, Payment};
+use crate::models::settings::{get_setting, get_setting_f64};
+use escpos::{Bitmap, CodePage};
+
+#[derive(Debug, Serialize)]
+pub struct ReceiptItem {
+    pub name: String,
+    pub quantity: f64,
+    pub unit: String,
+    pub price: f64,
+    pub extra_amount: f64,
+    pub total: f64,
+    // What went into a bundle, for the kitchen
+    pub components: Vec<SaleComponent>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Receipt {
+    pub sale_id: i64,
+    pub number: String,
+    pub employee_name: String,
+    pub timestamp: String,
+    pub items: Vec<ReceiptItem>,
+    pub subtotal: f64,
+    pub discount: f64,
+    pub tax: f64,
+    // Rate in force when the sale was completed, if it was recorded
+    pub tax_rate: Option<f64>,
+    pub total: f64,
+    // Paid on top of the total
+    pub service_charge: f64,
+    pub tips: f64,
+    pub payments: Vec<Payment>,
+    pub refund_of: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PaperWidth {
+    Mm58,
+    Mm80,
+}
+
+impl PaperWidth {
+    // Characters per line in font A
+    pub fn columns(self) -> usize {
+        match self {
+            PaperWidth::Mm58 => 32,
+            PaperWidth::Mm80 => 48,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReceiptCode {
+    None,
+    Barcode,
+    Qr,
+}
+
+// Printer settings, read from the `settings` table:
+// printer_paper_width ("58" | "80"), printer_code_page ("pc437" | "wpc1252" | "wpc1256"),
+// printer_logo (path to a PBM file), receipt_code ("barcode" | "qr" | "none"),
+// store_name, store_address, store_tax_id and tax_rate.
+// `rtl` comes from the template being printed.
+pub struct ReceiptOptions {
+    pub paper: PaperWidth,
+    pub code_page: CodePage,
+    pub logo: Option<Bitmap>,
+    pub code: ReceiptCode,
+    pub store_name: String,
+    pub store_address: String,
+    pub store_tax_id: String,
+    pub tax_rate: f64,
+    pub rtl: bool,
+}
+
+impl ReceiptOptions {
+    pub fn from_settings(conn: &Connection) -> Result<ReceiptOptions> {
+        let paper = match get_setting(conn, "printer_paper_width")?.as_deref() {
+            Some("58") => PaperWidth::Mm58,
+            _ => PaperWidth::Mm80,
+        };
+        let code_page = CodePage::parse(&get_setting(conn, "printer_code_page")?.unwrap_or_default());
+        let logo = get_setting(conn, "printer_logo")?
+            .and_then(|path| std::fs::read(path).ok())
+            .and_then(|bytes| Bitmap::from_pbm(&bytes));
+        let code = match get_setting(conn, "receipt_code")?.as_deref() {
+            Some("barcode") => ReceiptCode::Barcode,
+            Some("none") => ReceiptCode::None,
+            _ => ReceiptCode::Qr,
+        };
+
+        Ok(ReceiptOptions {
+            paper,
+            code_page,
+            logo,
+            code,
+            store_name: get_setting(conn, "store_name")?.unwrap_or_default(),
+            store_address: get_setting(conn, "store_address")?.unwrap_or_default(),
+            store_tax_id: get_setting(conn, "store_tax_id")?.unwrap_or_default(),
+            tax_rate: get_setting_f64(conn, "tax_rate", 0.0)?,
+            rtl: false,
+        })
+    }
+}
+
+// Rebuild a completed sale from its own rows. Item names, the cashier's name
+// and the tax rate are the values snapshotted with the sale, so renaming a
+// product or employee later does not change an old receipt. A sale without a
+// receipt number has not been completed and has no receipt.
+pub fn load_receipt(conn: &Connection, sale_id: i64) -> Result<Receipt> {
+    let (number, employee_name, timestamp, discount, tax, tax_rate, total, refund_of, service_charge, tips) = conn.query_row(
+        "SELECT s.receipt_number, COALESCE(s.employee_name, e.name, ''), s.timestamp,
+                s.discount, s.tax, s.tax_rate, s.total,
+                o.receipt_number,
+                s.service_charge,
+                IFNULL((SELECT SUM(amount) FROM tips WHERE sale_id = s.id AND kind = 'tip'), 0)
+        FROM sales s
+        LEFT JOIN employees e ON s.employee_id = e.id
+        LEFT JOIN sales o ON s.refund_of = o.id
+        WHERE s.id = ?1",
+        params![sale_id],
+        |row| {
+            Ok((
+                row.get::<_, Option<String>>(0)?,
+                row.get(1)?,
+                row.get(2)?,
+                row.get(3)?,
+                row.get(4)?,
+                row.get(5)?,
+                row.get(6)?,
+                row.get(7)?,
+                row.get(8)?,
+                row.get(9)?,
+            ))
+        },
+    )?;
+    let number = number.ok_or_else(|| AppError::Conflict {
+        field: Some("receipt".to_string()),
+        code: "not_completed".to_string(),
+        message: "the sale has not been completed".to_string(),
+    })?;
+
+    let mut stmt = conn.prepare(
+        "SELECT id, product_name, quantity, unit, price, extra_amount, ROUND(quantity * (price + extra_amount), 2)
+        FROM sale_items WHERE sale_id = ?1 ORDER BY id",
+    )?;
+    let rows = stmt.query_map(params![sale_id], |row| {
+        Ok((
+            row.get::<_, i64>(0)?,
+            ReceiptItem {
+                name: row.get(1)?,
+                quantity: row.get(2)?,
+                unit: row.get(3)?,
+                price: row.get(4)?,
+                extra_amount: row.get(5)?,
+                total: row.get(6)?,
+                components: Vec::new(),
+            },
+        ))
+    })?;
+    let mut items = Vec::new();
+    for row in rows {
+        let (id, mut item) = row?;
+        item.components = get_sale_components(conn, id)?;
+        items.push(item);
+    }
+
+    Ok(Receipt {
+        sale_id,
+        number,
+        employee_name,
+        timestamp,
+        subtotal: items.iter().map(|i| i.total).sum(),
+        items,
+        discount,
+        tax,
+        tax_rate,
+        total,
+        service_charge,
+        tips,
+        payments: get_sale_payments(conn, sale_id)?,
+        refund_of,
+    })
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+// Device independent receipt layout, rendered either to ESC/POS or to text
+#[derive(Debug, Clone)]
+pub enum Block {
+    Logo,
+    Text { text: String, align: Align, bold: bool, large: bool },
+    Columns { left: String, right: String, bold: bool },
+    Rule,
+    Barcode(String),
+    Qr(String),
+    Feed(u8),
+    Cut,
+}
+
+// Load a sale and lay it out with the template for `kind` ("receipt" or
+// "kitchen"). Without an explicit language the `<kind>_language` setting is used.
+pub fn render_receipt(
+    conn: &Connection,
+    sale_id: i64,
+    kind: &str,
+    language: Option<&str>,
+) -> Result<(Vec<Block>, ReceiptOptions)> {
+    let language = match language {
+        Some(language) => language.to_string(),
+        None => get_setting(conn, &format!("{}_language", kind))?.unwrap_or_else(|| "en".to_string()),
+    };
+    let template = template::resolve_template(conn, kind, &language)?;
+    let receipt = load_receipt(conn, sale_id)?;
+
+    let mut options = ReceiptOptions::from_settings(conn)?;
+    options.rtl = template.rtl;
+    Ok((template::render(&template, &receipt, &options), options))
+}
+
+// Reprint of a past sale: the normal receipt with a COPY banner at the top
+// and above the cut, so it cannot be mistaken for the original.
+pub fn render_copy(conn: &Connection, sale_id: i64, language: Option<&str>) -> Result<(Vec<Block>, ReceiptOptions)> {
+    let (mut blocks, options) = render_receipt(conn, sale_id, "receipt", language)?;
+
+    let banner = Block::Text {
+        text: if options.rtl { "*** نسخة ***" } else { "*** COPY ***" }.to_string(),
+        align: Align::Center,
+        bold: true,
+        large: true,
+    };
+    let end = blocks
+        .iter()
+        .rposition(|block| !matches!(block, Block::Cut | Block::Feed(_)))
+        .map_or(0, |i| i + 1);
+    blocks.insert(end, banner.clone());
+    blocks.insert(0, banner);
+
+    Ok((blocks, options))
+}
+
+pub fn money(value: f64) -> String {
+    format!("{:.2}", value)
+}
+
+// Left text and right text on one line, truncating the left side if needed
+pub fn fit_columns(left: &str, right: &str, width: usize) -> String {
+    let right_len = right.chars().count();
+    let room = width.saturating_sub(right_len + 1);
+    let left: String = left.chars().take(room).collect();
+    let padding = width.saturating_sub(left.chars().count() + right_len);
+    format!("{}{}{}", left, " ".repeat(padding), right)
+}
+
+fn pad(text: &str, align: &Align, width: usize) -> String {
+    let len = text.chars().count();
+    let space = width.saturating_sub(len);
+    match align {
+        Align::Left => text.to_string(),
+        Align::Center => format!("{}{}", " ".repeat(space / 2), text),
+        Align::Right => format!("{}{}", " ".repeat(space), text),
+    }
+}
+
+// Plain text preview of what the printer will produce
+pub fn render_text(blocks: &[Block], options: &ReceiptOptions) -> String {
+    let width = options.paper.columns();
+    let mut out = String::new();
+
+    for block in blocks {
+        let line = match block {
+            Block::Logo if options.logo.is_some() => pad("[LOGO]", &Align::Center, width),
+            Block::Logo => continue,
+            Block::Text { text, align, .. } => pad(text, align, width),
+            Block::Columns { left, right, .. } => fit_columns(left, right, width),
+            Block::Rule => "-".repeat(width),
+            Block::Barcode(data) => pad(&format!("[BARCODE {}]", data), &Align::Center, width),
+            Block::Qr(data) => pad(&format!("[QR {}]", data), &Align::Center, width),
+            Block::Feed(_) | Block::Cut => continue,
+        };
+        out.push_str(line.trim_end());
+        out.push('\n');
+    }
+
+    out
+}
@@ -0,0 +1,141 @@
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::path::PathBuf;
+use std::time::Duration;
+
+const DEFAULT_RAW_PORT: u16 = 9100;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PrinterTarget {
+    // Local device or spool file, e.g. /dev/usb/lp0 or \\.\COM3
+    Device(PathBuf),
+    // Raw TCP ("JetDirect") printer, e.g. tcp://192.168.1.50:9100
+    Network(String, u16),
+}
+
+impl PrinterTarget {
+    pub fn parse(value: &str) -> PrinterTarget {
+        let value = value.trim();
+        match value.strip_prefix("tcp://") {
+            Some(address) => match address.rsplit_once(':') {
+                Some((host, port)) => match port.parse() {
+                    Ok(port) => PrinterTarget::Network(host.to_string(), port),
+                    Err(_) => PrinterTarget::Network(address.to_string(), DEFAULT_RAW_PORT),
+                },
+                None => PrinterTarget::Network(address.to_string(), DEFAULT_RAW_PORT),
+            },
+            None => PrinterTarget::Device(PathBuf::from(value)),
+        }
+    }
+}
+
+pub fn send(target: &PrinterTarget, bytes: &[u8]) -> io::Result<()> {
+    match target {
+        PrinterTarget::Device(path) => {
+            let mut device = OpenOptions::new().append(true).create(true).open(path)?;
+            device.write_all(bytes)?;
+            device.flush()
+        }
+        PrinterTarget::Network(host, port) => {
+            let address = (host.as_str(), *port)
+                .to_socket_addrs()?
+                .next()
+                .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "printer address not found"))?;
+            let mut stream = TcpStream::connect_timeout(&address, Duration::from_secs(5))?;
+            stream.set_write_timeout(Some(Duration::from_secs(10)))?;
+            stream.write_all(bytes)?;
+            stream.flush()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+    use std::net::TcpListener;
+    use std::thread;
+
+    use super::*;
+    use crate::receipt::escpos::{self, CodePage};
+    use crate::receipt::{Block, PaperWidth, ReceiptCode, ReceiptOptions};
+
+    const ESC: u8 = 0x1B;
+    const GS: u8 = 0x1D;
+
+    fn options() -> ReceiptOptions {
+        ReceiptOptions {
+            paper: PaperWidth::Mm80,
+            code_page: CodePage::Wpc1256,
+            logo: None,
+            code: ReceiptCode::Qr,
+            store_name: String::new(),
+            store_address: String::new(),
+            store_tax_id: String::new(),
+            tax_rate: 0.0,
+            rtl: false,
+        }
+    }
+
+    // Send `bytes` to a raw TCP printer on localhost and return what it received
+    fn print_to_listener(bytes: &[u8]) -> Vec<u8> {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let printer = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut received = Vec::new();
+            stream.read_to_end(&mut received).unwrap();
+            received
+        });
+
+        let target = PrinterTarget::parse(&format!("tcp://127.0.0.1:{}", port));
+        assert_eq!(target, PrinterTarget::Network("127.0.0.1".to_string(), port));
+        send(&target, bytes).unwrap();
+        printer.join().unwrap()
+    }
+
+    fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+        haystack.windows(needle.len()).any(|window| window == needle)
+    }
+
+    #[test]
+    fn prints_receipt_over_tcp() {
+        let blocks = vec![
+            Block::Barcode("R-0001".to_string()),
+            Block::Qr("https://example.com/r/1".to_string()),
+            Block::Cut,
+        ];
+        let received = print_to_listener(&escpos::encode(&blocks, &options()));
+
+        // Initialise, then select code page 50 (WPC1256)
+        assert!(received.starts_with(&[ESC, b'@', ESC, b't', 50]));
+        // CODE128 code set B: length counts the two code set bytes
+        let mut code128 = vec![GS, b'k', 73, 8, b'{', b'B'];
+        code128.extend(b"R-0001");
+        assert!(contains(&received, &code128));
+        // QR: model 2, module size 6, error correction M, store data, print
+        assert!(contains(&received, &[GS, b'(', b'k', 4, 0, 49, 65, 50, 0]));
+        assert!(contains(&received, &[GS, b'(', b'k', 3, 0, 49, 67, 6]));
+        assert!(contains(&received, &[GS, b'(', b'k', 3, 0, 49, 69, 49]));
+        let mut store = vec![GS, b'(', b'k', 26, 0, 49, 80, 48];
+        store.extend(b"https://example.com/r/1");
+        assert!(contains(&received, &store));
+        assert!(contains(&received, &[GS, b'(', b'k', 3, 0, 49, 81, 48]));
+        // Partial cut after feeding 3 lines
+        assert!(received.ends_with(&[GS, b'V', 66, 3]));
+    }
+
+    #[test]
+    fn kicks_drawer_over_tcp() {
+        assert_eq!(print_to_listener(&escpos::drawer_kick()), vec![ESC, b'p', 0, 25, 250]);
+    }
+
+    #[test]
+    fn parses_targets() {
+        assert_eq!(
+            PrinterTarget::parse(" tcp://printer.local "),
+            PrinterTarget::Network("printer.local".to_string(), DEFAULT_RAW_PORT)
+        );
+        assert_eq!(PrinterTarget::parse("/dev/usb/lp0"), PrinterTarget::Device(PathBuf::from("/dev/usb/lp0")));
+    }
+}
@@ -0,0 +1,421 @@
+// Line based receipt templates.
+//
+// Each template line becomes one printed line. Placeholders are written as
+// `{{sale.total}}`; a line holding only `{{#items}}` ... `{{/items}}` repeats
+// the enclosed lines for every entry of a list (items, modifiers,
+// components, taxes, payments) or shows them once when a flag (has_discount, has_service_charge,
+// has_tip, has_gratuity, is_refund, or any non-empty value such as
+// store.tax_id) is set.
+//
+// A line may start with layout marks, in any order:
+//   ^ centered, > aligned to the end of the line, * bold, ! large
+// `left|right` splits a line into a label and a value pushed to the far end.
+// `---` prints a rule, and `[logo]`, `[code]` and `[cut]` print the logo,
+// the receipt barcode/QR code, and cut the paper.
+//
+// For right-to-left templates "start" and "end" are mirrored, so the same
+// layout marks work for Arabic.
+
+use rusqlite::Connection;
+
+use super::{money, Align, Block, Receipt, ReceiptCode, ReceiptOptions};
+use crate::error::Result;
+use crate::models::receipt_template::{get_receipt_template, get_receipt_templates, ReceiptTemplate};
+use crate::unit::Unit;
+
+enum Value {
+    Text(String),
+    List(Vec<Scope>),
+}
+
+type Scope = Vec<(String, Value)>;
+
+fn text(key: &str, value: impl Into<String>) -> (String, Value) {
+    (key.to_string(), Value::Text(value.into()))
+}
+
+fn flag(key: &str, set: bool) -> (String, Value) {
+    text(key, if set { "1" } else { "" })
+}
+
+fn list(key: &str, scopes: Vec<Scope>) -> (String, Value) {
+    (key.to_string(), Value::List(scopes))
+}
+
+fn lookup<'a>(scopes: &[&'a Scope], key: &str) -> Option<&'a Value> {
+    scopes
+        .iter()
+        .rev()
+        .find_map(|scope| scope.iter().find(|(k, _)| k == key).map(|(_, v)| v))
+}
+
+fn sale_scope(receipt: &Receipt, options: &ReceiptOptions) -> Scope {
+    let items = receipt
+        .items
+        .iter()
+        .map(|item| {
+            let modifiers = if item.extra_amount != 0.0 {
+                vec![vec![text("modifier.price", money(item.extra_amount))]]
+            } else {
+                Vec::new()
+            };
+            // Component quantities are per bundle, so scale them by the line
+            let components = item
+                .components
+                .iter()
+                .map(|c| {
+                    let unit = Unit::parse(&c.unit).unwrap_or(Unit::Each);
+                    vec![
+                        text("component.name", c.name.clone()),
+                        text("component.qty", unit.format(unit.round(c.quantity * item.quantity))),
+                    ]
+                })
+                .collect();
+            vec![
+                text("item.name", item.name.clone()),
+                text("item.qty", Unit::parse(&item.unit).unwrap_or(Unit::Each).format(item.quantity)),
+                text("item.price", money(item.price)),
+                text("item.total", money(item.total)),
+                list("modifiers", modifiers),
+                list("components", components),
+            ]
+        })
+        .collect();
+
+    let taxes = if receipt.tax != 0.0 {
+        vec![vec![
+            text("tax.rate", receipt.tax_rate.unwrap_or(options.tax_rate).to_string()),
+            text("tax.amount", money(receipt.tax)),
+        ]]
+    } else {
+        Vec::new()
+    };
+
+    let payments = receipt
+        .payments
+        .iter()
+        .map(|p| {
+            vec![
+                text("payment.method", p.method.clone()),
+                text("payment.amount", money(p.amount)),
+            ]
+        })
+        .collect();
+
+    vec![
+        text("store.name", options.store_name.clone()),
+        text("store.address", options.store_address.clone()),
+        text("store.tax_id", options.store_tax_id.clone()),
+        text("sale.number", receipt.number.clone()),
+        text("sale.date", receipt.timestamp.clone()),
+        text("sale.cashier", receipt.employee_name.clone()),
+        text("sale.subtotal", money(receipt.subtotal)),
+        text("sale.discount", money(receipt.discount)),
+        text("sale.tax", money(receipt.tax)),
+        text("sale.total", money(receipt.total)),
+        text("sale.service_charge", money(receipt.service_charge)),
+        text("sale.tip", money(receipt.tips)),
+        text("sale.amount_paid", money(receipt.total + receipt.service_charge + receipt.tips)),
+        text("sale.refund_of", receipt.refund_of.clone().unwrap_or_default()),
+        flag("has_discount", receipt.discount != 0.0),
+        flag("has_service_charge", receipt.service_charge != 0.0),
+        flag("has_tip", receipt.tips != 0.0),
+        flag("has_gratuity", receipt.service_charge != 0.0 || receipt.tips != 0.0),
+        flag("is_refund", receipt.refund_of.is_some()),
+        list("items", items),
+        list("taxes", taxes),
+        list("payments", payments),
+    ]
+}
+
+fn expand(line: &str, scopes: &[&Scope]) -> String {
+    let mut out = String::new();
+    let mut rest = line;
+    while let Some(start) = rest.find("{{") {
+        out.push_str(&rest[..start]);
+        match rest[start..].find("}}") {
+            Some(end) => {
+                let key = rest[start + 2..start + end].trim();
+                if let Some(Value::Text(value)) = lookup(scopes, key) {
+                    out.push_str(value);
+                }
+                rest = &rest[start + end + 2..];
+            }
+            None => {
+                out.push_str(&rest[start..]);
+                rest = "";
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn section_tag<'a>(line: &'a str, marker: &str) -> Option<&'a str> {
+    line.trim()
+        .strip_prefix("{{")?
+        .strip_suffix("}}")?
+        .strip_prefix(marker)
+        .map(str::trim)
+}
+
+// Index of the `{{/name}}` closing `lines[start - 1]`, allowing nesting
+fn section_end(lines: &[&str], start: usize, name: &str) -> usize {
+    let mut depth = 0;
+    for (i, line) in lines.iter().enumerate().skip(start) {
+        if section_tag(line, "#") == Some(name) {
+            depth += 1;
+        } else if section_tag(line, "/") == Some(name) {
+            if depth == 0 {
+                return i;
+            }
+            depth -= 1;
+        }
+    }
+    lines.len()
+}
+
+fn render_lines(lines: &[&str], scopes: &mut Vec<&Scope>, rtl: bool, code: &Block, out: &mut Vec<Block>) {
+    let mut i = 0;
+    while i < lines.len() {
+        if let Some(name) = section_tag(lines[i], "#") {
+            let end = section_end(lines, i + 1, name);
+            let inner = &lines[i + 1..end];
+            match lookup(scopes, name) {
+                Some(Value::List(entries)) => {
+                    for entry in entries {
+                        scopes.push(entry);
+                        render_lines(inner, scopes, rtl, code, out);
+                        scopes.pop();
+                    }
+                }
+                Some(Value::Text(value)) if !value.is_empty() => {
+                    render_lines(inner, scopes, rtl, code, out);
+                }
+                _ => {}
+            }
+            i = end + 1;
+            continue;
+        }
+
+        out.extend(line_blocks(lines[i], scopes, rtl, code));
+        i += 1;
+    }
+}
+
+fn line_blocks(line: &str, scopes: &[&Scope], rtl: bool, code: &Block) -> Vec<Block> {
+    match line.trim() {
+        "---" => return vec![Block::Rule],
+        "[logo]" => return vec![Block::Logo],
+        "[code]" => return vec![code.clone()],
+        "[cut]" => return vec![Block::Feed(3), Block::Cut],
+        _ => {}
+    }
+
+    let (start, end) = if rtl { (Align::Right, Align::Left) } else { (Align::Left, Align::Right) };
+    let mut align = start;
+    let mut bold = false;
+    let mut large = false;
+    let mut body = line;
+    loop {
+        match body.chars().next() {
+            Some('^') => align = Align::Center,
+            Some('>') => align = end.clone(),
+            Some('*') => bold = true,
+            Some('!') => large = true,
+            _ => break,
+        }
+        body = &body[1..];
+    }
+
+    match body.split_once('|') {
+        Some((first, second)) => {
+            let (first, second) = (expand(first, scopes), expand(second, scopes));
+            let (left, right) = if rtl { (second, first) } else { (first, second) };
+            vec![Block::Columns { left, right, bold }]
+        }
+        None => vec![Block::Text { text: expand(body, scopes), align, bold, large }],
+    }
+}
+
+pub fn render(template: &ReceiptTemplate, receipt: &Receipt, options: &ReceiptOptions) -> Vec<Block> {
+    let scope = sale_scope(receipt, options);
+    let code = match options.code {
+        ReceiptCode::Barcode => Block::Barcode(receipt.number.clone()),
+        ReceiptCode::Qr => Block::Qr(receipt.number.clone()),
+        ReceiptCode::None => Block::Feed(0),
+    };
+
+    let source = [template.header.as_str(), template.body.as_str(), template.footer.as_str()]
+        .iter()
+        .filter(|part| !part.is_empty())
+        .copied()
+        .collect::<Vec<_>>()
+        .join("\n");
+    let lines: Vec<&str> = source.lines().collect();
+
+    let mut blocks = Vec::new();
+    render_lines(&lines, &mut vec![&scope], template.rtl, &code, &mut blocks);
+    blocks
+}
+
+// Stored template for the kind and language, or the built-in default
+pub fn resolve_template(conn: &Connection, kind: &str, language: &str) -> Result<ReceiptTemplate> {
+    Ok(get_receipt_template(conn, kind, language)?.unwrap_or_else(|| default_template(kind, language)))
+}
+
+// Every stored template plus the defaults for the kinds and languages that
+// have not been customised yet, for the admin editor
+pub fn all_templates(conn: &Connection) -> Result<Vec<ReceiptTemplate>> {
+    let mut templates = get_receipt_templates(conn)?;
+    for kind in ["receipt", "kitchen"] {
+        for language in ["en", "ar"] {
+            if !templates.iter().any(|t| t.kind == kind && t.language == language) {
+                templates.push(default_template(kind, language));
+            }
+        }
+    }
+    templates.sort_by(|a, b| (&a.kind, &a.language).cmp(&(&b.kind, &b.language)));
+    Ok(templates)
+}
+
+pub fn default_template(kind: &str, language: &str) -> ReceiptTemplate {
+    let arabic = language == "ar";
+    let (header, body, footer) = match (kind, arabic) {
+        ("kitchen", false) => ("^!KITCHEN", KITCHEN_BODY_EN, "[cut]"),
+        ("kitchen", true) => ("^!المطبخ", KITCHEN_BODY_AR, "[cut]"),
+        (_, false) => (RECEIPT_HEADER_EN, RECEIPT_BODY_EN, "^Thank you for your visit!\n[code]\n[cut]"),
+        (_, true) => (RECEIPT_HEADER_AR, RECEIPT_BODY_AR, "^شكراً لزيارتكم!\n[code]\n[cut]"),
+    };
+
+    ReceiptTemplate {
+        kind: if kind == "kitchen" { "kitchen" } else { "receipt" }.to_string(),
+        language: language.to_string(),
+        rtl: arabic,
+        header: header.to_string(),
+        body: body.to_string(),
+        footer: footer.to_string(),
+    }
+}
+
+const RECEIPT_HEADER_EN: &str = "[logo]
+^!{{store.name}}
+{{#store.address}}
+^{{store.address}}
+{{/store.address}}
+{{#store.tax_id}}
+^Tax ID: {{store.tax_id}}
+{{/store.tax_id}}
+---";
+
+const RECEIPT_BODY_EN: &str = "{{#is_refund}}
+^*REFUND of #{{sale.refund_of}}
+{{/is_refund}}
+Receipt|#{{sale.number}}
+Date|{{sale.date}}
+Cashier|{{sale.cashier}}
+---
+{{#items}}
+{{item.name}}
+  {{item.qty}} x {{item.price}}|{{item.total}}
+{{#modifiers}}
+  + {{modifier.price}} each
+{{/modifiers}}
+{{/items}}
+---
+Subtotal|{{sale.subtotal}}
+{{#has_discount}}
+Discount|-{{sale.discount}}
+{{/has_discount}}
+*TOTAL|{{sale.total}}
+{{#taxes}}
+Tax {{tax.rate}}% included|{{tax.amount}}
+{{/taxes}}
+{{#has_service_charge}}
+Service charge|{{sale.service_charge}}
+{{/has_service_charge}}
+{{#has_tip}}
+Tip|{{sale.tip}}
+{{/has_tip}}
+{{#has_gratuity}}
+*AMOUNT PAID|{{sale.amount_paid}}
+{{/has_gratuity}}
+{{#payments}}
+{{payment.method}}|{{payment.amount}}
+{{/payments}}
+---";
+
+const RECEIPT_HEADER_AR: &str = "[logo]
+^!{{store.name}}
+{{#store.address}}
+^{{store.address}}
+{{/store.address}}
+{{#store.tax_id}}
+^الرقم الضريبي: {{store.tax_id}}
+{{/store.tax_id}}
+---";
+
+const RECEIPT_BODY_AR: &str = "{{#is_refund}}
+^*مرتجع للفاتورة #{{sale.refund_of}}
+{{/is_refund}}
+رقم الفاتورة|#{{sale.number}}
+التاريخ|{{sale.date}}
+الكاشير|{{sale.cashier}}
+---
+{{#items}}
+{{item.name}}
+  {{item.qty}} × {{item.price}}|{{item.total}}
+{{#modifiers}}
+  + إضافة {{modifier.price}}
+{{/modifiers}}
+{{/items}}
+---
+المجموع الفرعي|{{sale.subtotal}}
+{{#has_discount}}
+الخصم|-{{sale.discount}}
+{{/has_discount}}
+*الإجمالي|{{sale.total}}
+{{#taxes}}
+شامل الضريبة {{tax.rate}}%|{{tax.amount}}
+{{/taxes}}
+{{#has_service_charge}}
+رسوم الخدمة|{{sale.service_charge}}
+{{/has_service_charge}}
+{{#has_tip}}
+الإكرامية|{{sale.tip}}
+{{/has_tip}}
+{{#has_gratuity}}
+*المبلغ المدفوع|{{sale.amount_paid}}
+{{/has_gratuity}}
+{{#payments}}
+{{payment.method}}|{{payment.amount}}
+{{/payments}}
+---";
+
+const KITCHEN_BODY_EN: &str = "#{{sale.number}}|{{sale.date}}
+Server|{{sale.cashier}}
+---
+{{#items}}
+!{{item.qty}} x {{item.name}}
+{{#components}}
+   - {{component.qty}} x {{component.name}}
+{{/components}}
+{{#modifiers}}
+   + extra
+{{/modifiers}}
+{{/items}}
+---";
+
+const KITCHEN_BODY_AR: &str = "#{{sale.number}}|{{sale.date}}
+الموظف|{{sale.cashier}}
+---
+{{#items}}
+!{{item.qty}} × {{item.name}}
+{{#components}}
+   - {{component.qty}} × {{component.name}}
+{{/components}}
+{{#modifiers}}
+   + إضافة
+{{/modifiers}}
+{{/items}}
+---";
@@ -0,0 +1,277 @@
+// Retail scales for products sold by weight.
+//
+// Scales speak the NCR/Toledo 8217 protocol on a serial port: the POS sends
+// 'W' and the scale answers STX, the weight in kilograms and CR once it is
+// stable, or STX, '?', a status byte and CR while it is moving or faulted.
+// The port is opened as a file (/dev/ttyUSB0, \\.\COM3), so its speed and
+// framing (usually 9600 7E1) are set up in the operating system.
+
+use std::collections::VecDeque;
+use std::fs::OpenOptions;
+use std::io::{self, Read, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+use serde::Serialize;
+
+const STX: u8 = 0x02;
+const CR: u8 = 0x0d;
+const MAX_RESPONSE: usize = 32;
+const READ_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Reading {
+    pub kg: f64,
+    // False while the load is still moving; the weight is then not usable
+    pub stable: bool,
+}
+
+pub trait Scale {
+    fn read(&mut self) -> io::Result<Reading>;
+}
+
+// Keep reading until the weight settles, giving up after `attempts`
+// readings with the last (unstable) one. An error, such as a scale that does
+// not answer, ends the wait at once.
+pub fn read_stable(scale: &mut dyn Scale, attempts: u32, interval: Duration) -> io::Result<Reading> {
+    let mut reading = scale.read()?;
+    for _ in 1..attempts {
+        if reading.stable {
+            break;
+        }
+        thread::sleep(interval);
+        reading = scale.read()?;
+    }
+    Ok(reading)
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+// One 8217 answer, framing included
+pub fn parse_response(response: &[u8]) -> io::Result<Reading> {
+    let body = response.strip_prefix(&[STX]).unwrap_or(response);
+    let body = body.strip_suffix(&[CR]).unwrap_or(body);
+    match body {
+        // Status bit 0: in motion, bit 1: at zero; anything else is a fault
+        // such as over capacity
+        [b'?', status, ..] if status & 0x01 != 0 => Ok(Reading { kg: 0.0, stable: false }),
+        [b'?', status, ..] if status & 0x02 != 0 => Ok(Reading { kg: 0.0, stable: true }),
+        [b'?', ..] => Err(invalid("scale reported an error")),
+        _ => {
+            let text = std::str::from_utf8(body).map_err(|_| invalid("unreadable scale response"))?;
+            let number = text.trim().trim_end_matches(|c: char| c.is_ascii_alphabetic()).trim();
+            match number.parse::<f64>() {
+                Ok(kg) if kg.is_finite() && kg >= 0.0 => Ok(Reading { kg, stable: true }),
+                _ => Err(invalid("unreadable scale response")),
+            }
+        }
+    }
+}
+
+// The 8217 exchange over any byte stream
+pub struct Toledo<T: Read + Write> {
+    port: T,
+}
+
+impl<T: Read + Write> Toledo<T> {
+    pub fn new(port: T) -> Toledo<T> {
+        Toledo { port }
+    }
+}
+
+impl<T: Read + Write> Scale for Toledo<T> {
+    fn read(&mut self) -> io::Result<Reading> {
+        self.port.write_all(b"W")?;
+        self.port.flush()?;
+
+        let mut response = Vec::new();
+        let mut byte = [0u8; 1];
+        while response.len() < MAX_RESPONSE {
+            if self.port.read(&mut byte)? == 0 {
+                return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "scale closed the connection"));
+            }
+            response.push(byte[0]);
+            if byte[0] == CR {
+                return parse_response(&response);
+            }
+        }
+        Err(invalid("scale response too long"))
+    }
+}
+
+// A scale on a serial device. Device reads have no timeout of their own, so
+// each reading runs on a helper thread; a scale that never answers leaves
+// that thread waiting but the POS carries on. Until it returns, later reads
+// fail at once rather than leave another thread waiting.
+pub struct SerialScale {
+    path: PathBuf,
+}
+
+static READING: AtomicBool = AtomicBool::new(false);
+
+impl Scale for SerialScale {
+    fn read(&mut self) -> io::Result<Reading> {
+        if READING.swap(true, Ordering::SeqCst) {
+            return Err(io::Error::new(io::ErrorKind::TimedOut, "scale is still not answering"));
+        }
+        let path = self.path.clone();
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            let result = OpenOptions::new()
+                .read(true)
+                .write(true)
+                .open(path)
+                .and_then(|port| Toledo::new(port).read());
+            READING.store(false, Ordering::SeqCst);
+            let _ = tx.send(result);
+        });
+        rx.recv_timeout(READ_TIMEOUT)
+            .unwrap_or_else(|_| Err(io::Error::new(io::ErrorKind::TimedOut, "scale did not answer")))
+    }
+}
+
+// Canned readings for tests and for running the POS without a scale; the
+// last reading repeats once the others are used up
+pub struct MockScale {
+    readings: VecDeque<Reading>,
+}
+
+impl MockScale {
+    pub fn new(readings: Vec<Reading>) -> MockScale {
+        MockScale { readings: readings.into() }
+    }
+}
+
+impl Scale for MockScale {
+    fn read(&mut self) -> io::Result<Reading> {
+        if self.readings.len() > 1 {
+            return Ok(self.readings.pop_front().unwrap());
+        }
+        self.readings
+            .front()
+            .copied()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "mock scale has no readings"))
+    }
+}
+
+// The scale named by the `scale_port` setting: a serial device, or
+// "mock:<kg>" for a simulated scale always showing that weight
+pub fn open(port: &str) -> Box<dyn Scale> {
+    match port.trim().strip_prefix("mock:") {
+        Some(kg) => {
+            let readings = kg.trim().parse().map(|kg| vec![Reading { kg, stable: true }]).unwrap_or_default();
+            Box::new(MockScale::new(readings))
+        }
+        None => Box::new(SerialScale { path: PathBuf::from(port.trim()) }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    fn reading(kg: f64, stable: bool) -> Reading {
+        Reading { kg, stable }
+    }
+
+    #[test]
+    fn parses_stable_weight() {
+        assert_eq!(parse_response(b"\x020.345\r").unwrap(), reading(0.345, true));
+        assert_eq!(parse_response(b"\x02 1.200KG\r").unwrap(), reading(1.2, true));
+        assert_eq!(parse_response(b"2.5").unwrap(), reading(2.5, true));
+    }
+
+    #[test]
+    fn parses_status_responses() {
+        assert_eq!(parse_response(b"\x02?\x01\r").unwrap(), reading(0.0, false));
+        assert_eq!(parse_response(b"\x02?\x02\r").unwrap(), reading(0.0, true));
+        // Motion wins over zero
+        assert_eq!(parse_response(b"\x02?\x03\r").unwrap(), reading(0.0, false));
+        assert_eq!(parse_response(b"\x02?\x04\r").unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn rejects_unreadable_weight() {
+        assert!(parse_response(b"\x02abc\r").is_err());
+        assert!(parse_response(b"\x02-0.5\r").is_err());
+        assert!(parse_response(b"\x02\xff\r").is_err());
+    }
+
+    #[test]
+    fn read_stable_waits_for_settled_weight() {
+        let mut scale = MockScale::new(vec![reading(0.4, false), reading(0.52, false), reading(0.5, true)]);
+        assert_eq!(read_stable(&mut scale, 5, Duration::ZERO).unwrap(), reading(0.5, true));
+        // The last reading repeats
+        assert_eq!(scale.read().unwrap(), reading(0.5, true));
+    }
+
+    #[test]
+    fn read_stable_gives_up_with_last_reading() {
+        let mut scale = MockScale::new(vec![reading(0.4, false), reading(0.45, false), reading(0.5, true)]);
+        assert_eq!(read_stable(&mut scale, 2, Duration::ZERO).unwrap(), reading(0.45, false));
+        assert!(read_stable(&mut MockScale::new(Vec::new()), 3, Duration::ZERO).is_err());
+    }
+
+    struct Silent {
+        reads: u32,
+    }
+
+    impl Scale for Silent {
+        fn read(&mut self) -> io::Result<Reading> {
+            self.reads += 1;
+            Err(io::Error::new(io::ErrorKind::TimedOut, "scale did not answer"))
+        }
+    }
+
+    #[test]
+    fn read_stable_stops_at_timeout() {
+        let mut scale = Silent { reads: 0 };
+        assert_eq!(read_stable(&mut scale, 10, Duration::ZERO).unwrap_err().kind(), io::ErrorKind::TimedOut);
+        assert_eq!(scale.reads, 1);
+    }
+
+    // Serial port stand-in: answers from a canned response and records what
+    // was written
+    struct Port {
+        response: Cursor<Vec<u8>>,
+        written: Vec<u8>,
+    }
+
+    impl Read for Port {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.response.read(buf)
+        }
+    }
+
+    impl Write for Port {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.written.write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn toledo_requests_weight() {
+        let port = Port { response: Cursor::new(b"\x020.750\r".to_vec()), written: Vec::new() };
+        let mut scale = Toledo::new(port);
+        assert_eq!(scale.read().unwrap(), reading(0.75, true));
+        assert_eq!(scale.port.written, b"W");
+        assert_eq!(scale.read().unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn opens_mock_scale() {
+        assert_eq!(open("mock:1.25").read().unwrap(), reading(1.25, true));
+        assert!(open("mock:heavy").read().is_err());
+    }
+}
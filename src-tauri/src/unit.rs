@@ -0,0 +1,86 @@
+use serde::Serialize;
+
+// Units a product is sold in. The product price is per one unit, so a
+// product sold by the kilogram is priced per kilogram.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Unit {
+    Each,
+    Kg,
+    G,
+    L,
+}
+
+impl Unit {
+    pub fn parse(value: &str) -> Option<Unit> {
+        match value {
+            "each" => Some(Unit::Each),
+            "kg" => Some(Unit::Kg),
+            "g" => Some(Unit::G),
+            "l" => Some(Unit::L),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Unit::Each => "each",
+            Unit::Kg => "kg",
+            Unit::G => "g",
+            Unit::L => "l",
+        }
+    }
+
+    // Decimal places a quantity may have: whole items and grams, or down to
+    // the gram and millilitre for kilograms and litres
+    pub fn decimals(self) -> u32 {
+        match self {
+            Unit::Each | Unit::G => 0,
+            Unit::Kg | Unit::L => 3,
+        }
+    }
+
+    // Quantity of this unit in a scale reading, for units sold by weight
+    pub fn quantity_from_kg(self, kg: f64) -> Option<f64> {
+        match self {
+            Unit::Kg => Some(self.round(kg)),
+            Unit::G => Some(self.round(kg * 1000.0)),
+            Unit::Each | Unit::L => None,
+        }
+    }
+
+    pub fn round(self, quantity: f64) -> f64 {
+        let scale = 10f64.powi(self.decimals() as i32);
+        (quantity * scale).round() / scale
+    }
+
+    // "3" for items, "0.350 kg" or "250 g" otherwise
+    pub fn format(self, quantity: f64) -> String {
+        match self {
+            Unit::Each => format!("{}", quantity),
+            _ => format!("{:.*} {}", self.decimals() as usize, quantity, self.as_str()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quantity_from_kg_rounds_to_unit() {
+        assert_eq!(Unit::Kg.quantity_from_kg(0.3456), Some(0.346));
+        assert_eq!(Unit::Kg.quantity_from_kg(1.2344), Some(1.234));
+        assert_eq!(Unit::G.quantity_from_kg(0.3456), Some(346.0));
+        assert_eq!(Unit::G.quantity_from_kg(0.0004), Some(0.0));
+        assert_eq!(Unit::Each.quantity_from_kg(1.0), None);
+        assert_eq!(Unit::L.quantity_from_kg(1.0), None);
+    }
+
+    #[test]
+    fn formats_quantities() {
+        assert_eq!(Unit::Kg.format(0.35), "0.350 kg");
+        assert_eq!(Unit::G.format(250.0), "250 g");
+        assert_eq!(Unit::Each.format(3.0), "3");
+    }
+}
@@ -0,0 +1,168 @@
+use crate::barcode;
+use crate::error::{AppError, Result};
+use crate::unit::Unit;
+
+// Input rules shared by the models. Every check fails with a validation
+// error naming the field; text checks return the trimmed value to store.
+pub const MAX_NAME_LEN: usize = 100;
+pub const MAX_TEXT_LEN: usize = 1000;
+pub const MAX_PRICE: f64 = 1_000_000.0;
+pub const MAX_QUANTITY: f64 = 10_000.0;
+pub const MAX_GUESTS: i64 = 999;
+pub const MAX_LABEL_COPIES: u32 = 100;
+pub const MIN_PASSWORD_LEN: usize = 4;
+pub const MAX_PASSWORD_LEN: usize = 128;
+
+fn length_between<'a>(field: &str, value: &'a str, min: usize, max: usize) -> Result<&'a str> {
+    let value = value.trim();
+    let len = value.chars().count();
+    if len == 0 {
+        return Err(AppError::validation(field, "required", format!("{} is required", field)));
+    }
+    if len < min {
+        return Err(AppError::validation(field, "too_short", format!("{} must be at least {} characters", field, min)));
+    }
+    if len > max {
+        return Err(AppError::validation(field, "too_long", format!("{} must be at most {} characters", field, max)));
+    }
+    Ok(value)
+}
+
+fn number_between(field: &str, value: f64, min: f64, max: f64) -> Result<f64> {
+    if !value.is_finite() {
+        return Err(AppError::validation(field, "not_a_number", format!("{} must be a number", field)));
+    }
+    if value < min || value > max {
+        return Err(AppError::validation(
+            field,
+            "out_of_range",
+            format!("{} must be between {} and {}", field, min, max),
+        ));
+    }
+    Ok(value)
+}
+
+// Names of products, employees, customers, roles, rules...
+pub fn name<'a>(field: &str, value: &'a str) -> Result<&'a str> {
+    length_between(field, value, 1, MAX_NAME_LEN)
+}
+
+// Free text such as notes; may be empty
+pub fn text<'a>(field: &str, value: &'a str) -> Result<&'a str> {
+    if value.trim().is_empty() {
+        return Ok("");
+    }
+    length_between(field, value, 0, MAX_TEXT_LEN)
+}
+
+// Prices and other money amounts that may be zero
+pub fn price(field: &str, value: f64) -> Result<f64> {
+    number_between(field, value, 0.0, MAX_PRICE)
+}
+
+// Money amounts that must be more than zero (payments, tips, gift cards)
+pub fn amount(field: &str, value: f64) -> Result<f64> {
+    let value = number_between(field, value, 0.0, MAX_PRICE)?;
+    if value == 0.0 {
+        return Err(AppError::validation(field, "out_of_range", format!("{} must be more than 0", field)));
+    }
+    Ok(value)
+}
+
+pub fn percent(field: &str, value: f64) -> Result<f64> {
+    number_between(field, value, 0.0, 100.0)
+}
+
+// More than zero, and no finer than the unit allows (whole items, grams...)
+pub fn quantity(value: f64, unit: Unit) -> Result<f64> {
+    let value = number_between("quantity", value, 0.0, MAX_QUANTITY)?;
+    if value == 0.0 {
+        return Err(AppError::validation("quantity", "out_of_range", "quantity must be more than 0"));
+    }
+    if (value - unit.round(value)).abs() > 1e-9 {
+        return Err(AppError::validation(
+            "quantity",
+            "precision",
+            format!("quantity in {} may have at most {} decimals", unit.as_str(), unit.decimals()),
+        ));
+    }
+    Ok(value)
+}
+
+pub fn unit(value: &str) -> Result<Unit> {
+    Unit::parse(value)
+        .ok_or_else(|| AppError::validation("unit", "invalid_format", "unit must be each, kg, g or l"))
+}
+
+pub fn copies(value: u32) -> Result<u32> {
+    if !(1..=MAX_LABEL_COPIES).contains(&value) {
+        return Err(AppError::validation(
+            "copies",
+            "out_of_range",
+            format!("copies must be between 1 and {}", MAX_LABEL_COPIES),
+        ));
+    }
+    Ok(value)
+}
+
+pub fn guests(value: Option<i64>) -> Result<Option<i64>> {
+    if value.is_some_and(|guests| !(1..=MAX_GUESTS).contains(&guests)) {
+        return Err(AppError::validation(
+            "guests",
+            "out_of_range",
+            format!("guests must be between 1 and {}", MAX_GUESTS),
+        ));
+    }
+    Ok(value)
+}
+
+// EAN-8, UPC-A and EAN-13 codes need a valid check digit; other codes are
+// Code128 and may be any printable characters without spaces
+pub fn barcode(value: &str) -> Result<&str> {
+    let value = length_between("barcode", value, 1, barcode::MAX_CODE128_LEN)?;
+    if barcode::detect(value).is_none() {
+        if value.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(AppError::validation("barcode", "check_digit", "barcode check digit is wrong"));
+        }
+        return Err(AppError::validation("barcode", "invalid_format", "barcode may not contain spaces or non-ASCII characters"));
+    }
+    Ok(value)
+}
+
+// Passwords are stored as given, but may not be blank or padded with spaces
+pub fn password<'a>(field: &str, value: &'a str) -> Result<&'a str> {
+    if value.trim() != value {
+        return Err(AppError::validation(field, "invalid_format", format!("{} may not start or end with spaces", field)));
+    }
+    length_between(field, value, MIN_PASSWORD_LEN, MAX_PASSWORD_LEN)
+}
+
+pub fn pin(value: &str) -> Result<&str> {
+    if !(4..=8).contains(&value.len()) || !value.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(AppError::validation("pin", "invalid_format", "pin must be 4 to 8 digits"));
+    }
+    Ok(value)
+}
+
+// Optional contact fields: blank means none
+pub fn phone(value: Option<&str>) -> Result<Option<&str>> {
+    let Some(value) = value.map(str::trim).filter(|v| !v.is_empty()) else { return Ok(None) };
+    let digits = value.bytes().filter(u8::is_ascii_digit).count();
+    if !(6..=15).contains(&digits) || !value.bytes().all(|b| b.is_ascii_digit() || b" +-()".contains(&b)) {
+        return Err(AppError::validation("phone", "invalid_format", "phone must be 6 to 15 digits"));
+    }
+    Ok(Some(value))
+}
+
+pub fn email(value: Option<&str>) -> Result<Option<&str>> {
+    let Some(value) = value.map(str::trim).filter(|v| !v.is_empty()) else { return Ok(None) };
+    let valid = value.len() <= 254
+        && !value.contains(char::is_whitespace)
+        && value
+            .split_once('@')
+            .is_some_and(|(local, domain)| !local.is_empty() && domain.contains('.') && !domain.contains('@'));
+    if !valid {
+        return Err(AppError::validation("email", "invalid_format", "email is not a valid address"));
+    }
+    Ok(Some(value))
+}
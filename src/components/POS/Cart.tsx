@@ -1,11 +1,13 @@
 import { Input } from "@/components/ui/input";
 import { Button } from "@/components/ui/button";
 import { Trash2 } from "lucide-react";
+import { Unit, UNIT_LABELS, formatQuantity } from "@/lib/units";
 
 interface Product {
     id: number;
     name: string;
     price: number;
+    unit: Unit;
 }
 
 interface CartItem {
@@ -16,9 +18,9 @@ interface CartItem {
 
 interface CartProps {
     items: CartItem[];
-    onUpdateQuantity: (productId: number, quantity: number) => void;
-    onRemove: (productId: number) => void;
-    onUpdateExtra: (productId: number, extra: number) => void;
+    onUpdateQuantity: (index: number, quantity: number) => void;
+    onRemove: (index: number) => void;
+    onUpdateExtra: (index: number, extra: number) => void;
     total: number;
     onCheckout: () => void;
     loading: boolean
@@ -40,9 +42,9 @@ export default function Cart({
                 {items.length === 0 && (
                     <p className="text-gray-500 text-center">لا توجد منتجات في السلة.</p>
                 )}
-                {items.map((item) => (
+                {items.map((item, index) => (
                     <div
-                        key={item.product.id}
+                        key={index}
                         className="flex flex-col gap-2 mb-4 border-b border-gray-200 pb-4"
                     >
                         {/* Product Info */}
@@ -51,6 +53,7 @@ export default function Cart({
                                 <p className="font-semibold text-gray-900">{item.product.name}</p>
                                 <p className="text-sm text-gray-500">
                                     السعر: {item.product.price} ج.م
+                                    {item.product.unit !== "each" && ` / ${UNIT_LABELS[item.product.unit]}`}
                                 </p>
                                 {item.extraAmount && item.extraAmount > 0 && (
                                     <p className="text-sm text-green-600">
@@ -61,29 +64,37 @@ export default function Cart({
 
                             {/* Quantity & Remove */}
                             <div className="flex items-center gap-2">
-                                <Button
-                                    variant="outline"
-                                    size="sm"
-                                    onClick={() =>
-                                        onUpdateQuantity(item.product.id, item.quantity - 1)
-                                    }
-                                >
-                                    -
-                                </Button>
-                                <span className="font-medium">{item.quantity}</span>
-                                <Button
-                                    variant="outline"
-                                    size="sm"
-                                    onClick={() =>
-                                        onUpdateQuantity(item.product.id, item.quantity + 1)
-                                    }
-                                >
-                                    +
-                                </Button>
+                                {item.product.unit === "each" ? (
+                                    <>
+                                        <Button
+                                            variant="outline"
+                                            size="sm"
+                                            onClick={() =>
+                                                onUpdateQuantity(index, item.quantity - 1)
+                                            }
+                                        >
+                                            -
+                                        </Button>
+                                        <span className="font-medium">{item.quantity}</span>
+                                        <Button
+                                            variant="outline"
+                                            size="sm"
+                                            onClick={() =>
+                                                onUpdateQuantity(index, item.quantity + 1)
+                                            }
+                                        >
+                                            +
+                                        </Button>
+                                    </>
+                                ) : (
+                                    <span className="font-medium" dir="ltr">
+                                        {formatQuantity(item.quantity, item.product.unit)}
+                                    </span>
+                                )}
                                 <Button
                                     variant="destructive"
                                     size="icon"
-                                    onClick={() => onRemove(item.product.id)}
+                                    onClick={() => onRemove(index)}
                                 >
                                     <Trash2 size={16} />
                                 </Button>
@@ -100,7 +111,7 @@ export default function Cart({
                                 value={item.extraAmount || ""}
                                 onChange={(e) =>
                                     onUpdateExtra(
-                                        item.product.id,
+                                        index,
                                         parseFloat(e.target.value) || 0
                                     )
                                 }
@@ -0,0 +1,92 @@
+import { useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { Lock } from "lucide-react";
+import toast from "react-hot-toast";
+
+interface Employee {
+    id: number;
+    name: string;
+}
+
+interface PinLogin {
+    employee: Employee | null;
+    locked_for: number;
+    session: string | null;
+}
+
+interface LockScreenProps {
+    title: string;
+    onUnlock?: (employee: Employee, session: string) => void;
+    // Handle the PIN some other way (e.g. a manager override) instead of logging in
+    onPin?: (pin: string) => Promise<void>;
+    onCancel?: () => void;
+}
+
+const KEYS = ["1", "2", "3", "4", "5", "6", "7", "8", "9", "C", "0", "OK"];
+
+export default function LockScreen({ title, onUnlock, onPin, onCancel }: LockScreenProps) {
+    const [pin, setPin] = useState("");
+    const [busy, setBusy] = useState(false);
+
+    const submit = async () => {
+        if (!pin || busy) return;
+        setBusy(true);
+        try {
+            if (onPin) {
+                await onPin(pin);
+                return;
+            }
+            const result = await invoke<PinLogin>("pin_login_cmd", { pin });
+            if (result.employee && result.session) {
+                onUnlock?.(result.employee, result.session);
+            } else if (result.locked_for > 0) {
+                toast.error(`محاولات كثيرة، حاول بعد ${result.locked_for} ثانية`);
+            } else {
+                toast.error("الرمز غير صحيح");
+            }
+        } catch (e) {
+            console.error("PIN login failed:", e);
+            toast.error("حدث خطأ أثناء تسجيل الدخول");
+        } finally {
+            setPin("");
+            setBusy(false);
+        }
+    };
+
+    const press = (key: string) => {
+        if (key === "C") setPin("");
+        else if (key === "OK") submit();
+        else if (pin.length < 8) setPin(pin + key);
+    };
+
+    return (
+        <div dir="rtl" className="fixed inset-0 z-50 flex items-center justify-center bg-gray-900/80">
+            <div className="w-72 bg-white rounded-2xl shadow-lg p-6 flex flex-col items-center gap-4">
+                <Lock className="h-8 w-8 text-red-600" />
+                <p className="text-lg font-bold text-gray-800">{title}</p>
+                <p dir="ltr" className="text-3xl tracking-widest h-9">
+                    {"•".repeat(pin.length)}
+                </p>
+                <div className="grid grid-cols-3 gap-2 w-full">
+                    {KEYS.map((key) => (
+                        <button
+                            key={key}
+                            onClick={() => press(key)}
+                            disabled={busy}
+                            className={`py-3 rounded-lg text-xl font-semibold ${
+                                key === "OK" ? "bg-red-600 text-white" : "bg-gray-100 hover:bg-gray-200"
+                            }`}
+                        >
+                            {key === "C" ? "مسح" : key === "OK" ? "دخول" : key}
+                        </button>
+                    ))}
+                </div>
+                {onCancel && (
+                    <button onClick={onCancel} className="text-sm text-gray-500">
+                        رجوع
+                    </button>
+                )}
+            </div>
+        </div>
+    );
+}
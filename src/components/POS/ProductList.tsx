@@ -1,20 +1,26 @@
 import { Card, CardContent, CardHeader, CardTitle } from "@/components/ui/card";
+import { Unit, UNIT_LABELS } from "@/lib/units";
 
 interface Product {
     id: number;
     name: string;
     price: number;
     barcode: string;
+    unit: Unit;
+    parent_id: number | null;
+    sold_out: boolean;
+    available: boolean;
 }
 
 interface ProductListProps {
     products: Product[];
     onAddToCart: (product: Product) => void;
+    onToggleSoldOut: (product: Product) => void;
     loading: boolean;
     error: string | null;
 }
 
-export default function ProductList({ products, onAddToCart, loading, error }: ProductListProps) {
+export default function ProductList({ products, onAddToCart, onToggleSoldOut, loading, error }: ProductListProps) {
     if (loading) return <p className="text-center text-red-600 font-medium">جارٍ تحميل المنتجات...</p>;
     if (error) return <p className="text-red-500 text-center">{error}</p>;
     if (products.length === 0) return <p className="text-gray-500 text-center">لم يتم العثور على منتجات.</p>;
@@ -24,8 +30,12 @@ export default function ProductList({ products, onAddToCart, loading, error }: P
             {products.map((p) => (
                 <Card
                     key={p.id}
-                    className="bg-white border border-gray-200 shadow-md rounded-xl hover:shadow-xl hover:bg-red-50 transition-all cursor-pointer group"
-                    onClick={() => onAddToCart(p)}
+                    className={
+                        p.available
+                            ? "bg-white border border-gray-200 shadow-md rounded-xl hover:shadow-xl hover:bg-red-50 transition-all cursor-pointer group"
+                            : "bg-gray-100 border border-gray-200 rounded-xl opacity-60 cursor-not-allowed"
+                    }
+                    onClick={() => p.available && onAddToCart(p)}
                 >
                     <CardHeader className="p-4">
                         <CardTitle className="text-lg font-semibold text-gray-900 truncate">
@@ -33,8 +43,24 @@ export default function ProductList({ products, onAddToCart, loading, error }: P
                         </CardTitle>
                     </CardHeader>
                     <CardContent className="p-4 pt-0 flex flex-col gap-2">
-                        <p className="text-red-600 font-bold text-xl">{p.price} ج.م</p>
+                        <p className="text-red-600 font-bold text-xl">
+                            {p.price} ج.م{p.unit !== "each" && ` / ${UNIT_LABELS[p.unit]}`}
+                        </p>
                         <p className="text-sm text-gray-500">الباركود: {p.barcode}</p>
+                        <div className="flex justify-between items-center text-sm">
+                            <span className="text-gray-600">
+                                {p.sold_out ? "نفد اليوم" : !p.available && "غير متاح الآن"}
+                            </span>
+                            <button
+                                onClick={(e) => {
+                                    e.stopPropagation();
+                                    onToggleSoldOut(p);
+                                }}
+                                className="px-2 py-0.5 rounded border text-xs"
+                            >
+                                {p.sold_out ? "إعادة للبيع" : "86"}
+                            </button>
+                        </div>
                     </CardContent>
                 </Card>
             ))}
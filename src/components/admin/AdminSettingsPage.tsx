@@ -1,11 +1,27 @@
-import { useState } from "react";
+import { useEffect, useState } from "react";
 import { invoke } from "@tauri-apps/api/core";
+import { useAuth } from "@/context/AuthContext";
+import { errorMessage } from "@/lib/errors";
 import { Card, CardHeader, CardTitle, CardContent } from "@/components/ui/card";
 import { Input } from "@/components/ui/input";
 import { Button } from "@/components/ui/button";
 import toast from "react-hot-toast";
 
+type Lockout = { key: string; failures: number; locked_until: string | null; locked_for: number };
+
+type LoginFailure = {
+    id: number;
+    kind: "admin" | "employee" | "pin";
+    account: string | null;
+    terminal: string;
+    locked: boolean;
+    created_at: string;
+};
+
+const FAILURE_KINDS = { admin: "مدير", employee: "موظف", pin: "رمز سري" };
+
 export default function AdminSettingsPage() {
+    const { session } = useAuth();
     const [oldPassword, setOldPassword] = useState("");
     const [isVerified, setIsVerified] = useState(false);
     const [newName, setNewName] = useState("");
@@ -14,6 +30,32 @@ export default function AdminSettingsPage() {
     const [loading, setLoading] = useState(false);
     const [message, setMessage] = useState("");
     const [messageType, setMessageType] = useState<"success" | "error" | "">("");
+    const [lockouts, setLockouts] = useState<Lockout[]>([]);
+    const [failures, setFailures] = useState<LoginFailure[]>([]);
+
+    useEffect(() => {
+        fetchLockouts();
+    }, []);
+
+    const fetchLockouts = async () => {
+        try {
+            setLockouts(await invoke<Lockout[]>("get_lockouts_cmd"));
+            setFailures(await invoke<LoginFailure[]>("get_login_failures_cmd", { limit: 50 }));
+        } catch (e) {
+            console.error("فشل في جلب محاولات الدخول:", e);
+        }
+    };
+
+    const unlock = async (key: string) => {
+        try {
+            await invoke("unlock_login_cmd", { key, session });
+            await fetchLockouts();
+            toast.success("تم فك القفل");
+        } catch (e) {
+            console.error("فشل في فك القفل:", e);
+            toast.error(errorMessage(e, "فشل في فك القفل"));
+        }
+    };
 
     const handleVerifyOldPassword = async () => {
         if (!oldPassword) {
@@ -23,11 +65,16 @@ export default function AdminSettingsPage() {
         }
         setLoading(true);
         try {
-            const isValid = await invoke<boolean>("verify_old_password_cmd", { oldPassword });
-            if (isValid) {
+            const result = await invoke<{ success: boolean; locked_for: number }>("verify_old_password_cmd", {
+                oldPassword,
+            });
+            if (result.success) {
                 setIsVerified(true);
                 setMessage("تم التحقق من كلمة المرور. يمكنك الآن تحديث البيانات.");
                 setMessageType("success");
+            } else if (result.locked_for > 0) {
+                setMessage(`محاولات كثيرة، حاول بعد ${result.locked_for} ثانية`);
+                setMessageType("error");
             } else {
                 setMessage("كلمة المرور غير صحيحة");
                 setMessageType("error");
@@ -53,16 +100,19 @@ export default function AdminSettingsPage() {
         }
         setLoading(true);
         try {
-            const success = await invoke<boolean>("update_admin_cmd", {
+            const result = await invoke<{ success: boolean; locked_for: number }>("update_admin_cmd", {
                 oldPassword,
                 newName,
                 newPassword,
             });
-            if (success) {
+            if (result.success) {
                 setMessage("تم تحديث بيانات المسؤول بنجاح");
                 setMessageType("success");
                 toast.success("تم التحديث بنجاح");
                 resetForm();
+            } else if (result.locked_for > 0) {
+                setMessage(`محاولات كثيرة، حاول بعد ${result.locked_for} ثانية`);
+                setMessageType("error");
             } else {
                 setMessage("فشل في تحديث بيانات المسؤول. قد تكون كلمة المرور القديمة غير صحيحة.");
                 setMessageType("error");
@@ -165,6 +215,37 @@ export default function AdminSettingsPage() {
                     )}
                 </CardContent>
             </Card>
+
+            <Card className="shadow-lg border border-red-200 mt-6">
+                <CardHeader>
+                    <CardTitle className="text-2xl font-bold text-red-700">محاولات الدخول الفاشلة</CardTitle>
+                </CardHeader>
+                <CardContent className="space-y-4">
+                    {lockouts.length === 0 && <p className="text-sm text-gray-500">لا توجد حسابات أو أجهزة مقفلة.</p>}
+                    {lockouts.map((l) => (
+                        <div key={l.key} className="flex items-center justify-between bg-red-50 rounded-lg px-3 py-2">
+                            <span dir="ltr" className="text-sm">
+                                {l.key} — {l.failures}
+                                {l.locked_for > 0 && ` (${l.locked_for}s)`}
+                            </span>
+                            <Button size="sm" variant="outline" onClick={() => unlock(l.key)}>
+                                فك القفل
+                            </Button>
+                        </div>
+                    ))}
+                    <div className="max-h-64 overflow-y-auto text-sm">
+                        {failures.map((f) => (
+                            <div key={f.id} className="flex justify-between border-b py-1">
+                                <span>
+                                    {FAILURE_KINDS[f.kind]} {f.account ?? ""} — {f.terminal}
+                                    {f.locked && " (مرفوض بسبب القفل)"}
+                                </span>
+                                <span dir="ltr" className="text-gray-500">{f.created_at}</span>
+                            </div>
+                        ))}
+                    </div>
+                </CardContent>
+            </Card>
         </div>
     );
 }
@@ -0,0 +1,384 @@
+import { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { useAuth } from "@/context/AuthContext";
+import { errorMessage } from "@/lib/errors";
+import { Button } from "@/components/ui/button";
+import { Input } from "@/components/ui/input";
+import {
+    Table,
+    TableBody,
+    TableCell,
+    TableHead,
+    TableHeader,
+    TableRow
+} from "@/components/ui/table";
+import { Card, CardContent, CardTitle } from "@/components/ui/card";
+import { Trash2, History, PlusCircle, Search } from "lucide-react";
+import toast from "react-hot-toast";
+
+type Customer = {
+    id: number;
+    name: string;
+    phone: string | null;
+    email: string | null;
+    notes: string;
+    points: number;
+    created_at: string;
+};
+
+type CustomerSale = {
+    sale_id: number;
+    receipt_number: string | null;
+    total: number;
+    is_refund: boolean;
+    voided: boolean;
+    timestamp: string;
+};
+
+type LoyaltyEntry = {
+    id: number;
+    sale_id: number | null;
+    points: number;
+    reason: "earn" | "redeem" | "refund" | "adjust";
+    note: string;
+    created_at: string;
+};
+
+type TopCustomer = {
+    id: number;
+    name: string;
+    phone: string | null;
+    visits: number;
+    total_spent: number;
+    points: number;
+};
+
+const REASONS: Record<LoyaltyEntry["reason"], string> = {
+    earn: "اكتساب",
+    redeem: "استبدال",
+    refund: "استرجاع",
+    adjust: "تعديل",
+};
+
+export default function CustomersPage() {
+    const { session } = useAuth();
+    const [customers, setCustomers] = useState<Customer[]>([]);
+    const [query, setQuery] = useState("");
+    const [form, setForm] = useState({ name: "", phone: "", email: "", notes: "" });
+    const [selected, setSelected] = useState<Customer | null>(null);
+    const [history, setHistory] = useState<CustomerSale[]>([]);
+    const [ledger, setLedger] = useState<LoyaltyEntry[]>([]);
+    const [adjustment, setAdjustment] = useState("");
+    const [adjustNote, setAdjustNote] = useState("");
+    const [topCustomers, setTopCustomers] = useState<TopCustomer[]>([]);
+
+    useEffect(() => {
+        fetchCustomers("");
+        fetchTopCustomers();
+    }, []);
+
+    const fetchCustomers = async (q: string) => {
+        try {
+            const result = await invoke<Customer[]>("search_customers_cmd", { query: q });
+            setCustomers(result);
+        } catch (e) {
+            console.error("فشل في جلب العملاء:", e);
+            toast.error("فشل في جلب العملاء");
+        }
+    };
+
+    const fetchTopCustomers = async () => {
+        try {
+            const result = await invoke<TopCustomer[]>("get_top_customers_cmd", { limit: 10, session });
+            setTopCustomers(result);
+        } catch (e) {
+            console.error("فشل في جلب أفضل العملاء:", e);
+        }
+    };
+
+    const openCustomer = async (customer: Customer) => {
+        try {
+            setSelected(customer);
+            setHistory(await invoke<CustomerSale[]>("get_customer_sales_cmd", { customerId: customer.id }));
+            setLedger(await invoke<LoyaltyEntry[]>("get_loyalty_ledger_cmd", { customerId: customer.id }));
+        } catch (e) {
+            console.error("فشل في جلب سجل العميل:", e);
+            toast.error("فشل في جلب سجل العميل");
+        }
+    };
+
+    const addCustomer = async () => {
+        if (!form.name.trim()) {
+            toast.error("يرجى إدخال اسم العميل");
+            return;
+        }
+        try {
+            await invoke("add_customer_cmd", {
+                name: form.name.trim(),
+                phone: form.phone || null,
+                email: form.email || null,
+                notes: form.notes,
+            });
+            setForm({ name: "", phone: "", email: "", notes: "" });
+            await fetchCustomers(query);
+            toast.success("تمت إضافة العميل بنجاح");
+        } catch (e) {
+            console.error("فشل في إضافة العميل:", e);
+            toast.error(errorMessage(e, "فشل في إضافة العميل"));
+        }
+    };
+
+    const removeCustomer = async (id: number) => {
+        try {
+            await invoke("delete_customer_cmd", { id });
+            if (selected?.id === id) setSelected(null);
+            await fetchCustomers(query);
+            await fetchTopCustomers();
+            toast.success("تم حذف العميل");
+        } catch (e) {
+            console.error("فشل في حذف العميل:", e);
+            toast.error("فشل في حذف العميل");
+        }
+    };
+
+    const adjustPoints = async () => {
+        if (!selected || !adjustment) return;
+        try {
+            await invoke("adjust_points_cmd", {
+                customerId: selected.id,
+                points: Math.trunc(Number(adjustment)),
+                note: adjustNote,
+            });
+            setAdjustment("");
+            setAdjustNote("");
+            const updated = await invoke<Customer | null>("get_customer_cmd", { id: selected.id });
+            if (updated) await openCustomer(updated);
+            await fetchCustomers(query);
+            toast.success("تم تعديل النقاط");
+        } catch (e) {
+            console.error("فشل في تعديل النقاط:", e);
+            toast.error("فشل في تعديل النقاط");
+        }
+    };
+
+    return (
+        <div className="p-6 max-w-5xl mx-auto space-y-6">
+            <Card className="shadow-xl border border-red-200 rounded-2xl">
+                <CardContent className="p-6 space-y-6">
+                    <CardTitle className="text-3xl font-bold text-red-700 mb-4">
+                        العملاء
+                    </CardTitle>
+
+                    {/* إضافة عميل */}
+                    <div className="grid grid-cols-1 sm:grid-cols-5 gap-3">
+                        <Input
+                            placeholder="الاسم"
+                            value={form.name}
+                            onChange={(e) => setForm({ ...form, name: e.target.value })}
+                            className="border-red-300 focus:ring-2 focus:ring-red-500"
+                        />
+                        <Input
+                            placeholder="الهاتف"
+                            value={form.phone}
+                            onChange={(e) => setForm({ ...form, phone: e.target.value })}
+                            className="border-red-300 focus:ring-2 focus:ring-red-500"
+                        />
+                        <Input
+                            placeholder="البريد الإلكتروني"
+                            value={form.email}
+                            onChange={(e) => setForm({ ...form, email: e.target.value })}
+                            className="border-red-300 focus:ring-2 focus:ring-red-500"
+                        />
+                        <Input
+                            placeholder="ملاحظات"
+                            value={form.notes}
+                            onChange={(e) => setForm({ ...form, notes: e.target.value })}
+                            className="border-red-300 focus:ring-2 focus:ring-red-500"
+                        />
+                        <Button
+                            onClick={addCustomer}
+                            className="bg-red-600 hover:bg-red-700 text-white flex items-center gap-2 px-4 py-2 rounded-lg font-medium"
+                        >
+                            <PlusCircle size={18} /> إضافة
+                        </Button>
+                    </div>
+
+                    {/* بحث */}
+                    <div className="flex gap-3">
+                        <Input
+                            placeholder="ابحث بالاسم أو الهاتف أو البريد"
+                            value={query}
+                            onChange={(e) => setQuery(e.target.value)}
+                            onKeyDown={(e) => e.key === "Enter" && fetchCustomers(query)}
+                            className="flex-1 border-red-300 focus:ring-2 focus:ring-red-500"
+                        />
+                        <Button variant="outline" onClick={() => fetchCustomers(query)}>
+                            <Search size={18} />
+                        </Button>
+                    </div>
+
+                    <div className="overflow-x-auto">
+                        <Table className="border border-red-100 rounded-lg">
+                            <TableHeader>
+                                <TableRow className="bg-red-50 hover:bg-red-50">
+                                    <TableHead className="text-gray-700 font-semibold">الاسم</TableHead>
+                                    <TableHead className="text-gray-700 font-semibold">الهاتف</TableHead>
+                                    <TableHead className="text-gray-700 font-semibold">البريد</TableHead>
+                                    <TableHead className="text-gray-700 font-semibold">النقاط</TableHead>
+                                    <TableHead className="text-right pr-10 text-gray-700 font-semibold">
+                                        الإجراءات
+                                    </TableHead>
+                                </TableRow>
+                            </TableHeader>
+                            <TableBody>
+                                {customers.map((c) => (
+                                    <TableRow key={c.id} className="hover:bg-gray-50">
+                                        <TableCell className="font-medium text-gray-800">{c.name}</TableCell>
+                                        <TableCell dir="ltr">{c.phone ?? "-"}</TableCell>
+                                        <TableCell dir="ltr">{c.email ?? "-"}</TableCell>
+                                        <TableCell>{c.points}</TableCell>
+                                        <TableCell className="text-right">
+                                            <div className="flex justify-end gap-2">
+                                                <Button
+                                                    variant="secondary"
+                                                    className="bg-blue-600 hover:bg-blue-700 text-white px-3 rounded"
+                                                    onClick={() => openCustomer(c)}
+                                                >
+                                                    <History className="w-4 h-4" />
+                                                </Button>
+                                                <Button
+                                                    variant="destructive"
+                                                    onClick={() => removeCustomer(c.id)}
+                                                    className="px-3 rounded"
+                                                >
+                                                    <Trash2 className="w-4 h-4" />
+                                                </Button>
+                                            </div>
+                                        </TableCell>
+                                    </TableRow>
+                                ))}
+
+                                {customers.length === 0 && (
+                                    <TableRow>
+                                        <TableCell colSpan={5} className="text-center text-gray-500 p-4">
+                                            لا يوجد عملاء.
+                                        </TableCell>
+                                    </TableRow>
+                                )}
+                            </TableBody>
+                        </Table>
+                    </div>
+                </CardContent>
+            </Card>
+
+            {selected && (
+                <Card className="shadow-xl border border-red-200 rounded-2xl">
+                    <CardContent className="p-6 space-y-4">
+                        <CardTitle className="text-2xl font-bold text-red-700">
+                            {selected.name} — {ledger.reduce((sum, e) => sum + e.points, 0)} نقطة
+                        </CardTitle>
+                        {selected.notes && <p className="text-sm text-gray-600">{selected.notes}</p>}
+
+                        <div className="flex flex-col sm:flex-row gap-3">
+                            <Input
+                                type="number"
+                                placeholder="النقاط (+/-)"
+                                value={adjustment}
+                                onChange={(e) => setAdjustment(e.target.value)}
+                                className="sm:w-40 border-red-300 focus:ring-2 focus:ring-red-500"
+                            />
+                            <Input
+                                placeholder="السبب"
+                                value={adjustNote}
+                                onChange={(e) => setAdjustNote(e.target.value)}
+                                className="flex-1 border-red-300 focus:ring-2 focus:ring-red-500"
+                            />
+                            <Button onClick={adjustPoints} className="bg-red-600 hover:bg-red-700 text-white">
+                                تعديل النقاط
+                            </Button>
+                        </div>
+
+                        <div className="grid grid-cols-1 lg:grid-cols-2 gap-4">
+                            <Table className="border border-red-100 rounded-lg">
+                                <TableHeader>
+                                    <TableRow className="bg-red-50 hover:bg-red-50">
+                                        <TableHead>رقم الإيصال</TableHead>
+                                        <TableHead>الإجمالي</TableHead>
+                                        <TableHead>التاريخ</TableHead>
+                                    </TableRow>
+                                </TableHeader>
+                                <TableBody>
+                                    {history.map((s) => (
+                                        <TableRow key={s.sale_id} className={s.voided ? "line-through text-gray-400" : ""}>
+                                            <TableCell dir="ltr">
+                                                {s.receipt_number}
+                                                {s.is_refund && " (مرتجع)"}
+                                            </TableCell>
+                                            <TableCell>{s.total.toFixed(2)}</TableCell>
+                                            <TableCell dir="ltr">{s.timestamp}</TableCell>
+                                        </TableRow>
+                                    ))}
+                                    {history.length === 0 && (
+                                        <TableRow>
+                                            <TableCell colSpan={3} className="text-center text-gray-500 p-4">
+                                                لا توجد مشتريات.
+                                            </TableCell>
+                                        </TableRow>
+                                    )}
+                                </TableBody>
+                            </Table>
+
+                            <Table className="border border-red-100 rounded-lg">
+                                <TableHeader>
+                                    <TableRow className="bg-red-50 hover:bg-red-50">
+                                        <TableHead>الحركة</TableHead>
+                                        <TableHead>النقاط</TableHead>
+                                        <TableHead>التاريخ</TableHead>
+                                    </TableRow>
+                                </TableHeader>
+                                <TableBody>
+                                    {ledger.map((e) => (
+                                        <TableRow key={e.id}>
+                                            <TableCell>
+                                                {REASONS[e.reason]}
+                                                {e.note && ` — ${e.note}`}
+                                            </TableCell>
+                                            <TableCell dir="ltr">{e.points > 0 ? `+${e.points}` : e.points}</TableCell>
+                                            <TableCell dir="ltr">{e.created_at}</TableCell>
+                                        </TableRow>
+                                    ))}
+                                </TableBody>
+                            </Table>
+                        </div>
+                    </CardContent>
+                </Card>
+            )}
+
+            <Card className="shadow-xl border border-red-200 rounded-2xl">
+                <CardContent className="p-6 space-y-4">
+                    <CardTitle className="text-2xl font-bold text-red-700">أفضل العملاء</CardTitle>
+                    <Table className="border border-red-100 rounded-lg">
+                        <TableHeader>
+                            <TableRow className="bg-red-50 hover:bg-red-50">
+                                <TableHead>الاسم</TableHead>
+                                <TableHead>الزيارات</TableHead>
+                                <TableHead>إجمالي المشتريات</TableHead>
+                                <TableHead>النقاط</TableHead>
+                            </TableRow>
+                        </TableHeader>
+                        <TableBody>
+                            {topCustomers.map((c) => (
+                                <TableRow key={c.id}>
+                                    <TableCell>{c.name}</TableCell>
+                                    <TableCell>{c.visits}</TableCell>
+                                    <TableCell>{c.total_spent.toFixed(2)}</TableCell>
+                                    <TableCell>{c.points}</TableCell>
+                                </TableRow>
+                            ))}
+                        </TableBody>
+                    </Table>
+                </CardContent>
+            </Card>
+        </div>
+    );
+}
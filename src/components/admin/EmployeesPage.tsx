@@ -1,5 +1,7 @@
 import { useEffect, useState } from "react";
 import { invoke } from "@tauri-apps/api/core";
+import { useAuth } from "@/context/AuthContext";
+import { errorMessage } from "@/lib/errors";
 import { Button } from "@/components/ui/button";
 import { Input } from "@/components/ui/input";
 import {
@@ -14,15 +16,36 @@ import { Card, CardContent, CardTitle } from "@/components/ui/card";
 import { Trash2, Check, X, Edit3, PlusCircle } from "lucide-react";
 import toast from "react-hot-toast";
 
-type Employee = { id: number; name: string; password: string };
+type Employee = { id: number; name: string; password: string; has_pin: boolean; role_id: number | null };
+type Permission = { code: string; description: string };
+type Role = { id: number; name: string; permissions: string[] };
+
+const PERMISSION_LABELS: Record<string, string> = {
+    void_item: "إلغاء الأصناف والفواتير",
+    refund: "المرتجعات",
+    discount_over_limit: "خصم فوق الحد المسموح",
+    open_drawer_no_sale: "فتح الدرج بدون بيع",
+    edit_prices: "تعديل الأسعار",
+    view_reports: "عرض التقارير",
+    manage_employees: "إدارة الموظفين",
+};
 
 export default function EmployeesPage() {
+    const { session } = useAuth();
     const [employees, setEmployees] = useState<Employee[]>([]);
     const [newEmployee, setNewEmployee] = useState("");
     const [newPassword, setNewPassword] = useState("");
     const [editingId, setEditingId] = useState<number | null>(null);
     const [editingName, setEditingName] = useState("");
     const [editingPassword, setEditingPassword] = useState("");
+    const [editingPin, setEditingPin] = useState("");
+    const [catalog, setCatalog] = useState<Permission[]>([]);
+    const [roles, setRoles] = useState<Role[]>([]);
+    const [role, setRole] = useState<{ id: number | null; name: string; permissions: string[] }>({
+        id: null,
+        name: "",
+        permissions: [],
+    });
 
     useEffect(() => {
         (async () => {
@@ -30,9 +53,70 @@ export default function EmployeesPage() {
                 console.error("فشل إعداد جدول الموظفين:", e)
             );
             await fetchEmployees();
+            await fetchRoles();
         })();
     }, []);
 
+    const fetchRoles = async () => {
+        try {
+            setCatalog(await invoke<Permission[]>("get_permission_catalog_cmd"));
+            setRoles(await invoke<Role[]>("get_roles_cmd"));
+        } catch (e) {
+            console.error("فشل في جلب الأدوار:", e);
+        }
+    };
+
+    const togglePermission = (code: string) => {
+        setRole((r) => ({
+            ...r,
+            permissions: r.permissions.includes(code)
+                ? r.permissions.filter((p) => p !== code)
+                : [...r.permissions, code],
+        }));
+    };
+
+    const saveRole = async () => {
+        if (!role.name.trim()) {
+            toast.error("يرجى إدخال اسم الدور");
+            return;
+        }
+        try {
+            await invoke("save_role_cmd", {
+                id: role.id,
+                name: role.name.trim(),
+                permissions: role.permissions,
+                session,
+            });
+            setRole({ id: null, name: "", permissions: [] });
+            await fetchRoles();
+            toast.success("تم حفظ الدور");
+        } catch (e) {
+            console.error("فشل في حفظ الدور:", e);
+            toast.error(errorMessage(e, "فشل في حفظ الدور"));
+        }
+    };
+
+    const removeRole = async (id: number) => {
+        try {
+            await invoke("delete_role_cmd", { id, session });
+            await fetchRoles();
+            await fetchEmployees();
+        } catch (e) {
+            console.error("فشل في حذف الدور:", e);
+            toast.error("فشل في حذف الدور");
+        }
+    };
+
+    const assignRole = async (employeeId: number, roleId: string) => {
+        try {
+            await invoke("set_employee_role_cmd", { employeeId, roleId: roleId ? Number(roleId) : null, session });
+            await fetchEmployees();
+        } catch (e) {
+            console.error("فشل في تعيين الدور:", e);
+            toast.error("فشل في تعيين الدور");
+        }
+    };
+
     const fetchEmployees = async () => {
         try {
             const result = await invoke<Employee[]>("fetch_employees");
@@ -51,7 +135,8 @@ export default function EmployeesPage() {
         try {
             await invoke("add_new_employee", {
                 name: newEmployee.trim(),
-                password: newPassword.trim()
+                password: newPassword.trim(),
+                session,
             });
             setNewEmployee("");
             setNewPassword("");
@@ -59,7 +144,7 @@ export default function EmployeesPage() {
             toast.success("تمت إضافة الموظف بنجاح");
         } catch (e) {
             console.error("فشل في إضافة موظف جديد:", e);
-            toast.error("فشل في إضافة موظف جديد");
+            toast.error(errorMessage(e, "فشل في إضافة موظف جديد"));
         }
     };
 
@@ -73,6 +158,7 @@ export default function EmployeesPage() {
         setEditingId(null);
         setEditingName("");
         setEditingPassword("");
+        setEditingPin("");
     };
 
     const saveEdit = async () => {
@@ -85,20 +171,38 @@ export default function EmployeesPage() {
             await invoke("update_employee_cmd", {
                 id: editingId,
                 name: editingName.trim(),
-                password: editingPassword.trim()
+                password: editingPassword.trim(),
+                session,
             });
+            if (editingPin) {
+                const ok = await invoke<boolean>("set_employee_pin_cmd", { id: editingId, pin: editingPin, session });
+                if (!ok) {
+                    toast.error("الموظف غير موجود");
+                    return;
+                }
+            }
             cancelEdit();
             await fetchEmployees();
             toast.success("تم تعديل بيانات الموظف بنجاح");
         } catch (e) {
             console.error("فشل في تحديث الموظف:", e);
-            toast.error("فشل في تحديث الموظف");
+            toast.error(errorMessage(e, "فشل في تحديث الموظف"));
+        }
+    };
+
+    const clearPin = async (id: number) => {
+        try {
+            await invoke("clear_employee_pin_cmd", { id, session });
+            await fetchEmployees();
+        } catch (e) {
+            console.error("فشل في حذف الرمز:", e);
+            toast.error("فشل في حذف الرمز");
         }
     };
 
     const removeEmployee = async (id: number) => {
         try {
-            await invoke("delete_employee_cmd", { id });
+            await invoke("delete_employee_cmd", { id, session });
             await fetchEmployees();
             toast.success("تم حذف الموظف");
         } catch (e) {
@@ -146,6 +250,8 @@ export default function EmployeesPage() {
                                 <TableRow className="bg-red-50 hover:bg-red-50">
                                     <TableHead className="text-gray-700 font-semibold">الاسم</TableHead>
                                     <TableHead className="text-gray-700 font-semibold">كلمة المرور</TableHead>
+                                    <TableHead className="text-gray-700 font-semibold">الرمز السري</TableHead>
+                                    <TableHead className="text-gray-700 font-semibold">الدور</TableHead>
                                     <TableHead className="text-right pr-10 text-gray-700 font-semibold">
                                         الإجراءات
                                     </TableHead>
@@ -177,6 +283,38 @@ export default function EmployeesPage() {
                                                 <span className="font-medium text-gray-600">••••••</span>
                                             )}
                                         </TableCell>
+                                        <TableCell>
+                                            {editingId === e.id ? (
+                                                <Input
+                                                    type="password"
+                                                    inputMode="numeric"
+                                                    placeholder={e.has_pin ? "تغيير الرمز" : "رمز جديد"}
+                                                    value={editingPin}
+                                                    onChange={(ev) => setEditingPin(ev.target.value.replace(/\D/g, ""))}
+                                                    className="w-32 border-red-300 focus:ring-2 focus:ring-red-500"
+                                                />
+                                            ) : e.has_pin ? (
+                                                <button onClick={() => clearPin(e.id)} className="text-sm text-red-600">
+                                                    معيّن — حذف
+                                                </button>
+                                            ) : (
+                                                <span className="text-gray-400">-</span>
+                                            )}
+                                        </TableCell>
+                                        <TableCell>
+                                            <select
+                                                value={e.role_id ?? ""}
+                                                onChange={(ev) => assignRole(e.id, ev.target.value)}
+                                                className="border border-red-300 rounded-lg px-2 py-1"
+                                            >
+                                                <option value="">بدون</option>
+                                                {roles.map((r) => (
+                                                    <option key={r.id} value={r.id}>
+                                                        {r.name}
+                                                    </option>
+                                                ))}
+                                            </select>
+                                        </TableCell>
                                         <TableCell className="text-right">
                                             {editingId === e.id ? (
                                                 <div className="flex justify-end gap-2">
@@ -220,7 +358,7 @@ export default function EmployeesPage() {
 
                                 {employees.length === 0 && (
                                     <TableRow>
-                                        <TableCell colSpan={3} className="text-center text-gray-500 p-4">
+                                        <TableCell colSpan={5} className="text-center text-gray-500 p-4">
                                             لا يوجد موظفين.
                                         </TableCell>
                                     </TableRow>
@@ -230,6 +368,65 @@ export default function EmployeesPage() {
                     </div>
                 </CardContent>
             </Card>
+
+            <Card className="shadow-xl border border-red-200 rounded-2xl mt-6">
+                <CardContent className="p-6 space-y-4">
+                    <CardTitle className="text-2xl font-bold text-red-700">الأدوار والصلاحيات</CardTitle>
+                    <Input
+                        placeholder="اسم الدور"
+                        value={role.name}
+                        onChange={(e) => setRole({ ...role, name: e.target.value })}
+                        className="border-red-300 focus:ring-2 focus:ring-red-500"
+                    />
+                    <div className="grid grid-cols-1 sm:grid-cols-2 gap-2">
+                        {catalog.map((p) => (
+                            <label key={p.code} className="flex items-center gap-2 text-sm">
+                                <input
+                                    type="checkbox"
+                                    checked={role.permissions.includes(p.code)}
+                                    onChange={() => togglePermission(p.code)}
+                                />
+                                {PERMISSION_LABELS[p.code] ?? p.description}
+                            </label>
+                        ))}
+                    </div>
+                    <div className="flex gap-2">
+                        <Button onClick={saveRole} className="bg-red-600 hover:bg-red-700 text-white">
+                            <Check size={18} /> حفظ الدور
+                        </Button>
+                        {role.id !== null && (
+                            <Button variant="outline" onClick={() => setRole({ id: null, name: "", permissions: [] })}>
+                                <X size={18} /> إلغاء
+                            </Button>
+                        )}
+                    </div>
+                    <Table className="border border-red-100 rounded-lg">
+                        <TableBody>
+                            {roles.map((r) => (
+                                <TableRow key={r.id}>
+                                    <TableCell className="font-medium">{r.name}</TableCell>
+                                    <TableCell className="text-sm text-gray-600">
+                                        {r.permissions.map((p) => PERMISSION_LABELS[p] ?? p).join("، ")}
+                                    </TableCell>
+                                    <TableCell className="text-right">
+                                        <div className="flex justify-end gap-2">
+                                            <Button
+                                                className="bg-blue-600 hover:bg-blue-700 text-white px-3 rounded"
+                                                onClick={() => setRole({ id: r.id, name: r.name, permissions: r.permissions })}
+                                            >
+                                                <Edit3 className="w-4 h-4" />
+                                            </Button>
+                                            <Button variant="destructive" onClick={() => removeRole(r.id)} className="px-3 rounded">
+                                                <Trash2 className="w-4 h-4" />
+                                            </Button>
+                                        </div>
+                                    </TableCell>
+                                </TableRow>
+                            ))}
+                        </TableBody>
+                    </Table>
+                </CardContent>
+            </Card>
         </div>
     );
 }
@@ -0,0 +1,187 @@
+import { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { useAuth } from "@/context/AuthContext";
+import { Button } from "@/components/ui/button";
+import { Input } from "@/components/ui/input";
+import {
+    Table,
+    TableBody,
+    TableCell,
+    TableHead,
+    TableHeader,
+    TableRow
+} from "@/components/ui/table";
+import { Card, CardContent, CardTitle } from "@/components/ui/card";
+import { Search } from "lucide-react";
+import toast from "react-hot-toast";
+
+type GiftCard = {
+    id: number;
+    code: string;
+    kind: "gift_card" | "store_credit";
+    initial_amount: number;
+    balance: number;
+    expires_at: string | null;
+    customer_id: number | null;
+    active: boolean;
+    expired: boolean;
+    created_at: string;
+};
+
+type GiftCardEntry = {
+    id: number;
+    sale_id: number | null;
+    amount: number;
+    kind: "issue" | "redeem" | "void" | "adjust";
+    created_at: string;
+};
+
+type GiftCardLiability = {
+    outstanding: number;
+    gift_card_outstanding: number;
+    store_credit_outstanding: number;
+    expired: number;
+    cards: GiftCard[];
+};
+
+const KINDS = { gift_card: "بطاقة هدية", store_credit: "رصيد متجر" };
+const ENTRY_KINDS = { issue: "إصدار", redeem: "استخدام", void: "إلغاء", adjust: "تعديل" };
+
+const formatCode = (code: string) => code.match(/.{1,4}/g)?.join("-") ?? code;
+
+export default function GiftCardsPage() {
+    const { session } = useAuth();
+    const [code, setCode] = useState("");
+    const [card, setCard] = useState<GiftCard | null>(null);
+    const [ledger, setLedger] = useState<GiftCardEntry[]>([]);
+    const [liability, setLiability] = useState<GiftCardLiability | null>(null);
+
+    useEffect(() => {
+        fetchLiability();
+    }, []);
+
+    const fetchLiability = async () => {
+        try {
+            setLiability(await invoke<GiftCardLiability>("get_gift_card_liability_cmd", { session }));
+        } catch (e) {
+            console.error("فشل في جلب تقرير الأرصدة:", e);
+            toast.error("فشل في جلب تقرير الأرصدة");
+        }
+    };
+
+    const lookup = async () => {
+        try {
+            const result = await invoke<GiftCard | null>("get_gift_card_cmd", { code });
+            if (!result) {
+                setCard(null);
+                setLedger([]);
+                toast.error("رمز البطاقة غير صحيح");
+                return;
+            }
+            setCard(result);
+            setLedger(await invoke<GiftCardEntry[]>("get_gift_card_ledger_cmd", { cardId: result.id }));
+        } catch (e) {
+            console.error("فشل في البحث عن البطاقة:", e);
+            toast.error("فشل في البحث عن البطاقة");
+        }
+    };
+
+    return (
+        <div className="p-6 max-w-5xl mx-auto space-y-6">
+            <Card className="shadow-xl border border-red-200 rounded-2xl">
+                <CardContent className="p-6 space-y-4">
+                    <CardTitle className="text-3xl font-bold text-red-700">بطاقات الهدايا</CardTitle>
+                    <div className="flex gap-3">
+                        <Input
+                            dir="ltr"
+                            placeholder="0000-0000-0000-0000"
+                            value={code}
+                            onChange={(e) => setCode(e.target.value)}
+                            onKeyDown={(e) => e.key === "Enter" && lookup()}
+                            className="flex-1 border-red-300 focus:ring-2 focus:ring-red-500"
+                        />
+                        <Button onClick={lookup} className="bg-red-600 hover:bg-red-700 text-white">
+                            <Search size={18} /> الرصيد
+                        </Button>
+                    </div>
+
+                    {card && (
+                        <div className="space-y-3">
+                            <p className="text-lg">
+                                {KINDS[card.kind]} <span dir="ltr">{formatCode(card.code)}</span> —{" "}
+                                <span className="font-bold">{card.balance.toFixed(2)}</span>
+                                {!card.active && " (غير مفعلة)"}
+                                {card.expired && " (منتهية)"}
+                                {card.expires_at && !card.expired && ` — تنتهي في ${card.expires_at}`}
+                            </p>
+                            <Table className="border border-red-100 rounded-lg">
+                                <TableHeader>
+                                    <TableRow className="bg-red-50 hover:bg-red-50">
+                                        <TableHead>الحركة</TableHead>
+                                        <TableHead>المبلغ</TableHead>
+                                        <TableHead>التاريخ</TableHead>
+                                    </TableRow>
+                                </TableHeader>
+                                <TableBody>
+                                    {ledger.map((e) => (
+                                        <TableRow key={e.id}>
+                                            <TableCell>{ENTRY_KINDS[e.kind]}</TableCell>
+                                            <TableCell dir="ltr">{e.amount.toFixed(2)}</TableCell>
+                                            <TableCell dir="ltr">{e.created_at}</TableCell>
+                                        </TableRow>
+                                    ))}
+                                </TableBody>
+                            </Table>
+                        </div>
+                    )}
+                </CardContent>
+            </Card>
+
+            {liability && (
+                <Card className="shadow-xl border border-red-200 rounded-2xl">
+                    <CardContent className="p-6 space-y-4">
+                        <CardTitle className="text-2xl font-bold text-red-700">الأرصدة المستحقة</CardTitle>
+                        <div className="grid grid-cols-2 sm:grid-cols-4 gap-3 text-center">
+                            <div className="bg-red-50 rounded-lg p-3">
+                                <p className="text-sm text-gray-600">الإجمالي</p>
+                                <p className="text-xl font-bold">{liability.outstanding.toFixed(2)}</p>
+                            </div>
+                            <div className="bg-red-50 rounded-lg p-3">
+                                <p className="text-sm text-gray-600">بطاقات الهدايا</p>
+                                <p className="text-xl font-bold">{liability.gift_card_outstanding.toFixed(2)}</p>
+                            </div>
+                            <div className="bg-red-50 rounded-lg p-3">
+                                <p className="text-sm text-gray-600">رصيد المتجر</p>
+                                <p className="text-xl font-bold">{liability.store_credit_outstanding.toFixed(2)}</p>
+                            </div>
+                            <div className="bg-gray-100 rounded-lg p-3">
+                                <p className="text-sm text-gray-600">منتهية الصلاحية</p>
+                                <p className="text-xl font-bold">{liability.expired.toFixed(2)}</p>
+                            </div>
+                        </div>
+                        <Table className="border border-red-100 rounded-lg">
+                            <TableHeader>
+                                <TableRow className="bg-red-50 hover:bg-red-50">
+                                    <TableHead>الرمز</TableHead>
+                                    <TableHead>النوع</TableHead>
+                                    <TableHead>الرصيد</TableHead>
+                                    <TableHead>الانتهاء</TableHead>
+                                </TableRow>
+                            </TableHeader>
+                            <TableBody>
+                                {liability.cards.map((c) => (
+                                    <TableRow key={c.id} className={c.expired ? "text-gray-400" : ""}>
+                                        <TableCell dir="ltr">{formatCode(c.code)}</TableCell>
+                                        <TableCell>{KINDS[c.kind]}</TableCell>
+                                        <TableCell>{c.balance.toFixed(2)}</TableCell>
+                                        <TableCell dir="ltr">{c.expires_at ?? "-"}</TableCell>
+                                    </TableRow>
+                                ))}
+                            </TableBody>
+                        </Table>
+                    </CardContent>
+                </Card>
+            )}
+        </div>
+    );
+}
@@ -1,5 +1,7 @@
 import { useEffect, useState } from "react";
 import { invoke } from "@tauri-apps/api/core";
+import { errorMessage } from "@/lib/errors";
+import { Unit, UNIT_LABELS } from "@/lib/units";
 import { Card, CardContent, CardHeader, CardTitle } from "@/components/ui/card";
 import { Button } from "@/components/ui/button";
 import { Input } from "@/components/ui/input";
@@ -11,7 +13,22 @@ import {
     TableHeader,
     TableRow,
 } from "@/components/ui/table";
-import { PlusCircle, Edit3, Trash2, Check, X } from "lucide-react";
+import {
+    PlusCircle,
+    Edit3,
+    Trash2,
+    Check,
+    X,
+    Barcode,
+    Printer,
+    FileDown,
+    Layers,
+    Package,
+    History,
+    Clock,
+    Archive,
+    ArchiveRestore,
+} from "lucide-react";
 import toast from "react-hot-toast";
 
 type Product = {
@@ -19,24 +36,167 @@ type Product = {
     name: string;
     price: number;
     barcode: string;
+    net_content: number | null;
+    net_content_unit: string | null;
+    unit: Unit;
+    parent_id: number | null;
+    category: string | null;
+    sold_out: boolean;
+    archived: boolean;
+};
+
+type Variant = {
+    product: Product;
+    attributes: { name: string; value: string }[];
+    stock: number | null;
+};
+
+type BundleGroup = {
+    name: string;
+    options: { product_id: number; quantity: number; name?: string }[];
+};
+
+type PriceEntry = {
+    id: number;
+    price: number;
+    effective_at: string;
+    scheduled: boolean;
+};
+
+type AvailabilityRule = {
+    id: number;
+    product_id: number | null;
+    category: string | null;
+    days: string;
+    start_time: string | null;
+    end_time: string | null;
+    start_date: string | null;
+    end_date: string | null;
+};
+
+// أيام الأسبوع بترقيم ISO (الإثنين = 1)
+const WEEKDAYS = [
+    ["1", "الإثنين"],
+    ["2", "الثلاثاء"],
+    ["3", "الأربعاء"],
+    ["4", "الخميس"],
+    ["5", "الجمعة"],
+    ["6", "السبت"],
+    ["7", "الأحد"],
+];
+
+const EMPTY_RULE = { target: "", days: "1234567", startTime: "", endTime: "", startDate: "", endDate: "" };
+
+const CONTENT_UNITS = ["g", "kg", "ml", "l"];
+
+type ProductBarcode = {
+    id: number;
+    product_id: number;
+    barcode: string;
+    pack_quantity: number;
 };
 
 export default function ProductsPage() {
     const [products, setProducts] = useState<Product[]>([]);
+    const [showArchived, setShowArchived] = useState(false);
     const [newProduct, setNewProduct] = useState({
         name: "",
         price: "",
         barcode: "",
+        unit: "each" as Unit,
     });
     const [editingProduct, setEditingProduct] = useState<Product | null>(null);
+    const [barcodesFor, setBarcodesFor] = useState<Product | null>(null);
+    const [extraBarcodes, setExtraBarcodes] = useState<ProductBarcode[]>([]);
+    const [newBarcode, setNewBarcode] = useState({ barcode: "", packQuantity: "1" });
+    const [variantsFor, setVariantsFor] = useState<Product | null>(null);
+    const [variants, setVariants] = useState<Variant[]>([]);
+    const [newVariant, setNewVariant] = useState({ attributes: "", price: "", barcode: "" });
+    const [stockChange, setStockChange] = useState<Record<number, string>>({});
+    const [bundleFor, setBundleFor] = useState<Product | null>(null);
+    const [bundleGroups, setBundleGroups] = useState<BundleGroup[]>([]);
+    const [pricesFor, setPricesFor] = useState<Product | null>(null);
+    const [priceHistory, setPriceHistory] = useState<PriceEntry[]>([]);
+    const [scheduled, setScheduled] = useState({ price: "", at: "" });
+    const [rules, setRules] = useState<AvailabilityRule[]>([]);
+    const [newRule, setNewRule] = useState(EMPTY_RULE);
+    const [selected, setSelected] = useState<number[]>([]);
+    const [labelOptions, setLabelOptions] = useState({
+        since: "",
+        copies: "1",
+        unitPrice: true,
+        skip: "0",
+        path: "",
+    });
 
     useEffect(() => {
-        fetchProducts();
+        fetchRules();
     }, []);
 
+    useEffect(() => {
+        fetchProducts();
+    }, [showArchived]);
+
+    const fetchRules = async () => {
+        try {
+            setRules(await invoke<AvailabilityRule[]>("get_availability_rules_cmd"));
+        } catch (e) {
+            console.error("get_availability_rules failed:", e);
+        }
+    };
+
+    // الهدف منتج ("p:<id>") أو تصنيف ("c:<الاسم>")
+    const addRule = async () => {
+        if (!newRule.target) return;
+        const [kind, value] = [newRule.target.slice(0, 2), newRule.target.slice(2)];
+        try {
+            await invoke("add_availability_rule_cmd", {
+                rule: {
+                    product_id: kind === "p:" ? Number(value) : null,
+                    category: kind === "c:" ? value : null,
+                    days: newRule.days,
+                    start_time: newRule.startTime || null,
+                    end_time: newRule.endTime || null,
+                    start_date: newRule.startDate || null,
+                    end_date: newRule.endDate || null,
+                },
+            });
+            setNewRule(EMPTY_RULE);
+            fetchRules();
+        } catch (e) {
+            toast.error(errorMessage(e, "فشل في إضافة وقت التوفر"));
+        }
+    };
+
+    const deleteRule = async (id: number) => {
+        try {
+            await invoke("delete_availability_rule_cmd", { id });
+            fetchRules();
+        } catch (e) {
+            toast.error(errorMessage(e));
+        }
+    };
+
+    const toggleRuleDay = (day: string) =>
+        setNewRule({
+            ...newRule,
+            days: newRule.days.includes(day) ? newRule.days.replace(day, "") : newRule.days + day,
+        });
+
+    const toggleSoldOut = async (product: Product) => {
+        try {
+            await invoke("set_sold_out_cmd", { productId: product.id, soldOut: !product.sold_out });
+            fetchProducts();
+        } catch (e) {
+            toast.error(errorMessage(e));
+        }
+    };
+
+    const categories = Array.from(new Set(products.map((p) => p.category).filter((c): c is string => !!c)));
+
     const fetchProducts = async () => {
         try {
-            const result = await invoke<Product[]>("get_products_cmd");
+            const result = await invoke<Product[]>("get_products_cmd", { includeArchived: showArchived });
             if (result) setProducts(result);
         } catch (e) {
             console.error("fetch_products failed:", e);
@@ -50,15 +210,173 @@ export default function ProductsPage() {
             name: newProduct.name.trim(),
             price: parseFloat(newProduct.price),
             barcode: newProduct.barcode.trim() === "" ? null : newProduct.barcode.trim(),
+            unit: newProduct.unit,
         };
 
         try {
             await invoke("add_product_cmd", payload as any);
-            setNewProduct({ name: "", price: "", barcode: "" });
+            setNewProduct({ name: "", price: "", barcode: "", unit: "each" });
             fetchProducts();
             toast.success("تمت إضافة المنتج بنجاح");
-        } catch (e: any) {
-            toast.error("فشل في إضافة المنتج");
+        } catch (e) {
+            toast.error(errorMessage(e, "فشل في إضافة المنتج"));
+        }
+    };
+
+    const generateBarcode = async () => {
+        try {
+            const barcode = await invoke<string>("generate_barcode_cmd");
+            setNewProduct({ ...newProduct, barcode });
+        } catch (e) {
+            toast.error(errorMessage(e, "فشل في توليد الباركود"));
+        }
+    };
+
+    const openBarcodes = async (product: Product) => {
+        setBarcodesFor(product);
+        try {
+            setExtraBarcodes(await invoke<ProductBarcode[]>("get_product_barcodes_cmd", { productId: product.id }));
+        } catch (e) {
+            console.error("get_product_barcodes failed:", e);
+        }
+    };
+
+    const addExtraBarcode = async () => {
+        if (!barcodesFor || !newBarcode.barcode.trim()) return;
+        try {
+            await invoke("add_product_barcode_cmd", {
+                productId: barcodesFor.id,
+                barcode: newBarcode.barcode.trim(),
+                packQuantity: Number(newBarcode.packQuantity) || 1,
+            });
+            setNewBarcode({ barcode: "", packQuantity: "1" });
+            await openBarcodes(barcodesFor);
+        } catch (e) {
+            toast.error(errorMessage(e, "فشل في إضافة الباركود"));
+        }
+    };
+
+    const deleteExtraBarcode = async (id: number) => {
+        if (!barcodesFor) return;
+        try {
+            await invoke("delete_product_barcode_cmd", { id });
+            await openBarcodes(barcodesFor);
+        } catch (e) {
+            toast.error(errorMessage(e, "فشل في حذف الباركود"));
+        }
+    };
+
+    const openVariants = async (product: Product) => {
+        setVariantsFor(product);
+        try {
+            setVariants(await invoke<Variant[]>("get_variants_cmd", { parentId: product.id }));
+        } catch (e) {
+            console.error("get_variants failed:", e);
+        }
+    };
+
+    // السمات تكتب بالشكل: الحجم: كبير، الحليب: شوفان
+    const addVariant = async () => {
+        if (!variantsFor || newVariant.price === "") return;
+        const attributes = newVariant.attributes
+            .split(/[,،]/)
+            .filter((a) => a.trim())
+            .map((a) => {
+                const [name, ...value] = a.split(":");
+                return { name: name.trim(), value: value.join(":").trim() };
+            });
+        try {
+            await invoke("add_variant_cmd", {
+                parentId: variantsFor.id,
+                attributes,
+                price: parseFloat(newVariant.price),
+                barcode: newVariant.barcode.trim() || null,
+            });
+            setNewVariant({ attributes: "", price: "", barcode: "" });
+            await openVariants(variantsFor);
+            fetchProducts();
+        } catch (e) {
+            toast.error(errorMessage(e, "فشل في إضافة النوع"));
+        }
+    };
+
+    const adjustStock = async (productId: number) => {
+        const quantity = parseFloat(stockChange[productId] ?? "");
+        if (!quantity || !variantsFor) return;
+        try {
+            await invoke("adjust_stock_cmd", { productId, quantity, note: "" });
+            setStockChange({ ...stockChange, [productId]: "" });
+            await openVariants(variantsFor);
+        } catch (e) {
+            toast.error(errorMessage(e, "فشل في تعديل المخزون"));
+        }
+    };
+
+    const stopTracking = async (productId: number) => {
+        if (!variantsFor) return;
+        try {
+            await invoke("set_stock_tracking_cmd", { productId, track: false });
+            await openVariants(variantsFor);
+        } catch (e) {
+            toast.error(errorMessage(e));
+        }
+    };
+
+    const openBundle = async (product: Product) => {
+        setBundleFor(product);
+        try {
+            setBundleGroups(await invoke<BundleGroup[]>("get_bundle_cmd", { bundleId: product.id }));
+        } catch (e) {
+            console.error("get_bundle failed:", e);
+        }
+    };
+
+    const updateGroup = (index: number, group: BundleGroup) =>
+        setBundleGroups(bundleGroups.map((g, i) => (i === index ? group : g)));
+
+    // مجموعة بخيار واحد مكون ثابت، وبأكثر من خيار يختار العميل أحدها
+    const saveBundle = async () => {
+        if (!bundleFor) return;
+        try {
+            await invoke("set_bundle_cmd", { bundleId: bundleFor.id, groups: bundleGroups });
+            toast.success("تم حفظ مكونات الوجبة");
+        } catch (e) {
+            toast.error(errorMessage(e, "فشل في حفظ مكونات الوجبة"));
+        }
+    };
+
+    const openPrices = async (product: Product) => {
+        setPricesFor(product);
+        try {
+            setPriceHistory(await invoke<PriceEntry[]>("get_price_history_cmd", { productId: product.id }));
+        } catch (e) {
+            console.error("get_price_history failed:", e);
+        }
+    };
+
+    // الوقت المحلي يرسل بتوقيت UTC كما تخزن الأوقات في قاعدة البيانات
+    const schedulePrice = async () => {
+        if (!pricesFor || scheduled.price === "" || !scheduled.at) return;
+        try {
+            await invoke("schedule_price_cmd", {
+                productId: pricesFor.id,
+                price: parseFloat(scheduled.price),
+                effectiveAt: new Date(scheduled.at).toISOString(),
+            });
+            setScheduled({ price: "", at: "" });
+            await openPrices(pricesFor);
+        } catch (e) {
+            toast.error(errorMessage(e, "فشل في جدولة السعر"));
+        }
+    };
+
+    const cancelScheduledPrice = async (id: number) => {
+        if (!pricesFor) return;
+        try {
+            await invoke("cancel_scheduled_price_cmd", { id });
+            await openPrices(pricesFor);
+        } catch (e) {
+            toast.error(errorMessage(e));
         }
     };
 
@@ -73,12 +391,78 @@ export default function ProductsPage() {
                 name: editingProduct.name,
                 price: editingProduct.price,
                 barcode: editingProduct.barcode,
+                unit: editingProduct.unit,
+            });
+            await invoke("set_product_net_content_cmd", {
+                id: editingProduct.id,
+                netContent: editingProduct.net_content,
+                unit: editingProduct.net_content_unit,
+            });
+            await invoke("set_product_category_cmd", {
+                id: editingProduct.id,
+                category: editingProduct.category,
             });
             setEditingProduct(null);
             fetchProducts();
             toast.success("تم تعديل المنتج");
-        } catch {
-            toast.error("فشل في تعديل المنتج");
+        } catch (e) {
+            toast.error(errorMessage(e, "فشل في تعديل المنتج"));
+        }
+    };
+
+    const toggleSelected = (id: number) =>
+        setSelected(selected.includes(id) ? selected.filter((s) => s !== id) : [...selected, id]);
+
+    // اختيار المنتجات التي تغير سعرها منذ التاريخ المحدد
+    const selectPriceChanged = async () => {
+        if (!labelOptions.since) return;
+        try {
+            const changed = await invoke<Product[]>("get_price_changed_products_cmd", { since: labelOptions.since });
+            setSelected(changed.map((p) => p.id));
+            toast.success(`تم اختيار ${changed.length} منتج`);
+        } catch (e) {
+            toast.error(errorMessage(e));
+        }
+    };
+
+    const labelArgs = () => ({
+        productIds: selected,
+        copies: Number(labelOptions.copies) || 1,
+        unitPrice: labelOptions.unitPrice,
+    });
+
+    const printLabels = async () => {
+        if (selected.length === 0) return;
+        try {
+            await invoke("print_labels_cmd", labelArgs());
+            toast.success("تم إرسال الملصقات إلى الطابعة");
+        } catch (e) {
+            toast.error(errorMessage(e, "فشل في طباعة الملصقات"));
+        }
+    };
+
+    const exportLabels = async () => {
+        if (selected.length === 0 || !labelOptions.path.trim()) return;
+        try {
+            await invoke("export_labels_cmd", {
+                ...labelArgs(),
+                skip: Number(labelOptions.skip) || 0,
+                path: labelOptions.path.trim(),
+            });
+            toast.success("تم حفظ ورقة الملصقات");
+        } catch (e) {
+            toast.error(errorMessage(e, "فشل في حفظ ورقة الملصقات"));
+        }
+    };
+
+    // المنتجات المؤرشفة تختفي من نقطة البيع وتبقى في التقارير
+    const toggleArchived = async (product: Product) => {
+        try {
+            await invoke(product.archived ? "restore_product_cmd" : "archive_product_cmd", { id: product.id });
+            await fetchProducts();
+            toast.success(product.archived ? "تمت استعادة المنتج" : "تمت أرشفة المنتج");
+        } catch (e) {
+            toast.error(errorMessage(e));
         }
     };
 
@@ -87,8 +471,8 @@ export default function ProductsPage() {
             await invoke("delete_product_cmd", { id });
             await fetchProducts();
             toast.success("تم حذف المنتج");
-        } catch {
-            toast.error("فشل في حذف المنتج");
+        } catch (e) {
+            toast.error(errorMessage(e, "فشل في حذف المنتج"));
         }
     };
 
@@ -131,6 +515,20 @@ export default function ProductsPage() {
                                 onChange={(e) => setNewProduct({ ...newProduct, barcode: e.target.value })}
                                 className="border-gray-300"
                             />
+                            <select
+                                className="border border-gray-300 rounded-md px-2 h-9"
+                                value={newProduct.unit}
+                                onChange={(e) => setNewProduct({ ...newProduct, unit: e.target.value as Unit })}
+                            >
+                                {(Object.keys(UNIT_LABELS) as Unit[]).map((u) => (
+                                    <option key={u} value={u}>
+                                        {UNIT_LABELS[u]}
+                                    </option>
+                                ))}
+                            </select>
+                            <Button variant="outline" onClick={generateBarcode} className="flex items-center gap-2">
+                                <Barcode className="w-4 h-4" /> توليد
+                            </Button>
                             <Button
                                 onClick={addProduct}
                                 className="bg-red-600 hover:bg-red-700 text-white flex items-center gap-2"
@@ -141,6 +539,14 @@ export default function ProductsPage() {
                     </div>
 
                     {/* جدول المنتجات */}
+                    <label className="flex items-center gap-2 text-sm text-gray-600">
+                        <input
+                            type="checkbox"
+                            checked={showArchived}
+                            onChange={(e) => setShowArchived(e.target.checked)}
+                        />
+                        عرض المنتجات المؤرشفة
+                    </label>
                     <div className="overflow-x-auto">
                         <Table>
                             <TableHeader>
@@ -148,38 +554,89 @@ export default function ProductsPage() {
                                     <TableHead className="text-gray-700">الاسم</TableHead>
                                     <TableHead className="text-gray-700">السعر</TableHead>
                                     <TableHead className="text-gray-700">الباركود</TableHead>
+                                    <TableHead className="text-gray-700">المحتوى</TableHead>
                                     <TableHead className="text-right text-gray-700 pr-8">الإجراءات</TableHead>
                                 </TableRow>
                             </TableHeader>
                             <TableBody>
                                 {products.map((p) => (
-                                    <TableRow key={p.id} className="hover:bg-gray-50">
+                                    <TableRow
+                                        key={p.id}
+                                        className={`${p.parent_id ? "bg-gray-50/50 hover:bg-gray-50" : "hover:bg-gray-50"} ${
+                                            p.archived ? "text-gray-400" : ""
+                                        }`}
+                                    >
                                         <TableCell>
+                                            <input
+                                                type="checkbox"
+                                                className="ml-2"
+                                                checked={selected.includes(p.id)}
+                                                onChange={() => toggleSelected(p.id)}
+                                            />
                                             {editingProduct?.id === p.id ? (
-                                                <Input
-                                                    value={editingProduct.name}
-                                                    onChange={(e) =>
-                                                        setEditingProduct({ ...editingProduct, name: e.target.value })
-                                                    }
-                                                />
+                                                <div className="space-y-1">
+                                                    <Input
+                                                        value={editingProduct.name}
+                                                        onChange={(e) =>
+                                                            setEditingProduct({ ...editingProduct, name: e.target.value })
+                                                        }
+                                                    />
+                                                    <Input
+                                                        placeholder="التصنيف"
+                                                        value={editingProduct.category ?? ""}
+                                                        onChange={(e) =>
+                                                            setEditingProduct({
+                                                                ...editingProduct,
+                                                                category: e.target.value || null,
+                                                            })
+                                                        }
+                                                    />
+                                                </div>
+                                            ) : p.parent_id ? (
+                                                <span className="pr-4 text-gray-600">↳ {p.name}</span>
                                             ) : (
-                                                p.name
+                                                <>
+                                                    {p.name}
+                                                    {p.category && (
+                                                        <span className="mr-2 text-xs text-gray-500">({p.category})</span>
+                                                    )}
+                                                </>
                                             )}
                                         </TableCell>
                                         <TableCell>
                                             {editingProduct?.id === p.id ? (
-                                                <Input
-                                                    type="number"
-                                                    value={editingProduct.price}
-                                                    onChange={(e) =>
-                                                        setEditingProduct({
-                                                            ...editingProduct,
-                                                            price: parseFloat(e.target.value),
-                                                        })
-                                                    }
-                                                />
-                                            ) : (
+                                                <div className="flex gap-1">
+                                                    <Input
+                                                        type="number"
+                                                        value={editingProduct.price}
+                                                        onChange={(e) =>
+                                                            setEditingProduct({
+                                                                ...editingProduct,
+                                                                price: parseFloat(e.target.value),
+                                                            })
+                                                        }
+                                                    />
+                                                    <select
+                                                        className="border rounded-md px-2"
+                                                        value={editingProduct.unit}
+                                                        onChange={(e) =>
+                                                            setEditingProduct({
+                                                                ...editingProduct,
+                                                                unit: e.target.value as Unit,
+                                                            })
+                                                        }
+                                                    >
+                                                        {(Object.keys(UNIT_LABELS) as Unit[]).map((u) => (
+                                                            <option key={u} value={u}>
+                                                                {UNIT_LABELS[u]}
+                                                            </option>
+                                                        ))}
+                                                    </select>
+                                                </div>
+                                            ) : p.unit === "each" ? (
                                                 `${p.price} ج.م`
+                                            ) : (
+                                                `${p.price} ج.م / ${UNIT_LABELS[p.unit]}`
                                             )}
                                         </TableCell>
                                         <TableCell>
@@ -197,6 +654,46 @@ export default function ProductsPage() {
                                                 p.barcode || "-"
                                             )}
                                         </TableCell>
+                                        <TableCell>
+                                            {editingProduct?.id === p.id ? (
+                                                <div className="flex gap-1">
+                                                    <Input
+                                                        type="number"
+                                                        min="0"
+                                                        className="w-24"
+                                                        value={editingProduct.net_content ?? ""}
+                                                        onChange={(e) =>
+                                                            setEditingProduct({
+                                                                ...editingProduct,
+                                                                net_content:
+                                                                    e.target.value === "" ? null : parseFloat(e.target.value),
+                                                            })
+                                                        }
+                                                    />
+                                                    <select
+                                                        className="border rounded-md px-2"
+                                                        value={editingProduct.net_content_unit ?? ""}
+                                                        onChange={(e) =>
+                                                            setEditingProduct({
+                                                                ...editingProduct,
+                                                                net_content_unit: e.target.value || null,
+                                                            })
+                                                        }
+                                                    >
+                                                        <option value="">-</option>
+                                                        {CONTENT_UNITS.map((u) => (
+                                                            <option key={u} value={u}>
+                                                                {u}
+                                                            </option>
+                                                        ))}
+                                                    </select>
+                                                </div>
+                                            ) : p.net_content && p.net_content_unit ? (
+                                                <span dir="ltr">{`${p.net_content} ${p.net_content_unit}`}</span>
+                                            ) : (
+                                                "-"
+                                            )}
+                                        </TableCell>
                                         <TableCell className="text-right">
                                             {editingProduct?.id === p.id ? (
                                                 <div className="flex justify-end gap-2">
@@ -225,6 +722,41 @@ export default function ProductsPage() {
                                                     >
                                                         <Edit3 className="w-4 h-4" />
                                                     </Button>
+                                                    <Button variant="outline" onClick={() => openBarcodes(p)}>
+                                                        <Barcode className="w-4 h-4" />
+                                                    </Button>
+                                                    {p.parent_id === null && (
+                                                        <Button variant="outline" onClick={() => openVariants(p)}>
+                                                            <Layers className="w-4 h-4" />
+                                                        </Button>
+                                                    )}
+                                                    <Button
+                                                        variant="outline"
+                                                        title={p.sold_out ? "إعادة للبيع" : "نفد اليوم (86)"}
+                                                        className={p.sold_out ? "bg-gray-200" : ""}
+                                                        onClick={() => toggleSoldOut(p)}
+                                                    >
+                                                        86
+                                                    </Button>
+                                                    <Button variant="outline" onClick={() => openPrices(p)}>
+                                                        <History className="w-4 h-4" />
+                                                    </Button>
+                                                    {p.unit === "each" && (
+                                                        <Button variant="outline" onClick={() => openBundle(p)}>
+                                                            <Package className="w-4 h-4" />
+                                                        </Button>
+                                                    )}
+                                                    <Button
+                                                        variant="outline"
+                                                        title={p.archived ? "استعادة" : "أرشفة"}
+                                                        onClick={() => toggleArchived(p)}
+                                                    >
+                                                        {p.archived ? (
+                                                            <ArchiveRestore className="w-4 h-4" />
+                                                        ) : (
+                                                            <Archive className="w-4 h-4" />
+                                                        )}
+                                                    </Button>
                                                     <Button
                                                         variant="destructive"
                                                         onClick={() => deleteProduct(p.id)}
@@ -239,7 +771,7 @@ export default function ProductsPage() {
 
                                 {products.length === 0 && (
                                     <TableRow>
-                                        <TableCell colSpan={4} className="text-center text-gray-500 p-4">
+                                        <TableCell colSpan={5} className="text-center text-gray-500 p-4">
                                             لا توجد منتجات بعد.
                                         </TableCell>
                                     </TableRow>
@@ -247,6 +779,361 @@ export default function ProductsPage() {
                             </TableBody>
                         </Table>
                     </div>
+
+                    {/* أوقات توفر المنتجات والتصنيفات */}
+                    <div className="border border-gray-200 rounded-lg p-4 space-y-3">
+                        <p className="font-semibold flex items-center gap-2">
+                            <Clock className="w-4 h-4" /> أوقات التوفر
+                        </p>
+                        <div className="flex flex-wrap gap-2 items-center">
+                            <select
+                                className="border border-gray-300 rounded-md px-2 h-9"
+                                value={newRule.target}
+                                onChange={(e) => setNewRule({ ...newRule, target: e.target.value })}
+                            >
+                                <option value="">المنتج أو التصنيف</option>
+                                {categories.map((c) => (
+                                    <option key={c} value={`c:${c}`}>
+                                        تصنيف: {c}
+                                    </option>
+                                ))}
+                                {products
+                                    .filter((p) => p.parent_id === null && !p.archived)
+                                    .map((p) => (
+                                        <option key={p.id} value={`p:${p.id}`}>
+                                            {p.name}
+                                        </option>
+                                    ))}
+                            </select>
+                            {WEEKDAYS.map(([day, label]) => (
+                                <label key={day} className="flex items-center gap-1 text-sm">
+                                    <input
+                                        type="checkbox"
+                                        checked={newRule.days.includes(day)}
+                                        onChange={() => toggleRuleDay(day)}
+                                    />
+                                    {label}
+                                </label>
+                            ))}
+                        </div>
+                        <div className="flex flex-wrap gap-2 items-center text-sm">
+                            <span>من</span>
+                            <Input
+                                type="time"
+                                value={newRule.startTime}
+                                onChange={(e) => setNewRule({ ...newRule, startTime: e.target.value })}
+                                className="w-32 border-gray-300"
+                            />
+                            <span>إلى</span>
+                            <Input
+                                type="time"
+                                value={newRule.endTime}
+                                onChange={(e) => setNewRule({ ...newRule, endTime: e.target.value })}
+                                className="w-32 border-gray-300"
+                            />
+                            <span>خلال الفترة</span>
+                            <Input
+                                type="date"
+                                value={newRule.startDate}
+                                onChange={(e) => setNewRule({ ...newRule, startDate: e.target.value })}
+                                className="w-44 border-gray-300"
+                            />
+                            <Input
+                                type="date"
+                                value={newRule.endDate}
+                                onChange={(e) => setNewRule({ ...newRule, endDate: e.target.value })}
+                                className="w-44 border-gray-300"
+                            />
+                            <Button onClick={addRule} className="bg-red-600 hover:bg-red-700 text-white">
+                                <PlusCircle className="w-4 h-4" /> إضافة
+                            </Button>
+                        </div>
+                        {rules.map((r) => (
+                            <div key={r.id} className="flex flex-wrap gap-3 justify-between items-center text-sm">
+                                <span className="font-medium">
+                                    {r.category
+                                        ? `تصنيف: ${r.category}`
+                                        : products.find((p) => p.id === r.product_id)?.name ?? r.product_id}
+                                </span>
+                                <span>
+                                    {WEEKDAYS.filter(([day]) => r.days.includes(day))
+                                        .map(([, label]) => label)
+                                        .join("، ")}
+                                </span>
+                                <span dir="ltr">{r.start_time ? `${r.start_time} - ${r.end_time}` : "طوال اليوم"}</span>
+                                <span dir="ltr">{r.start_date || r.end_date ? `${r.start_date ?? "…"} → ${r.end_date ?? "…"}` : ""}</span>
+                                <Button variant="destructive" size="sm" onClick={() => deleteRule(r.id)}>
+                                    <Trash2 className="w-4 h-4" />
+                                </Button>
+                            </div>
+                        ))}
+                    </div>
+
+                    {/* طباعة ملصقات الرفوف */}
+                    <div className="border border-gray-200 rounded-lg p-4 space-y-3">
+                        <p className="font-semibold">ملصقات الرفوف ({selected.length} منتج محدد)</p>
+                        <div className="flex flex-wrap gap-2 items-center">
+                            <span className="text-sm">تغير سعرها منذ</span>
+                            <Input
+                                type="date"
+                                value={labelOptions.since}
+                                onChange={(e) => setLabelOptions({ ...labelOptions, since: e.target.value })}
+                                className="w-44 border-gray-300"
+                            />
+                            <Button variant="outline" onClick={selectPriceChanged}>
+                                اختيار
+                            </Button>
+                            <Button variant="outline" onClick={() => setSelected([])}>
+                                إلغاء التحديد
+                            </Button>
+                        </div>
+                        <div className="flex flex-wrap gap-2 items-center">
+                            <Input
+                                type="number"
+                                min="1"
+                                placeholder="عدد النسخ"
+                                value={labelOptions.copies}
+                                onChange={(e) => setLabelOptions({ ...labelOptions, copies: e.target.value })}
+                                className="w-28 border-gray-300"
+                            />
+                            <label className="flex items-center gap-1 text-sm">
+                                <input
+                                    type="checkbox"
+                                    checked={labelOptions.unitPrice}
+                                    onChange={(e) => setLabelOptions({ ...labelOptions, unitPrice: e.target.checked })}
+                                />
+                                سعر الوحدة
+                            </label>
+                            <Button onClick={printLabels} className="bg-red-600 hover:bg-red-700 text-white">
+                                <Printer className="w-4 h-4" /> طباعة
+                            </Button>
+                        </div>
+                        <div className="flex flex-wrap gap-2 items-center">
+                            <Input
+                                type="number"
+                                min="0"
+                                placeholder="تخطي أول ملصقات"
+                                value={labelOptions.skip}
+                                onChange={(e) => setLabelOptions({ ...labelOptions, skip: e.target.value })}
+                                className="w-28 border-gray-300"
+                            />
+                            <Input
+                                placeholder="مسار ملف PDF"
+                                value={labelOptions.path}
+                                onChange={(e) => setLabelOptions({ ...labelOptions, path: e.target.value })}
+                                className="w-72 border-gray-300"
+                                dir="ltr"
+                            />
+                            <Button variant="outline" onClick={exportLabels}>
+                                <FileDown className="w-4 h-4" /> حفظ PDF
+                            </Button>
+                        </div>
+                    </div>
+
+                    {pricesFor && (
+                        <div className="mt-6 border border-gray-200 rounded-lg p-4 space-y-3">
+                            <div className="flex justify-between items-center">
+                                <p className="font-semibold">سجل الأسعار: {pricesFor.name}</p>
+                                <Button variant="outline" size="sm" onClick={() => setPricesFor(null)}>
+                                    <X size={16} />
+                                </Button>
+                            </div>
+                            <div className="flex flex-wrap gap-2">
+                                <Input
+                                    type="number"
+                                    min="0"
+                                    placeholder="السعر الجديد"
+                                    value={scheduled.price}
+                                    onChange={(e) => setScheduled({ ...scheduled, price: e.target.value })}
+                                    className="w-32 border-gray-300"
+                                />
+                                <Input
+                                    type="datetime-local"
+                                    value={scheduled.at}
+                                    onChange={(e) => setScheduled({ ...scheduled, at: e.target.value })}
+                                    className="w-56 border-gray-300"
+                                />
+                                <Button onClick={schedulePrice} className="bg-red-600 hover:bg-red-700 text-white">
+                                    <PlusCircle className="w-4 h-4" /> جدولة
+                                </Button>
+                            </div>
+                            {priceHistory.map((h) => (
+                                <div key={h.id} className="flex justify-between items-center text-sm">
+                                    <span dir="ltr">{h.effective_at.startsWith("1970") ? "-" : `${h.effective_at} UTC`}</span>
+                                    <span>{h.price} ج.م</span>
+                                    {h.scheduled ? (
+                                        <Button variant="destructive" size="sm" onClick={() => cancelScheduledPrice(h.id)}>
+                                            إلغاء
+                                        </Button>
+                                    ) : (
+                                        <span className="text-gray-400">سابق</span>
+                                    )}
+                                </div>
+                            ))}
+                        </div>
+                    )}
+
+                    {bundleFor && (
+                        <div className="mt-6 border border-gray-200 rounded-lg p-4 space-y-3">
+                            <div className="flex justify-between items-center">
+                                <p className="font-semibold">مكونات الوجبة: {bundleFor.name}</p>
+                                <Button variant="outline" size="sm" onClick={() => setBundleFor(null)}>
+                                    <X size={16} />
+                                </Button>
+                            </div>
+                            {bundleGroups.map((g, i) => (
+                                <div key={i} className="flex flex-wrap gap-2 items-start">
+                                    <Input
+                                        placeholder="اسم المجموعة (مثال: المشروب)"
+                                        value={g.name}
+                                        onChange={(e) => updateGroup(i, { ...g, name: e.target.value })}
+                                        className="w-48 border-gray-300"
+                                    />
+                                    <select
+                                        multiple
+                                        className="border border-gray-300 rounded-md px-2 h-24 w-64"
+                                        value={g.options.map((o) => String(o.product_id))}
+                                        onChange={(e) =>
+                                            updateGroup(i, {
+                                                ...g,
+                                                options: Array.from(e.target.selectedOptions).map((o) => ({
+                                                    product_id: Number(o.value),
+                                                    quantity:
+                                                        g.options.find((x) => x.product_id === Number(o.value))?.quantity ?? 1,
+                                                })),
+                                            })
+                                        }
+                                    >
+                                        {products
+                                            .filter((p) => p.id !== bundleFor.id && !p.archived)
+                                            .map((p) => (
+                                                <option key={p.id} value={p.id}>
+                                                    {p.name}
+                                                </option>
+                                            ))}
+                                    </select>
+                                    <Button
+                                        variant="destructive"
+                                        size="sm"
+                                        onClick={() => setBundleGroups(bundleGroups.filter((_, j) => j !== i))}
+                                    >
+                                        <Trash2 className="w-4 h-4" />
+                                    </Button>
+                                </div>
+                            ))}
+                            <div className="flex gap-2">
+                                <Button
+                                    variant="outline"
+                                    onClick={() => setBundleGroups([...bundleGroups, { name: "", options: [] }])}
+                                >
+                                    <PlusCircle className="w-4 h-4" /> مجموعة
+                                </Button>
+                                <Button onClick={saveBundle} className="bg-red-600 hover:bg-red-700 text-white">
+                                    <Check className="w-4 h-4" /> حفظ
+                                </Button>
+                            </div>
+                        </div>
+                    )}
+
+                    {variantsFor && (
+                        <div className="mt-6 border border-gray-200 rounded-lg p-4 space-y-3">
+                            <div className="flex justify-between items-center">
+                                <p className="font-semibold">الأنواع: {variantsFor.name}</p>
+                                <Button variant="outline" size="sm" onClick={() => setVariantsFor(null)}>
+                                    <X size={16} />
+                                </Button>
+                            </div>
+                            <div className="flex flex-wrap gap-2">
+                                <Input
+                                    placeholder="السمات (الحجم: كبير، الحليب: شوفان)"
+                                    value={newVariant.attributes}
+                                    onChange={(e) => setNewVariant({ ...newVariant, attributes: e.target.value })}
+                                    className="w-72 border-gray-300"
+                                />
+                                <Input
+                                    type="number"
+                                    min="0"
+                                    placeholder="السعر"
+                                    value={newVariant.price}
+                                    onChange={(e) => setNewVariant({ ...newVariant, price: e.target.value })}
+                                    className="w-28 border-gray-300"
+                                />
+                                <Input
+                                    placeholder="الباركود (اختياري)"
+                                    value={newVariant.barcode}
+                                    onChange={(e) => setNewVariant({ ...newVariant, barcode: e.target.value })}
+                                    className="w-48 border-gray-300"
+                                />
+                                <Button onClick={addVariant} className="bg-red-600 hover:bg-red-700 text-white">
+                                    <PlusCircle className="w-4 h-4" /> إضافة
+                                </Button>
+                            </div>
+                            {variants.map((v) => (
+                                <div key={v.product.id} className="flex flex-wrap gap-2 justify-between items-center text-sm">
+                                    <span>{v.attributes.map((a) => `${a.name}: ${a.value}`).join("، ")}</span>
+                                    <span>{v.product.price} ج.م</span>
+                                    <span dir="ltr">{v.product.barcode || "-"}</span>
+                                    <span>المخزون: {v.stock ?? "غير متتبع"}</span>
+                                    <div className="flex gap-1">
+                                        <Input
+                                            type="number"
+                                            placeholder="+/-"
+                                            value={stockChange[v.product.id] ?? ""}
+                                            onChange={(e) => setStockChange({ ...stockChange, [v.product.id]: e.target.value })}
+                                            className="w-20 border-gray-300"
+                                        />
+                                        <Button variant="outline" size="sm" onClick={() => adjustStock(v.product.id)}>
+                                            تعديل المخزون
+                                        </Button>
+                                        {v.stock !== null && (
+                                            <Button variant="outline" size="sm" onClick={() => stopTracking(v.product.id)}>
+                                                إيقاف التتبع
+                                            </Button>
+                                        )}
+                                    </div>
+                                </div>
+                            ))}
+                        </div>
+                    )}
+
+                    {barcodesFor && (
+                        <div className="mt-6 border border-gray-200 rounded-lg p-4 space-y-3">
+                            <div className="flex justify-between items-center">
+                                <p className="font-semibold">باركودات إضافية: {barcodesFor.name}</p>
+                                <Button variant="outline" size="sm" onClick={() => setBarcodesFor(null)}>
+                                    <X size={16} />
+                                </Button>
+                            </div>
+                            <div className="flex flex-wrap gap-2">
+                                <Input
+                                    placeholder="الباركود"
+                                    value={newBarcode.barcode}
+                                    onChange={(e) => setNewBarcode({ ...newBarcode, barcode: e.target.value })}
+                                    className="w-56 border-gray-300"
+                                />
+                                <Input
+                                    type="number"
+                                    min="1"
+                                    placeholder="عدد الوحدات في العبوة"
+                                    value={newBarcode.packQuantity}
+                                    onChange={(e) => setNewBarcode({ ...newBarcode, packQuantity: e.target.value })}
+                                    className="w-40 border-gray-300"
+                                />
+                                <Button onClick={addExtraBarcode} className="bg-red-600 hover:bg-red-700 text-white">
+                                    <PlusCircle className="w-4 h-4" /> إضافة
+                                </Button>
+                            </div>
+                            {extraBarcodes.map((b) => (
+                                <div key={b.id} className="flex justify-between items-center text-sm">
+                                    <span dir="ltr">{b.barcode}</span>
+                                    <span>× {b.pack_quantity}</span>
+                                    <Button variant="destructive" size="sm" onClick={() => deleteExtraBarcode(b.id)}>
+                                        <Trash2 className="w-4 h-4" />
+                                    </Button>
+                                </div>
+                            ))}
+                        </div>
+                    )}
                 </CardContent>
             </Card>
         </div>
@@ -0,0 +1,276 @@
+import { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { errorMessage } from "@/lib/errors";
+import { Button } from "@/components/ui/button";
+import { Input } from "@/components/ui/input";
+import { Card, CardContent, CardTitle } from "@/components/ui/card";
+import { Eye, FileDown, Printer, RotateCcw, Save } from "lucide-react";
+import toast from "react-hot-toast";
+
+type ReceiptTemplate = {
+    kind: "receipt" | "kitchen";
+    language: string;
+    rtl: boolean;
+    header: string;
+    body: string;
+    footer: string;
+};
+
+type Setting = { key: string; value: string };
+
+const STORE_KEYS = [
+    { key: "store_name", label: "اسم الفرع" },
+    { key: "store_address", label: "العنوان" },
+    { key: "store_tax_id", label: "الرقم الضريبي" },
+];
+
+export default function ReceiptTemplatesPage() {
+    const [templates, setTemplates] = useState<ReceiptTemplate[]>([]);
+    const [kind, setKind] = useState<"receipt" | "kitchen">("receipt");
+    const [language, setLanguage] = useState("ar");
+    const [draft, setDraft] = useState<ReceiptTemplate | null>(null);
+    const [store, setStore] = useState<Record<string, string>>({});
+    const [preview, setPreview] = useState("");
+    const [copySaleId, setCopySaleId] = useState("");
+    const [copyFormat, setCopyFormat] = useState<"pdf" | "html">("html");
+    const [copyPath, setCopyPath] = useState("");
+
+    useEffect(() => {
+        fetchTemplates();
+        fetchStore();
+    }, []);
+
+    useEffect(() => {
+        const current = templates.find((t) => t.kind === kind && t.language === language);
+        setDraft(current ? { ...current } : null);
+        setPreview("");
+    }, [templates, kind, language]);
+
+    const fetchTemplates = async () => {
+        try {
+            const result = await invoke<ReceiptTemplate[]>("get_receipt_templates_cmd");
+            setTemplates(result);
+        } catch (e) {
+            console.error("فشل في جلب القوالب:", e);
+            toast.error("فشل في جلب القوالب");
+        }
+    };
+
+    const fetchStore = async () => {
+        try {
+            const settings = await invoke<Setting[]>("get_settings_cmd");
+            setStore(Object.fromEntries(settings.map((s) => [s.key, s.value])));
+        } catch (e) {
+            console.error("فشل في جلب بيانات الفرع:", e);
+        }
+    };
+
+    const saveStore = async () => {
+        try {
+            for (const { key } of STORE_KEYS) {
+                await invoke("set_setting_cmd", { key, value: store[key] ?? "" });
+            }
+            toast.success("تم حفظ بيانات الفرع");
+        } catch (e) {
+            console.error("فشل في حفظ بيانات الفرع:", e);
+            toast.error("فشل في حفظ بيانات الفرع");
+        }
+    };
+
+    const saveTemplate = async () => {
+        if (!draft) return;
+        try {
+            await invoke("save_receipt_template_cmd", { template: draft });
+            await fetchTemplates();
+            toast.success("تم حفظ القالب");
+        } catch (e) {
+            console.error("فشل في حفظ القالب:", e);
+            toast.error("فشل في حفظ القالب");
+        }
+    };
+
+    const resetTemplate = async () => {
+        try {
+            await invoke<ReceiptTemplate>("reset_receipt_template_cmd", { kind, language });
+            await fetchTemplates();
+            toast.success("تمت استعادة القالب الافتراضي");
+        } catch (e) {
+            console.error("فشل في استعادة القالب:", e);
+            toast.error("فشل في استعادة القالب");
+        }
+    };
+
+    const previewTemplate = async () => {
+        if (!draft) return;
+        try {
+            const text = await invoke<string>("preview_template_cmd", { template: draft });
+            setPreview(text);
+        } catch (e) {
+            console.error("فشل في المعاينة:", e);
+            toast.error(errorMessage(e));
+        }
+    };
+
+    const reprint = async () => {
+        try {
+            await invoke("reprint_receipt_cmd", { saleId: Number(copySaleId), language });
+            toast.success("تمت إعادة الطباعة");
+        } catch (e) {
+            console.error("فشل في إعادة الطباعة:", e);
+            toast.error(errorMessage(e));
+        }
+    };
+
+    const exportCopy = async () => {
+        try {
+            await invoke("export_receipt_cmd", {
+                saleId: Number(copySaleId),
+                format: copyFormat,
+                path: copyPath,
+                language,
+            });
+            toast.success("تم حفظ نسخة الإيصال");
+        } catch (e) {
+            console.error("فشل في حفظ نسخة الإيصال:", e);
+            toast.error(errorMessage(e));
+        }
+    };
+
+    const field = (name: "header" | "body" | "footer", label: string, rows: number) => (
+        <div className="space-y-1">
+            <label className="text-sm font-medium text-gray-700">{label}</label>
+            <textarea
+                dir={draft?.rtl ? "rtl" : "ltr"}
+                rows={rows}
+                value={draft?.[name] ?? ""}
+                onChange={(e) => draft && setDraft({ ...draft, [name]: e.target.value })}
+                className="w-full font-mono text-sm border border-red-300 rounded-lg p-2 focus:ring-2 focus:ring-red-500"
+            />
+        </div>
+    );
+
+    return (
+        <div className="p-6 max-w-5xl mx-auto space-y-6">
+            <Card className="shadow-xl border border-red-200 rounded-2xl">
+                <CardContent className="p-6 space-y-4">
+                    <CardTitle className="text-2xl font-bold text-red-700">بيانات الفرع</CardTitle>
+                    <div className="grid grid-cols-1 sm:grid-cols-3 gap-3">
+                        {STORE_KEYS.map(({ key, label }) => (
+                            <Input
+                                key={key}
+                                placeholder={label}
+                                value={store[key] ?? ""}
+                                onChange={(e) => setStore({ ...store, [key]: e.target.value })}
+                                className="border-red-300 focus:ring-2 focus:ring-red-500"
+                            />
+                        ))}
+                    </div>
+                    <Button onClick={saveStore} className="bg-red-600 hover:bg-red-700 text-white">
+                        <Save size={18} /> حفظ
+                    </Button>
+                </CardContent>
+            </Card>
+
+            <Card className="shadow-xl border border-red-200 rounded-2xl">
+                <CardContent className="p-6 space-y-4">
+                    <CardTitle className="text-2xl font-bold text-red-700">قوالب الإيصالات</CardTitle>
+
+                    <div className="flex flex-wrap gap-3 items-center">
+                        <select
+                            value={kind}
+                            onChange={(e) => setKind(e.target.value as "receipt" | "kitchen")}
+                            className="border border-red-300 rounded-lg px-3 py-2"
+                        >
+                            <option value="receipt">إيصال العميل</option>
+                            <option value="kitchen">تذكرة المطبخ</option>
+                        </select>
+                        <select
+                            value={language}
+                            onChange={(e) => setLanguage(e.target.value)}
+                            className="border border-red-300 rounded-lg px-3 py-2"
+                        >
+                            <option value="ar">العربية</option>
+                            <option value="en">English</option>
+                        </select>
+                        <label className="flex items-center gap-2 text-sm text-gray-700">
+                            <input
+                                type="checkbox"
+                                checked={draft?.rtl ?? false}
+                                onChange={(e) => draft && setDraft({ ...draft, rtl: e.target.checked })}
+                            />
+                            من اليمين إلى اليسار
+                        </label>
+                    </div>
+
+                    {field("header", "الترويسة", 6)}
+                    {field("body", "المحتوى", 14)}
+                    {field("footer", "التذييل", 4)}
+
+                    <p className="text-xs text-gray-500" dir="ltr">
+                        {"{{store.name}} {{sale.number}} {{sale.total}} {{#items}}…{{/items}} {{item.name}} {{#payments}}…{{/payments}} | ^ > * ! --- [logo] [code] [cut]"}
+                    </p>
+
+                    <div className="flex gap-3">
+                        <Button onClick={saveTemplate} className="bg-red-600 hover:bg-red-700 text-white">
+                            <Save size={18} /> حفظ القالب
+                        </Button>
+                        <Button onClick={previewTemplate} variant="outline">
+                            <Eye size={18} /> معاينة
+                        </Button>
+                        <Button onClick={resetTemplate} variant="outline">
+                            <RotateCcw size={18} /> استعادة الافتراضي
+                        </Button>
+                    </div>
+
+                    {preview && (
+                        <pre dir="ltr" className="bg-gray-100 rounded-lg p-4 text-sm font-mono overflow-x-auto">
+                            {preview}
+                        </pre>
+                    )}
+                </CardContent>
+            </Card>
+
+            <Card className="shadow-xl border border-red-200 rounded-2xl">
+                <CardContent className="p-6 space-y-4">
+                    <CardTitle className="text-2xl font-bold text-red-700">نسخة من إيصال سابق</CardTitle>
+                    <div className="grid grid-cols-1 sm:grid-cols-3 gap-3">
+                        <Input
+                            type="number"
+                            placeholder="رقم العملية"
+                            value={copySaleId}
+                            onChange={(e) => setCopySaleId(e.target.value)}
+                            className="border-red-300 focus:ring-2 focus:ring-red-500"
+                        />
+                        <select
+                            value={copyFormat}
+                            onChange={(e) => setCopyFormat(e.target.value as "pdf" | "html")}
+                            className="border border-red-300 rounded-lg px-3 py-2"
+                        >
+                            <option value="html">HTML</option>
+                            <option value="pdf">PDF</option>
+                        </select>
+                        <Input
+                            dir="ltr"
+                            placeholder="مسار الحفظ"
+                            value={copyPath}
+                            onChange={(e) => setCopyPath(e.target.value)}
+                            className="border-red-300 focus:ring-2 focus:ring-red-500"
+                        />
+                    </div>
+                    <div className="flex gap-3">
+                        <Button onClick={reprint} disabled={!copySaleId} variant="outline">
+                            <Printer size={18} /> إعادة طباعة
+                        </Button>
+                        <Button
+                            onClick={exportCopy}
+                            disabled={!copySaleId || !copyPath}
+                            className="bg-red-600 hover:bg-red-700 text-white"
+                        >
+                            <FileDown size={18} /> حفظ كملف
+                        </Button>
+                    </div>
+                </CardContent>
+            </Card>
+        </div>
+    );
+}
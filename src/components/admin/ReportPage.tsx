@@ -1,5 +1,6 @@
 import { useEffect, useState } from "react";
 import { invoke } from "@tauri-apps/api/core";
+import { useAuth } from "@/context/AuthContext";
 import { Card, CardContent, CardTitle } from "@/components/ui/card";
 import { Button } from "@/components/ui/button";
 import { Input } from "@/components/ui/input";
@@ -28,22 +29,132 @@ type SalesReport = {
     sales: SaleDetail[];
 };
 
+type EmployeePerformance = {
+    employee_id: number;
+    employee_name: string;
+    net_sales: number;
+    transactions: number;
+    average_ticket: number;
+    items_per_sale: number;
+    void_count: number;
+    voids: number;
+    refund_count: number;
+    refunds: number;
+    discount_count: number;
+    discounts: number;
+    tips: number;
+};
+
+type ProductSales = {
+    product_id: number | null;
+    name: string;
+    quantity: number;
+    total: number;
+};
+
+type PriceChange = {
+    product_id: number;
+    name: string;
+    old_price: number;
+    new_price: number;
+    effective_at: string;
+};
+
+type TipPayout = {
+    employee_id: number;
+    employee_name: string;
+    collected: number;
+    hours: number;
+    payout: number;
+};
+
+type TipPayoutReport = {
+    mode: "none" | "equal" | "hours";
+    pool_percent: number;
+    total_tips: number;
+    pool: number;
+    payouts: TipPayout[];
+};
+
+type ServiceChargeRule = {
+    id: number;
+    name: string;
+    min_guests: number;
+    percent: number;
+    active: boolean;
+};
+
 export default function ReportPage() {
+    const { session } = useAuth();
     const [sales, setSales] = useState<SaleDetail[]>([]);
     const [totalSales, setTotalSales] = useState<number>(0);
     const [totalTransactions, setTotalTransactions] = useState<number>(0);
     const [startDate, setStartDate] = useState<string>("");
     const [endDate, setEndDate] = useState<string>("");
+    const [productSales, setProductSales] = useState<ProductSales[]>([]);
+    const [breakdown, setBreakdown] = useState<"product" | "variant" | "component">("product");
+    const [priceChanges, setPriceChanges] = useState<PriceChange[]>([]);
+    const [performance, setPerformance] = useState<EmployeePerformance[]>([]);
+    const [tipPayouts, setTipPayouts] = useState<TipPayoutReport | null>(null);
+    const [rules, setRules] = useState<ServiceChargeRule[]>([]);
+    const [rule, setRule] = useState({ name: "", minGuests: "", percent: "" });
 
     useEffect(() => {
         applyPreset("daily");
+        fetchRules();
     }, []);
 
+    const fetchRules = async () => {
+        try {
+            setRules(await invoke<ServiceChargeRule[]>("get_service_charge_rules_cmd"));
+        } catch (err) {
+            console.error("فشل في جلب رسوم الخدمة:", err);
+        }
+    };
+
+    const addRule = async () => {
+        if (!rule.name || !rule.minGuests || !rule.percent) {
+            toast.error("يرجى إدخال الاسم وعدد الأشخاص والنسبة");
+            return;
+        }
+        try {
+            await invoke("add_service_charge_rule_cmd", {
+                name: rule.name,
+                minGuests: Number(rule.minGuests),
+                percent: Number(rule.percent),
+            });
+            setRule({ name: "", minGuests: "", percent: "" });
+            await fetchRules();
+        } catch (err) {
+            console.error("فشل في إضافة رسوم الخدمة:", err);
+            toast.error("فشل في إضافة رسوم الخدمة");
+        }
+    };
+
+    const toggleRule = async (r: ServiceChargeRule) => {
+        try {
+            await invoke("set_service_charge_rule_active_cmd", { id: r.id, active: !r.active });
+            await fetchRules();
+        } catch (err) {
+            console.error("فشل في تعديل رسوم الخدمة:", err);
+        }
+    };
+
+    const deleteRule = async (id: number) => {
+        try {
+            await invoke("delete_service_charge_rule_cmd", { id });
+            await fetchRules();
+        } catch (err) {
+            console.error("فشل في حذف رسوم الخدمة:", err);
+        }
+    };
+
     const fetchReport = async (start?: string, end?: string) => {
         try {
             const result = await invoke<SalesReport>("get_report_cmd", {
                 startDate: start || startDate || null,
                 endDate: end || endDate || null,
+                session,
             });
 
             if (result) {
@@ -51,12 +162,40 @@ export default function ReportPage() {
                 setTotalSales(result.total_sales || 0);
                 setTotalTransactions(result.total_transactions || 0);
             }
+
+            const range = {
+                startDate: start || startDate || null,
+                endDate: end || endDate || null,
+                session,
+            };
+            setProductSales(await fetchProductSales(range, breakdown));
+            setPriceChanges(await invoke<PriceChange[]>("get_price_changes_cmd", range));
+            setPerformance(await invoke<EmployeePerformance[]>("get_employee_performance_cmd", range));
+            setTipPayouts(await invoke<TipPayoutReport>("get_tip_payouts_cmd", range));
         } catch (err) {
             console.error("فشل في جلب التقرير:", err);
             toast.error("فشل في جلب التقرير");
         }
     };
 
+    // المنتجات مجمعة تحت المنتج الأصلي، أو مفصلة حسب النوع، أو بتفكيك الوجبات إلى مكوناتها
+    const fetchProductSales = (
+        range: { startDate: string | null; endDate: string | null; session: string | null },
+        by: "product" | "variant" | "component"
+    ) =>
+        by === "component"
+            ? invoke<ProductSales[]>("get_component_sales_cmd", range)
+            : invoke<ProductSales[]>("get_product_sales_cmd", { ...range, byVariant: by === "variant" });
+
+    const changeBreakdown = async (by: "product" | "variant" | "component") => {
+        setBreakdown(by);
+        try {
+            setProductSales(await fetchProductSales({ startDate: startDate || null, endDate: endDate || null, session }, by));
+        } catch (err) {
+            console.error("فشل في جلب مبيعات المنتجات:", err);
+        }
+    };
+
     const formatCairoDateTime = (utcLike: string) => {
         try {
             const iso =
@@ -231,6 +370,191 @@ export default function ReportPage() {
                             </Table>
                         </ScrollArea>
                     </div>
+
+                    {/* Employee performance */}
+                    <div className="flex justify-between items-center mt-8 mb-3">
+                        <h3 className="text-xl font-bold text-red-900">مبيعات المنتجات</h3>
+                        <select
+                            className="border rounded-md px-2 py-1 text-sm"
+                            value={breakdown}
+                            onChange={(e) => changeBreakdown(e.target.value as "product" | "variant" | "component")}
+                        >
+                            <option value="product">حسب المنتج</option>
+                            <option value="variant">حسب النوع</option>
+                            <option value="component">مكونات الوجبات</option>
+                        </select>
+                    </div>
+                    <div className="overflow-x-auto">
+                        <Table className="border rounded-md">
+                            <TableHeader>
+                                <TableRow className="bg-red-100 hover:bg-red-100">
+                                    <TableHead>المنتج</TableHead>
+                                    <TableHead>الكمية</TableHead>
+                                    <TableHead>الإجمالي</TableHead>
+                                </TableRow>
+                            </TableHeader>
+                            <TableBody>
+                                {productSales.map((p, index) => (
+                                    <TableRow key={p.product_id ?? `deleted-${index}`}>
+                                        <TableCell>{p.name}</TableCell>
+                                        <TableCell>{p.quantity}</TableCell>
+                                        <TableCell>{p.total.toFixed(2)} ج.م</TableCell>
+                                    </TableRow>
+                                ))}
+                            </TableBody>
+                        </Table>
+                    </div>
+
+                    {priceChanges.length > 0 && (
+                        <>
+                            <h3 className="text-xl font-bold text-red-900 mt-8 mb-3">تغييرات الأسعار في الفترة</h3>
+                            <div className="overflow-x-auto">
+                                <Table className="border rounded-md">
+                                    <TableHeader>
+                                        <TableRow className="bg-red-100 hover:bg-red-100">
+                                            <TableHead>المنتج</TableHead>
+                                            <TableHead>السعر السابق</TableHead>
+                                            <TableHead>السعر الجديد</TableHead>
+                                            <TableHead>من تاريخ</TableHead>
+                                        </TableRow>
+                                    </TableHeader>
+                                    <TableBody>
+                                        {priceChanges.map((c, index) => (
+                                            <TableRow key={index}>
+                                                <TableCell>{c.name}</TableCell>
+                                                <TableCell>{c.old_price.toFixed(2)}</TableCell>
+                                                <TableCell>{c.new_price.toFixed(2)}</TableCell>
+                                                <TableCell>{formatCairoDateTime(c.effective_at)}</TableCell>
+                                            </TableRow>
+                                        ))}
+                                    </TableBody>
+                                </Table>
+                            </div>
+                        </>
+                    )}
+
+                    <h3 className="text-xl font-bold text-red-900 mt-8 mb-3">أداء الموظفين</h3>
+                    <div className="overflow-x-auto">
+                        <Table className="border rounded-md">
+                            <TableHeader>
+                                <TableRow className="bg-red-100 hover:bg-red-100">
+                                    <TableHead>الموظف</TableHead>
+                                    <TableHead>صافي المبيعات</TableHead>
+                                    <TableHead>العمليات</TableHead>
+                                    <TableHead>متوسط الفاتورة</TableHead>
+                                    <TableHead>أصناف/عملية</TableHead>
+                                    <TableHead>الإلغاءات</TableHead>
+                                    <TableHead>المرتجعات</TableHead>
+                                    <TableHead>الخصومات</TableHead>
+                                    <TableHead>الإكراميات</TableHead>
+                                </TableRow>
+                            </TableHeader>
+                            <TableBody>
+                                {performance.map((p) => (
+                                    <TableRow key={p.employee_id}>
+                                        <TableCell className="capitalize">{p.employee_name}</TableCell>
+                                        <TableCell>{p.net_sales.toFixed(2)} ج.م</TableCell>
+                                        <TableCell>{p.transactions}</TableCell>
+                                        <TableCell>{p.average_ticket.toFixed(2)}</TableCell>
+                                        <TableCell>{p.items_per_sale.toFixed(1)}</TableCell>
+                                        <TableCell>{p.void_count} ({p.voids.toFixed(2)})</TableCell>
+                                        <TableCell>{p.refund_count} ({p.refunds.toFixed(2)})</TableCell>
+                                        <TableCell>{p.discount_count} ({p.discounts.toFixed(2)})</TableCell>
+                                        <TableCell>{p.tips.toFixed(2)}</TableCell>
+                                    </TableRow>
+                                ))}
+                            </TableBody>
+                        </Table>
+                    </div>
+
+                    {/* Tip payouts */}
+                    {tipPayouts && (
+                        <>
+                            <h3 className="text-xl font-bold text-red-900 mt-8 mb-3">
+                                توزيع الإكراميات
+                                <span className="text-sm font-normal text-gray-600 mr-3">
+                                    {tipPayouts.mode === "none"
+                                        ? "بدون تجميع"
+                                        : `تجميع ${tipPayouts.pool_percent}% — ${tipPayouts.mode === "hours" ? "حسب الساعات" : "بالتساوي"}`}
+                                </span>
+                            </h3>
+                            <Table className="border rounded-md">
+                                <TableHeader>
+                                    <TableRow className="bg-red-100 hover:bg-red-100">
+                                        <TableHead>الموظف</TableHead>
+                                        <TableHead>المحصّل</TableHead>
+                                        <TableHead>الساعات</TableHead>
+                                        <TableHead>المستحق</TableHead>
+                                    </TableRow>
+                                </TableHeader>
+                                <TableBody>
+                                    {tipPayouts.payouts.map((p) => (
+                                        <TableRow key={p.employee_id}>
+                                            <TableCell className="capitalize">{p.employee_name}</TableCell>
+                                            <TableCell>{p.collected.toFixed(2)}</TableCell>
+                                            <TableCell>{p.hours.toFixed(1)}</TableCell>
+                                            <TableCell className="font-semibold">{p.payout.toFixed(2)} ج.م</TableCell>
+                                        </TableRow>
+                                    ))}
+                                </TableBody>
+                            </Table>
+                        </>
+                    )}
+
+                    {/* Service charge rules */}
+                    <h3 className="text-xl font-bold text-red-900 mt-8 mb-3">رسوم الخدمة</h3>
+                    <div className="flex flex-wrap gap-3 mb-3">
+                        <Input
+                            placeholder="الاسم"
+                            value={rule.name}
+                            onChange={(e) => setRule({ ...rule, name: e.target.value })}
+                            className="w-40"
+                        />
+                        <Input
+                            type="number"
+                            placeholder="من عدد أشخاص"
+                            value={rule.minGuests}
+                            onChange={(e) => setRule({ ...rule, minGuests: e.target.value })}
+                            className="w-36"
+                        />
+                        <Input
+                            type="number"
+                            placeholder="النسبة %"
+                            value={rule.percent}
+                            onChange={(e) => setRule({ ...rule, percent: e.target.value })}
+                            className="w-28"
+                        />
+                        <Button onClick={addRule} className="bg-red-600 hover:bg-red-700 text-white">
+                            إضافة
+                        </Button>
+                    </div>
+                    <Table className="border rounded-md">
+                        <TableHeader>
+                            <TableRow className="bg-red-100 hover:bg-red-100">
+                                <TableHead>الاسم</TableHead>
+                                <TableHead>من عدد أشخاص</TableHead>
+                                <TableHead>النسبة</TableHead>
+                                <TableHead />
+                            </TableRow>
+                        </TableHeader>
+                        <TableBody>
+                            {rules.map((r) => (
+                                <TableRow key={r.id} className={r.active ? "" : "text-gray-400"}>
+                                    <TableCell>{r.name}</TableCell>
+                                    <TableCell>{r.min_guests}+</TableCell>
+                                    <TableCell>{r.percent}%</TableCell>
+                                    <TableCell className="flex gap-2 justify-end">
+                                        <Button variant="outline" onClick={() => toggleRule(r)}>
+                                            {r.active ? "إيقاف" : "تفعيل"}
+                                        </Button>
+                                        <Button variant="destructive" onClick={() => deleteRule(r.id)}>
+                                            حذف
+                                        </Button>
+                                    </TableCell>
+                                </TableRow>
+                            ))}
+                        </TableBody>
+                    </Table>
                 </CardContent>
             </Card>
         </div>
@@ -0,0 +1,241 @@
+import { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
+import { useAuth } from "@/context/AuthContext";
+import { errorMessage } from "@/lib/errors";
+import { Button } from "@/components/ui/button";
+import { Input } from "@/components/ui/input";
+import {
+    Table,
+    TableBody,
+    TableCell,
+    TableHead,
+    TableHeader,
+    TableRow
+} from "@/components/ui/table";
+import { Card, CardContent, CardTitle } from "@/components/ui/card";
+import { FileDown, PlusCircle, Trash2 } from "lucide-react";
+import toast from "react-hot-toast";
+
+type TimesheetDay = { date: string; hours: number; regular: number; overtime: number };
+
+type Timesheet = {
+    employee_id: number;
+    employee_name: string;
+    days: TimesheetDay[];
+    hours: number;
+    regular: number;
+    overtime: number;
+    scheduled: number;
+};
+
+type Shift = {
+    id: number;
+    employee_id: number;
+    employee_name: string;
+    starts_at: string;
+    ends_at: string;
+    note: string;
+};
+
+type Employee = { id: number; name: string };
+
+const today = () => new Date().toISOString().split("T")[0];
+const monthStart = () => {
+    const d = new Date();
+    return new Date(d.getFullYear(), d.getMonth(), 1).toISOString().split("T")[0];
+};
+const toDb = (local: string) => (local ? local.replace("T", " ") + ":00" : "");
+
+export default function TimesheetsPage() {
+    const { session } = useAuth();
+    const [startDate, setStartDate] = useState(monthStart());
+    const [endDate, setEndDate] = useState(today());
+    const [timesheets, setTimesheets] = useState<Timesheet[]>([]);
+    const [shifts, setShifts] = useState<Shift[]>([]);
+    const [employees, setEmployees] = useState<Employee[]>([]);
+    const [shift, setShift] = useState({ employeeId: "", startsAt: "", endsAt: "", note: "" });
+    const [exportPath, setExportPath] = useState("");
+
+    useEffect(() => {
+        invoke<Employee[]>("fetch_employees").then(setEmployees).catch(console.error);
+        fetchData();
+    }, []);
+
+    const fetchData = async () => {
+        try {
+            setTimesheets(await invoke<Timesheet[]>("get_timesheets_cmd", { startDate, endDate, session }));
+            setShifts(await invoke<Shift[]>("get_shifts_cmd", { startDate, endDate }));
+        } catch (e) {
+            console.error("فشل في جلب سجلات الدوام:", e);
+            toast.error("فشل في جلب سجلات الدوام");
+        }
+    };
+
+    const addShift = async () => {
+        if (!shift.employeeId || !shift.startsAt || !shift.endsAt) {
+            toast.error("يرجى اختيار الموظف ووقت البداية والنهاية");
+            return;
+        }
+        try {
+            await invoke("add_shift_cmd", {
+                employeeId: Number(shift.employeeId),
+                startsAt: toDb(shift.startsAt),
+                endsAt: toDb(shift.endsAt),
+                note: shift.note,
+                session,
+            });
+            setShift({ employeeId: "", startsAt: "", endsAt: "", note: "" });
+            await fetchData();
+            toast.success("تمت إضافة الوردية");
+        } catch (e) {
+            console.error("فشل في إضافة الوردية:", e);
+            toast.error(errorMessage(e, "فشل في إضافة الوردية"));
+        }
+    };
+
+    const removeShift = async (id: number) => {
+        try {
+            await invoke("delete_shift_cmd", { id, session });
+            await fetchData();
+        } catch (e) {
+            console.error("فشل في حذف الوردية:", e);
+            toast.error(errorMessage(e, "فشل في حذف الوردية"));
+        }
+    };
+
+    const exportCsv = async () => {
+        try {
+            await invoke("export_timesheets_cmd", { startDate, endDate, path: exportPath, session });
+            toast.success("تم تصدير سجل الدوام");
+        } catch (e) {
+            console.error("فشل في التصدير:", e);
+            toast.error(errorMessage(e));
+        }
+    };
+
+    return (
+        <div className="p-6 max-w-5xl mx-auto space-y-6">
+            <Card className="shadow-xl border border-red-200 rounded-2xl">
+                <CardContent className="p-6 space-y-4">
+                    <CardTitle className="text-3xl font-bold text-red-700">سجل الدوام</CardTitle>
+
+                    <div className="flex flex-wrap gap-3 items-center">
+                        <Input type="date" value={startDate} onChange={(e) => setStartDate(e.target.value)} className="w-44" />
+                        <Input type="date" value={endDate} onChange={(e) => setEndDate(e.target.value)} className="w-44" />
+                        <Button onClick={fetchData} className="bg-red-600 hover:bg-red-700 text-white">
+                            عرض
+                        </Button>
+                        <Input
+                            dir="ltr"
+                            placeholder="timesheet.csv"
+                            value={exportPath}
+                            onChange={(e) => setExportPath(e.target.value)}
+                            className="flex-1 min-w-48"
+                        />
+                        <Button onClick={exportCsv} disabled={!exportPath} variant="outline">
+                            <FileDown size={18} /> تصدير CSV
+                        </Button>
+                    </div>
+
+                    <Table className="border border-red-100 rounded-lg">
+                        <TableHeader>
+                            <TableRow className="bg-red-50 hover:bg-red-50">
+                                <TableHead>الموظف</TableHead>
+                                <TableHead>الأيام</TableHead>
+                                <TableHead>الساعات</TableHead>
+                                <TableHead>عادية</TableHead>
+                                <TableHead>إضافية</TableHead>
+                                <TableHead>المجدولة</TableHead>
+                            </TableRow>
+                        </TableHeader>
+                        <TableBody>
+                            {timesheets.map((t) => (
+                                <TableRow key={t.employee_id}>
+                                    <TableCell className="font-medium">{t.employee_name}</TableCell>
+                                    <TableCell>{t.days.length}</TableCell>
+                                    <TableCell>{t.hours.toFixed(2)}</TableCell>
+                                    <TableCell>{t.regular.toFixed(2)}</TableCell>
+                                    <TableCell className={t.overtime > 0 ? "text-red-600 font-semibold" : ""}>
+                                        {t.overtime.toFixed(2)}
+                                    </TableCell>
+                                    <TableCell>{t.scheduled.toFixed(2)}</TableCell>
+                                </TableRow>
+                            ))}
+                            {timesheets.length === 0 && (
+                                <TableRow>
+                                    <TableCell colSpan={6} className="text-center text-gray-500 p-4">
+                                        لا توجد سجلات في هذه الفترة.
+                                    </TableCell>
+                                </TableRow>
+                            )}
+                        </TableBody>
+                    </Table>
+                </CardContent>
+            </Card>
+
+            <Card className="shadow-xl border border-red-200 rounded-2xl">
+                <CardContent className="p-6 space-y-4">
+                    <CardTitle className="text-2xl font-bold text-red-700">جدول الورديات</CardTitle>
+                    <div className="grid grid-cols-1 sm:grid-cols-5 gap-3">
+                        <select
+                            value={shift.employeeId}
+                            onChange={(e) => setShift({ ...shift, employeeId: e.target.value })}
+                            className="border border-red-300 rounded-lg px-3 py-2"
+                        >
+                            <option value="">الموظف</option>
+                            {employees.map((e) => (
+                                <option key={e.id} value={e.id}>
+                                    {e.name}
+                                </option>
+                            ))}
+                        </select>
+                        <Input
+                            type="datetime-local"
+                            value={shift.startsAt}
+                            onChange={(e) => setShift({ ...shift, startsAt: e.target.value })}
+                        />
+                        <Input
+                            type="datetime-local"
+                            value={shift.endsAt}
+                            onChange={(e) => setShift({ ...shift, endsAt: e.target.value })}
+                        />
+                        <Input
+                            placeholder="ملاحظة"
+                            value={shift.note}
+                            onChange={(e) => setShift({ ...shift, note: e.target.value })}
+                        />
+                        <Button onClick={addShift} className="bg-red-600 hover:bg-red-700 text-white">
+                            <PlusCircle size={18} /> إضافة
+                        </Button>
+                    </div>
+                    <Table className="border border-red-100 rounded-lg">
+                        <TableHeader>
+                            <TableRow className="bg-red-50 hover:bg-red-50">
+                                <TableHead>الموظف</TableHead>
+                                <TableHead>من</TableHead>
+                                <TableHead>إلى</TableHead>
+                                <TableHead>ملاحظة</TableHead>
+                                <TableHead />
+                            </TableRow>
+                        </TableHeader>
+                        <TableBody>
+                            {shifts.map((s) => (
+                                <TableRow key={s.id}>
+                                    <TableCell>{s.employee_name}</TableCell>
+                                    <TableCell dir="ltr">{s.starts_at}</TableCell>
+                                    <TableCell dir="ltr">{s.ends_at}</TableCell>
+                                    <TableCell>{s.note}</TableCell>
+                                    <TableCell className="text-right">
+                                        <Button variant="destructive" onClick={() => removeShift(s.id)} className="px-3 rounded">
+                                            <Trash2 className="w-4 h-4" />
+                                        </Button>
+                                    </TableCell>
+                                </TableRow>
+                            ))}
+                        </TableBody>
+                    </Table>
+                </CardContent>
+            </Card>
+        </div>
+    );
+}
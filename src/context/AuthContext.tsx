@@ -1,9 +1,12 @@
-import { createContext, useContext, useState, useEffect, ReactNode } from "react";
+import { createContext, useContext, useState, ReactNode } from "react";
+import { invoke } from "@tauri-apps/api/core";
 
 interface AuthState {
   role: "admin" | "employee" | null;
   employeeId: number | null;
   employeeName: string | null;
+  // Token from the login command, passed to commands that check permissions
+  session: string | null;
 }
 
 interface AuthContextType extends AuthState {
@@ -11,23 +14,29 @@ interface AuthContextType extends AuthState {
   logout: () => void;
 }
 
+const signedOut: AuthState = {
+  role: null,
+  employeeId: null,
+  employeeName: null,
+  session: null,
+};
+
 const AuthContext = createContext<AuthContextType | undefined>(undefined);
 
 export function AuthProvider({ children }: { children: ReactNode }) {
-  const [auth, setAuth] = useState<AuthState>({
-    role: null,
-    employeeId: null,
-    employeeName: null,
+  // Restored before the first render, so pages loading data on mount
+  // already have the session
+  const [auth, setAuth] = useState<AuthState>(() => {
+    const savedAuth = localStorage.getItem("auth");
+    return savedAuth ? { ...signedOut, ...JSON.parse(savedAuth) } : signedOut;
   });
 
-  useEffect(() => {
-    const savedAuth = localStorage.getItem("auth");
-    if (savedAuth) {
-      setAuth(JSON.parse(savedAuth));
-    }
-  }, []);
+  const endSession = (session: string | null) => {
+    if (session) invoke("logout_cmd", { session }).catch(() => {});
+  };
 
   const login = (data: Partial<AuthState>) => {
+    if (data.session && data.session !== auth.session) endSession(auth.session);
     setAuth((prev) => {
       const newAuth = { ...prev, ...data };
       localStorage.setItem("auth", JSON.stringify(newAuth));
@@ -36,12 +45,8 @@ export function AuthProvider({ children }: { children: ReactNode }) {
   };
 
   const logout = () => {
-    const resetAuth: AuthState = {
-      role: null,
-      employeeId: null,
-      employeeName: null,
-    };
-    setAuth(resetAuth);
+    endSession(auth.session);
+    setAuth(signedOut);
     localStorage.removeItem("auth");
   };
 
@@ -0,0 +1,110 @@
+// Errors returned by backend commands (see src-tauri/src/error.rs)
+export type AppErrorKind = "validation" | "not_found" | "conflict" | "unauthorized" | "database" | "io";
+
+export interface AppError {
+    kind: AppErrorKind;
+    code: string;
+    field: string | null;
+    message: string;
+}
+
+export function isAppError(e: unknown, kind?: AppErrorKind): e is AppError {
+    const ok = typeof e === "object" && e !== null && "kind" in e && "code" in e;
+    return ok && (!kind || (e as AppError).kind === kind);
+}
+
+const FIELD_LABELS: Record<string, string> = {
+    name: "الاسم",
+    price: "السعر",
+    barcode: "الباركود",
+    password: "كلمة المرور",
+    pin: "الرمز السري",
+    quantity: "الكمية",
+    extra_amount: "الإضافات",
+    discount: "الخصم",
+    amount: "المبلغ",
+    percent: "النسبة",
+    guests: "عدد الضيوف",
+    opening_float: "رصيد البداية",
+    counted_cash: "النقد المعدود",
+    method: "طريقة الدفع",
+    notes: "الملاحظات",
+    note: "الملاحظة",
+    reason: "السبب",
+    phone: "رقم الهاتف",
+    email: "البريد الإلكتروني",
+    code: "الرمز",
+    printer_target: "الطابعة",
+    label_printer_target: "طابعة الملصقات",
+    copies: "عدد النسخ",
+    net_content: "المحتوى الصافي",
+    net_content_unit: "وحدة المحتوى",
+    product: "المنتج",
+    unit: "الوحدة",
+    scale_port: "الميزان",
+    attributes: "السمات",
+    attribute: "السمة",
+    value: "قيمة السمة",
+    parent_id: "المنتج الأصلي",
+    options: "خيارات المجموعة",
+    choices: "اختيارات الوجبة",
+    effective_at: "تاريخ السريان",
+    category: "التصنيف",
+    days: "الأيام",
+    start_time: "وقت البداية",
+    end_time: "وقت النهاية",
+    start_date: "تاريخ البداية",
+    end_date: "تاريخ النهاية",
+    clock_in: "وقت الحضور",
+    clock_out: "وقت الانصراف",
+    starts_at: "بداية الوردية",
+    ends_at: "نهاية الوردية",
+    format: "الصيغة",
+    doc_type: "نوع المستند",
+    prefix: "بادئة الترقيم",
+    reset_period: "فترة إعادة الترقيم",
+    padding: "عدد خانات الرقم",
+    sale: "الفاتورة",
+    points: "النقاط",
+    gift_card: "بطاقة الهدية",
+    expires_at: "تاريخ الانتهاء",
+    record: "السجل",
+};
+
+// Arabic text for an error thrown by `invoke`, or `fallback` if it isn't one of ours
+export function errorMessage(e: unknown, fallback = "حدث خطأ غير متوقع"): string {
+    if (!isAppError(e)) return fallback;
+    const field = (e.field && FIELD_LABELS[e.field]) || e.field || "";
+    switch (e.kind) {
+        case "validation":
+            if (e.code === "required") return `${field} مطلوب`;
+            if (e.code === "too_short") return `${field} قصير جداً`;
+            if (e.code === "too_long") return `${field} طويل جداً`;
+            if (e.code === "out_of_range") return `${field} خارج الحدود المسموحة`;
+            if (e.code === "check_digit") return "رقم التحقق في الباركود غير صحيح";
+            if (e.code === "rtl_unsupported") return "لا يمكن حفظ الإيصالات العربية بصيغة PDF، استخدم HTML";
+            if (e.code === "precision") return "عدد الخانات العشرية في الكمية أكبر من المسموح للوحدة";
+            return field ? `قيمة ${field} غير صالحة` : fallback;
+        case "not_found":
+            return field ? `${field} غير موجود` : "العنصر غير موجود";
+        case "conflict":
+            if (e.code === "exhausted") return "لم تعد هناك أرقام باركود متاحة في النطاق المحدد";
+            if (e.code === "unavailable") return "المنتج غير متاح للبيع الآن";
+            if (e.code === "refunded") return "لا يمكن إلغاء عملية استرداد أو بيع تم استرداده";
+            if (e.code === "completed") return "الفاتورة مكتملة ولا يمكن تعديلها";
+            if (e.code === "voided") return "الفاتورة ملغاة ولا يمكن إتمامها";
+            if (e.code === "not_completed" && e.field === "receipt") return "لا يوجد إيصال لفاتورة غير مكتملة";
+            if (e.code === "not_completed") return "لا يمكن إضافة إكرامية إلا لفاتورة مكتملة غير ملغاة";
+            if (e.code === "in_use" && e.field === "gift_card") return "تم استخدام بطاقة هدية مباعة في هذه الفاتورة";
+            if (e.code === "in_use" && e.field === "product") return "للمنتج مبيعات مسجلة، قم بأرشفته بدلاً من حذفه";
+            if (e.code === "duplicate") return field ? `${field} مستخدم مسبقاً` : "القيمة مستخدمة مسبقاً";
+            return "لا يمكن إتمام العملية لارتباطها ببيانات أخرى";
+        case "unauthorized":
+            if (e.code === "session") return "انتهت الجلسة، يرجى تسجيل الدخول مجدداً";
+            return "ليست لديك صلاحية لهذه العملية";
+        case "io":
+            return "تعذر الاتصال بالجهاز أو حفظ الملف";
+        default:
+            return fallback;
+    }
+}
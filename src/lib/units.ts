@@ -0,0 +1,20 @@
+// Units products are sold in (see src-tauri/src/unit.rs); prices are per unit
+export type Unit = "each" | "kg" | "g" | "l";
+
+export const UNIT_LABELS: Record<Unit, string> = {
+    each: "قطعة",
+    kg: "كجم",
+    g: "جم",
+    l: "لتر",
+};
+
+// Quantities of kilograms and litres go down to the gram and millilitre
+export function roundQuantity(quantity: number, unit: Unit): number {
+    const scale = unit === "kg" || unit === "l" ? 1000 : 1;
+    return Math.round(quantity * scale) / scale;
+}
+
+export function formatQuantity(quantity: number, unit: Unit): string {
+    if (unit === "each") return String(quantity);
+    return `${unit === "kg" || unit === "l" ? quantity.toFixed(3) : quantity} ${UNIT_LABELS[unit]}`;
+}
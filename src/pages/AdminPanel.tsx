@@ -1,15 +1,19 @@
 import { useEffect, useState } from "react";
-import { LogOut, Package, Users, BarChart3, Settings } from "lucide-react";
+import { LogOut, Package, Users, BarChart3, Settings, Receipt, Contact, Gift, Clock } from "lucide-react";
 import { Button } from "@/components/ui/button";
 import ProductsPage from "../components/admin/ProductsPage";
 import EmployeesPage from "../components/admin/EmployeesPage";
 import ReportPage from "../components/admin/ReportPage";
 import AdminSettingsPage from "../components/admin/AdminSettingsPage";
+import ReceiptTemplatesPage from "../components/admin/ReceiptTemplatesPage";
+import CustomersPage from "../components/admin/CustomersPage";
+import TimesheetsPage from "../components/admin/TimesheetsPage";
+import GiftCardsPage from "../components/admin/GiftCardsPage";
 import { useNavigate } from "react-router-dom";
 import { invoke } from "@tauri-apps/api/core";
 
 export default function AdminPanel() {
-    const [activePage, setActivePage] = useState<"products" | "employees" | "reports" | "settings" | null>(null);
+    const [activePage, setActivePage] = useState<"products" | "employees" | "timesheets" | "customers" | "giftcards" | "reports" | "receipts" | "settings" | null>(null);
     const [adminName, setAdminName] = useState<string>("admin");
 
     const navigate = useNavigate();
@@ -17,7 +21,11 @@ export default function AdminPanel() {
     const menuItems = [
         { key: "products", label: "المنتجات", icon: Package },
         { key: "employees", label: "الموظفين", icon: Users },
+        { key: "timesheets", label: "الدوام", icon: Clock },
+        { key: "customers", label: "العملاء", icon: Contact },
+        { key: "giftcards", label: "بطاقات الهدايا", icon: Gift },
         { key: "reports", label: "التقارير", icon: BarChart3 },
+        { key: "receipts", label: "الإيصالات", icon: Receipt },
         { key: "settings", label: "الإعدادات", icon: Settings },
     ];
 
@@ -106,7 +114,11 @@ export default function AdminPanel() {
                 )}
                 {activePage === "products" && <ProductsPage />}
                 {activePage === "employees" && <EmployeesPage />}
+                {activePage === "timesheets" && <TimesheetsPage />}
+                {activePage === "customers" && <CustomersPage />}
+                {activePage === "giftcards" && <GiftCardsPage />}
                 {activePage === "reports" && <ReportPage />}
+                {activePage === "receipts" && <ReceiptTemplatesPage />}
                 {activePage === "settings" && <AdminSettingsPage />}
             </main>
         </div>
@@ -7,11 +7,12 @@ import { Card, CardHeader, CardContent, CardFooter } from "@/components/ui/card"
 import { Input } from "@/components/ui/input";
 import { Button } from "@/components/ui/button";
 import toast from "react-hot-toast";
+import LockScreen from "../components/POS/LockScreen";
 
 export default function LoginPage() {
     const navigate = useNavigate();
     const { login } = useAuth();
-    const [role, setRole] = useState<"employee" | "admin" | null>(null);
+    const [role, setRole] = useState<"employee" | "admin" | "pin" | null>(null);
     const [adminName, setAdminName] = useState("");
     const [adminPassword, setAdminPassword] = useState("");
 
@@ -26,14 +27,16 @@ export default function LoginPage() {
     // ✅ Admin Login
     const handleAdminLogin = async () => {
         try {
-            const isValid = await invoke<boolean>("login_admin", {
+            const result = await invoke<{ success: boolean; locked_for: number; session: string | null }>("login_admin", {
                 name: adminName,
                 password: adminPassword,
             });
 
-            if (isValid) {
-                login({ role: "admin" });
+            if (result.success) {
+                login({ role: "admin", employeeId: null, employeeName: null, session: result.session });
                 navigate("/admin");
+            } else if (result.locked_for > 0) {
+                toast.error(`محاولات كثيرة، حاول بعد ${result.locked_for} ثانية`);
             } else {
                 toast.error("بيانات المدير غير صحيحة");
             }
@@ -45,17 +48,20 @@ export default function LoginPage() {
     // ✅ Employee Login (fetch ID and Name)
     const handleEmployeeLogin = async () => {
         try {
-            const employee = await invoke<{ id: number; name: string } | null>(
-                "login_employee_cmd",
-                {
-                    name: employeeName,
-                    password: employeePassword,
-                }
-            );
+            const { employee, locked_for, session } = await invoke<{
+                employee: { id: number; name: string } | null;
+                locked_for: number;
+                session: string | null;
+            }>("login_employee_cmd", {
+                name: employeeName,
+                password: employeePassword,
+            });
 
             if (employee && employee.id) {
-                login({ role: "employee", employeeId: employee.id, employeeName: employee.name });
+                login({ role: "employee", employeeId: employee.id, employeeName: employee.name, session });
                 navigate("/pos");
+            } else if (locked_for > 0) {
+                toast.error(`محاولات كثيرة، حاول بعد ${locked_for} ثانية`);
             } else {
                 toast.error("بيانات الموظف غير صحيحة");
             }
@@ -65,8 +71,16 @@ export default function LoginPage() {
         }
     };
 
+    const handlePinLogin = (employee: { id: number; name: string }, session: string) => {
+        login({ role: "employee", employeeId: employee.id, employeeName: employee.name, session });
+        navigate("/pos");
+    };
+
     return (
         <div dir="rtl" className="flex items-center justify-center min-h-screen bg-gradient-to-br from-red-50 to-white p-4">
+            {role === "pin" && (
+                <LockScreen title="أدخل الرمز السري" onUnlock={handlePinLogin} onCancel={() => setRole(null)} />
+            )}
             <Card className="w-full max-w-md shadow-lg rounded-2xl border border-gray-200 bg-white">
                 <CardHeader className="flex flex-col items-center gap-3 text-center">
                     <img
@@ -93,6 +107,12 @@ export default function LoginPage() {
                             >
                                 موظف
                             </Button>
+                            <Button
+                                onClick={() => setRole("pin")}
+                                className="w-full bg-red-100 hover:bg-red-200 text-red-700 font-semibold py-2 rounded-lg"
+                            >
+                                دخول سريع بالرمز
+                            </Button>
                             <Button
                                 onClick={() => setRole("admin")}
                                 className="w-full bg-gray-800 hover:bg-gray-900 text-white font-semibold py-2 rounded-lg"
@@ -153,7 +173,7 @@ export default function LoginPage() {
                     )}
                 </CardContent>
 
-                {role && (
+                {(role === "admin" || role === "employee") && (
                     <CardFooter className="flex flex-col gap-3">
                         {role === "admin" && (
                             <Button
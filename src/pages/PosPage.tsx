@@ -1,8 +1,11 @@
 import { useEffect, useState } from "react";
 import { invoke } from "@tauri-apps/api/core";
+import { errorMessage, isAppError } from "@/lib/errors";
+import { Unit, UNIT_LABELS, roundQuantity } from "@/lib/units";
 import SearchBar from "../components/POS/SearchBar";
 import ProductList from "../components/POS/ProductList";
 import Cart from "../components/POS/Cart";
+import LockScreen from "../components/POS/LockScreen";
 import Header from "@/components/Header";
 import toast from "react-hot-toast";
 import { useAuth } from "@/context/AuthContext";
@@ -12,16 +15,55 @@ interface Product {
     name: string;
     price: number;
     barcode: string;
+    unit: Unit;
+    parent_id: number | null;
+    sold_out: boolean;
+    available: boolean;
+}
+
+interface ScaleReading {
+    kg: number;
+    stable: boolean;
+}
+
+interface BundleGroup {
+    name: string;
+    options: { product_id: number; name: string; quantity: number }[];
+}
+
+interface TimeEntry {
+    clock_in: string;
+    on_break: boolean;
+}
+
+interface Customer {
+    id: number;
+    name: string;
+    phone: string | null;
+    points: number;
 }
 
 interface CartItem {
     product: Product;
     quantity: number;
     extraAmount?: number;
+    // Scanned price or weight code; the line keeps the price from the label
+    barcode?: string;
+    // Products picked from a bundle's choice groups
+    choices?: number[];
+}
+
+interface BarcodeMatch {
+    product: Product;
+    barcode: string;
+    kind: "product" | "pack" | "price" | "weight";
+    quantity: number;
+    price: number;
+    weight: number | null;
 }
 
 export default function PosPage() {
-    const { employeeId, employeeName } = useAuth();
+    const { employeeId, employeeName, session, login } = useAuth();
     const [products, setProducts] = useState<Product[]>([]);
     const [filteredProducts, setFilteredProducts] = useState<Product[]>([]);
     const [search, setSearch] = useState("");
@@ -29,75 +71,304 @@ export default function PosPage() {
     const [loading, setLoading] = useState(true);
     const [error, setError] = useState<string | null>(null);
     const [checkoutLoading, setCheckoutLoading] = useState(false);
+    const [customerQuery, setCustomerQuery] = useState("");
+    const [customer, setCustomer] = useState<Customer | null>(null);
+    const [clock, setClock] = useState<TimeEntry | null>(null);
+    const [guests, setGuests] = useState("");
+    const [tip, setTip] = useState("");
+    const [locked, setLocked] = useState(false);
+    const [overrideFor, setOverrideFor] = useState<string | null>(null);
+    const [pickVariantOf, setPickVariantOf] = useState<Product | null>(null);
+    const [pickChoicesFor, setPickChoicesFor] = useState<{
+        product: Product;
+        groups: BundleGroup[];
+        picked: Record<number, number>;
+    } | null>(null);
 
-    useEffect(() => {
-        async function fetchProducts() {
-            try {
-                const res = await invoke<Product[]>("get_products_cmd");
-                setProducts(res);
-                setFilteredProducts(res);
-            } catch (err) {
-                console.error(err);
-                setError("فشل تحميل المنتجات");
-            } finally {
-                setLoading(false);
-            }
+    async function fetchProducts() {
+        try {
+            const res = await invoke<Product[]>("get_products_cmd");
+            setProducts(res);
+            setFilteredProducts(res);
+        } catch (err) {
+            console.error(err);
+            setError("فشل تحميل المنتجات");
+        } finally {
+            setLoading(false);
         }
+    }
+
+    // Availability follows the clock, so the list is refreshed every minute
+    useEffect(() => {
         fetchProducts();
+        const timer = setInterval(fetchProducts, 60 * 1000);
+        return () => clearInterval(timer);
     }, []);
 
+    const toggleSoldOut = async (product: Product) => {
+        try {
+            await invoke("set_sold_out_cmd", { productId: product.id, soldOut: !product.sold_out });
+            await fetchProducts();
+        } catch (err) {
+            toast.error(errorMessage(err));
+        }
+    };
+
+    // Auto-lock after `auto_lock_minutes` (setting, default 5, 0 = off) without
+    // input. The cart stays as it is, so another cashier can pick it up.
+    useEffect(() => {
+        let minutes = 5;
+        let timer: ReturnType<typeof setTimeout> | undefined;
+        const reset = () => {
+            clearTimeout(timer);
+            if (minutes > 0) timer = setTimeout(() => setLocked(true), minutes * 60 * 1000);
+        };
+        invoke<{ key: string; value: string }[]>("get_settings_cmd")
+            .then((settings) => {
+                const value = Number(settings.find((s) => s.key === "auto_lock_minutes")?.value);
+                if (!Number.isNaN(value)) minutes = value;
+                reset();
+            })
+            .catch(console.error);
+
+        const events = ["mousedown", "keydown", "touchstart"];
+        events.forEach((e) => window.addEventListener(e, reset));
+        reset();
+        return () => {
+            clearTimeout(timer);
+            events.forEach((e) => window.removeEventListener(e, reset));
+        };
+    }, []);
+
+    const unlock = (employee: { id: number; name: string }, newSession: string) => {
+        login({ role: "employee", employeeId: employee.id, employeeName: employee.name, session: newSession });
+        if (employee.id !== employeeId) {
+            toast.success(`تم التبديل إلى ${employee.name}`);
+        }
+        setLocked(false);
+    };
+
+    const openDrawer = async (overrideId: number | null = null) => {
+        try {
+            await invoke("open_drawer_no_sale_cmd", { session, overrideId });
+            setOverrideFor(null);
+        } catch (err) {
+            if (isAppError(err, "unauthorized") && err.code !== "session") {
+                setOverrideFor("open_drawer_no_sale");
+            } else {
+                console.error("Open drawer failed:", err);
+                toast.error(errorMessage(err));
+            }
+        }
+    };
+
+    // A supervisor's PIN authorizes the one blocked action for this cashier
+    const approveOverride = async (pin: string) => {
+        const result = await invoke<{ override_id: number | null; manager_name: string | null; locked_for: number }>(
+            "manager_override_cmd",
+            { pin, permission: overrideFor, employeeId }
+        );
+        if (result.override_id) {
+            toast.success(`تمت الموافقة من ${result.manager_name}`);
+            if (overrideFor === "open_drawer_no_sale") await openDrawer(result.override_id);
+        } else if (result.locked_for > 0) {
+            toast.error(`محاولات كثيرة، حاول بعد ${result.locked_for} ثانية`);
+        } else if (result.manager_name) {
+            toast.error("هذا المشرف لا يملك الصلاحية");
+        } else {
+            toast.error("الرمز غير صحيح");
+        }
+    };
+
+    // Variants are picked under their parent, but a search finds them directly
     useEffect(() => {
         const query = search.toLowerCase();
         setFilteredProducts(
             products.filter(
                 (p) =>
-                    p.name.toLowerCase().includes(query) ||
-                    p.barcode.toLowerCase().includes(query)
+                    (query || p.parent_id === null) &&
+                    (p.name.toLowerCase().includes(query) || p.barcode.toLowerCase().includes(query))
             )
         );
     }, [search, products]);
 
-    const addToCart = (product: Product) => {
+    const variantsOf = (product: Product) => products.filter((p) => p.parent_id === product.id);
+
+    const addToCart = (product: Product, quantity = 1) => {
         setCart((prev) => {
-            const existing = prev.find((item) => item.product.id === product.id);
+            const existing = prev.find((item) => item.product.id === product.id && !item.barcode && !item.choices);
             if (existing) {
                 return prev.map((item) =>
-                    item.product.id === product.id
-                        ? { ...item, quantity: item.quantity + 1 }
+                    item === existing
+                        ? { ...item, quantity: item.quantity + quantity }
                         : item
                 );
             }
-            return [...prev, { product, quantity: 1 }];
+            return [...prev, { product, quantity }];
         });
     };
 
-    const removeFromCart = (productId: number) => {
-        setCart((prev) => prev.filter((item) => item.product.id !== productId));
+    // Products sold by measure get a line of their own: weighed items take
+    // the scale reading, or a typed weight when no scale is set up
+    const selectProduct = async (product: Product) => {
+        if (variantsOf(product).length > 0) {
+            setPickVariantOf(product);
+            return;
+        }
+        setPickVariantOf(null);
+        if (product.unit === "each") {
+            // Bundles with choice groups ask which option goes in
+            try {
+                const groups = await invoke<BundleGroup[]>("get_bundle_cmd", { bundleId: product.id });
+                if (groups.some((g) => g.options.length > 1)) {
+                    setPickChoicesFor({ product, groups, picked: {} });
+                    return;
+                }
+            } catch (err) {
+                console.error("Bundle lookup failed:", err);
+            }
+            addToCart(product);
+            return;
+        }
+        let quantity: number | null = null;
+        if (product.unit === "kg" || product.unit === "g") {
+            try {
+                const reading = await invoke<ScaleReading>("read_scale_cmd");
+                if (!reading.stable || reading.kg <= 0) {
+                    toast.error("الوزن غير مستقر، أعد المحاولة");
+                    return;
+                }
+                quantity = product.unit === "g" ? reading.kg * 1000 : reading.kg;
+            } catch (err) {
+                if (!isAppError(err, "validation")) {
+                    toast.error(errorMessage(err, "تعذرت قراءة الميزان"));
+                    return;
+                }
+            }
+        }
+        if (quantity === null) {
+            const typed = window.prompt(`الكمية (${UNIT_LABELS[product.unit]})`);
+            quantity = typed ? parseFloat(typed) : NaN;
+        }
+        quantity = roundQuantity(quantity, product.unit);
+        if (!(quantity > 0)) return;
+        setCart((prev) => [...prev, { product, quantity: quantity as number }]);
+    };
+
+    const addBundle = () => {
+        if (!pickChoicesFor) return;
+        const { product, groups, picked } = pickChoicesFor;
+        const choiceGroups = groups.map((g, i) => ({ g, i })).filter(({ g }) => g.options.length > 1);
+        if (choiceGroups.some(({ i }) => picked[i] === undefined)) {
+            toast.error("يرجى اختيار كل المكونات");
+            return;
+        }
+        const choices = choiceGroups.map(({ i }) => picked[i]);
+        const names = choiceGroups.map(({ g, i }) => g.options.find((o) => o.product_id === picked[i])?.name);
+        setCart((prev) => [
+            ...prev,
+            { product: { ...product, name: `${product.name} (${names.join("، ")})` }, quantity: 1, choices },
+        ]);
+        setPickChoicesFor(null);
+    };
+
+    // Scanner input: add the scanned product, pack or labelled item; unknown
+    // codes are left in the search box as a filter
+    const scan = async (code: string) => {
+        try {
+            const match = await invoke<BarcodeMatch | null>("lookup_barcode_cmd", { barcode: code });
+            if (!match) return;
+            if (match.kind === "price" || match.kind === "weight") {
+                // Items sold by weight carry the weight as their quantity
+                const name =
+                    match.weight && match.product.unit === "each"
+                        ? `${match.product.name} (${match.weight} كجم)`
+                        : match.product.name;
+                setCart((prev) => [
+                    ...prev,
+                    {
+                        product: { ...match.product, name, price: match.price },
+                        quantity: match.quantity,
+                        barcode: match.barcode,
+                    },
+                ]);
+            } else {
+                addToCart(match.product, match.quantity);
+            }
+            setSearch("");
+        } catch (err) {
+            console.error("Barcode lookup failed:", err);
+            toast.error(errorMessage(err));
+        }
     };
 
-    const updateQuantity = (productId: number, quantity: number) => {
+    const removeFromCart = (index: number) => {
+        setCart((prev) => prev.filter((_, i) => i !== index));
+    };
+
+    const updateQuantity = (index: number, quantity: number) => {
         if (quantity < 1) return;
         setCart((prev) =>
-            prev.map((item) =>
-                item.product.id === productId ? { ...item, quantity } : item
+            prev.map((item, i) =>
+                i === index ? { ...item, quantity } : item
             )
         );
     };
 
-    const updateExtra = (productId: number, extra: number) => {
+    const updateExtra = (index: number, extra: number) => {
         setCart((prev) =>
-            prev.map((item) =>
-                item.product.id === productId ? { ...item, extraAmount: extra } : item
+            prev.map((item, i) =>
+                i === index ? { ...item, extraAmount: extra } : item
             )
         );
     };
 
+    // Lines are rounded to cents like on the receipt
     const total = cart.reduce(
         (sum, item) =>
-            sum + (item.product.price + (item.extraAmount || 0)) * item.quantity,
+            sum + Math.round((item.product.price + (item.extraAmount || 0)) * item.quantity * 100) / 100,
         0
     );
 
+    const fetchClock = async () => {
+        if (!employeeId) return;
+        try {
+            setClock(await invoke<TimeEntry | null>("get_clock_status_cmd", { employeeId }));
+        } catch (err) {
+            console.error("Clock status failed:", err);
+        }
+    };
+
+    useEffect(() => {
+        fetchClock();
+    }, [employeeId]);
+
+    const punch = async (command: string, args: Record<string, unknown> = {}) => {
+        try {
+            const ok = await invoke<boolean>(command, { employeeId, ...args });
+            if (!ok) toast.error("لا يمكن تنفيذ العملية الآن");
+            await fetchClock();
+        } catch (err) {
+            console.error("Time clock failed:", err);
+            toast.error("فشل تسجيل الوقت");
+        }
+    };
+
+    const findCustomer = async () => {
+        if (!customerQuery.trim()) return;
+        try {
+            const result = await invoke<Customer[]>("search_customers_cmd", { query: customerQuery });
+            if (result.length === 0) {
+                toast.error("لم يتم العثور على العميل");
+                return;
+            }
+            setCustomer(result[0]);
+        } catch (err) {
+            console.error("Customer lookup failed:", err);
+            toast.error("فشل البحث عن العميل");
+        }
+    };
+
     const handleCheckout = async () => {
         if (cart.length === 0) {
             toast.error("السلة فارغة");
@@ -115,6 +386,10 @@ export default function PosPage() {
                 employeeId,
             });
 
+            if (customer) {
+                await invoke("set_sale_customer_cmd", { saleId, customerId: customer.id });
+            }
+
             for (const item of cart) {
                 await invoke("add_sale_item_cmd", {
                     saleId,
@@ -123,14 +398,35 @@ export default function PosPage() {
                     price: item.product.price,
                     extraAmount: item.extraAmount || 0,
                     productName: item.product.name,
+                    barcode: item.barcode ?? null,
+                    choices: item.choices ?? null,
                 });
             }
 
-            toast.success("تمت عملية البيع بنجاح!");
+            const serviceCharge = guests
+                ? (await invoke<number | null>("set_sale_guests_cmd", { saleId, guests: Number(guests) })) ?? 0
+                : 0;
+
+            const receiptNumber = await invoke<string>("finalize_sale_cmd", { saleId });
+            const tipAmount = Number(tip) || 0;
+            if (tipAmount > 0) {
+                await invoke("add_tip_cmd", { saleId, amount: tipAmount, paymentId: null });
+            }
+            await invoke("add_payment_cmd", {
+                saleId,
+                method: "cash",
+                amount: total + serviceCharge + tipAmount,
+            });
+
+            toast.success(`تمت عملية البيع بنجاح! (${receiptNumber})`);
             setCart([]);
+            setCustomer(null);
+            setCustomerQuery("");
+            setGuests("");
+            setTip("");
         } catch (err) {
             console.error("Checkout failed:", err);
-            toast.error("فشل إتمام العملية. يرجى المحاولة مرة أخرى.");
+            toast.error(errorMessage(err, "فشل إتمام العملية. يرجى المحاولة مرة أخرى."));
         } finally {
             setCheckoutLoading(false);
         }
@@ -138,6 +434,10 @@ export default function PosPage() {
 
     return (
         <div className="min-h-screen bg-gray-50 flex flex-col">
+            {locked && <LockScreen title="الشاشة مقفلة" onUnlock={unlock} />}
+            {overrideFor && !locked && (
+                <LockScreen title="موافقة المشرف" onPin={approveOverride} onCancel={() => setOverrideFor(null)} />
+            )}
             <Header />
             <div className="p-6 flex flex-col lg:flex-row gap-6">
                 {/* Left Section */}
@@ -148,11 +448,104 @@ export default function PosPage() {
                     <p className="text-sm text-gray-500 mb-4 text-right">
                         الموظف: <span className="font-semibold capitalize">{employeeName || "غير معروف"}</span>
                     </p>
-                    <SearchBar value={search} onChange={setSearch} />
+                    <div className="flex justify-end items-center gap-2 mb-4 text-sm">
+                        <button onClick={() => openDrawer()} className="px-3 py-1 rounded-lg border">
+                            فتح الدرج
+                        </button>
+                        <button onClick={() => setLocked(true)} className="px-3 py-1 rounded-lg border">
+                            قفل / تبديل المستخدم
+                        </button>
+                        {clock ? (
+                            <>
+                                <span className="text-gray-500">
+                                    {clock.on_break ? "في استراحة" : `منذ ${clock.clock_in}`}
+                                </span>
+                                {clock.on_break ? (
+                                    <button onClick={() => punch("end_break_cmd")} className="px-3 py-1 rounded-lg border">
+                                        إنهاء الاستراحة
+                                    </button>
+                                ) : (
+                                    <button
+                                        onClick={() => punch("start_break_cmd", { paid: false })}
+                                        className="px-3 py-1 rounded-lg border"
+                                    >
+                                        استراحة
+                                    </button>
+                                )}
+                                <button onClick={() => punch("clock_out_cmd")} className="px-3 py-1 rounded-lg bg-gray-700 text-white">
+                                    تسجيل الانصراف
+                                </button>
+                            </>
+                        ) : (
+                            <button onClick={() => punch("clock_in_cmd")} className="px-3 py-1 rounded-lg bg-green-600 text-white">
+                                تسجيل الحضور
+                            </button>
+                        )}
+                    </div>
+                    <SearchBar value={search} onChange={setSearch} onSubmit={scan} />
+                    {pickVariantOf && (
+                        <div className="mt-4 p-3 rounded-xl border border-red-200 bg-red-50 text-right" dir="rtl">
+                            <div className="flex justify-between items-center mb-2">
+                                <span className="font-semibold">{pickVariantOf.name}: اختر النوع</span>
+                                <button onClick={() => setPickVariantOf(null)} className="text-sm text-red-600">
+                                    إلغاء
+                                </button>
+                            </div>
+                            <div className="flex flex-wrap gap-2">
+                                {variantsOf(pickVariantOf).map((v) => (
+                                    <button
+                                        key={v.id}
+                                        disabled={!v.available}
+                                        onClick={() => selectProduct(v)}
+                                        className="px-3 py-2 rounded-lg bg-white border hover:bg-red-100 disabled:opacity-50"
+                                    >
+                                        {v.name} — {v.price} ج.م
+                                    </button>
+                                ))}
+                            </div>
+                        </div>
+                    )}
+                    {pickChoicesFor && (
+                        <div className="mt-4 p-3 rounded-xl border border-red-200 bg-red-50 text-right" dir="rtl">
+                            <div className="flex justify-between items-center mb-2">
+                                <span className="font-semibold">{pickChoicesFor.product.name}</span>
+                                <button onClick={() => setPickChoicesFor(null)} className="text-sm text-red-600">
+                                    إلغاء
+                                </button>
+                            </div>
+                            {pickChoicesFor.groups.map((g, i) =>
+                                g.options.length > 1 ? (
+                                    <div key={i} className="flex flex-wrap items-center gap-2 mb-2">
+                                        <span className="text-sm text-gray-600">{g.name}:</span>
+                                        {g.options.map((o) => (
+                                            <button
+                                                key={o.product_id}
+                                                onClick={() =>
+                                                    setPickChoicesFor({
+                                                        ...pickChoicesFor,
+                                                        picked: { ...pickChoicesFor.picked, [i]: o.product_id },
+                                                    })
+                                                }
+                                                className={`px-3 py-1 rounded-lg border ${
+                                                    pickChoicesFor.picked[i] === o.product_id ? "bg-red-600 text-white" : "bg-white"
+                                                }`}
+                                            >
+                                                {o.name}
+                                            </button>
+                                        ))}
+                                    </div>
+                                ) : null
+                            )}
+                            <button onClick={addBundle} className="px-4 py-2 rounded-lg bg-red-600 text-white">
+                                إضافة
+                            </button>
+                        </div>
+                    )}
                     <div className="mt-6">
                         <ProductList
                             products={filteredProducts}
-                            onAddToCart={addToCart}
+                            onAddToCart={selectProduct}
+                            onToggleSoldOut={toggleSoldOut}
                             loading={loading}
                             error={error}
                         />
@@ -161,6 +554,53 @@ export default function PosPage() {
 
                 {/* Cart Section */}
                 <div className="w-full lg:w-96 bg-white shadow-md rounded-2xl border border-gray-200 p-6 flex-shrink-0">
+                    <div className="mb-4 text-right">
+                        {customer ? (
+                            <div className="flex items-center justify-between bg-red-50 rounded-lg px-3 py-2">
+                                <button onClick={() => setCustomer(null)} className="text-sm text-red-600">
+                                    إزالة
+                                </button>
+                                <span className="text-sm">
+                                    {customer.name} — {customer.points} نقطة
+                                </span>
+                            </div>
+                        ) : (
+                            <div className="flex gap-2">
+                                <button
+                                    onClick={findCustomer}
+                                    className="px-3 py-2 text-sm rounded-lg bg-red-600 text-white"
+                                >
+                                    بحث
+                                </button>
+                                <input
+                                    value={customerQuery}
+                                    onChange={(e) => setCustomerQuery(e.target.value)}
+                                    onKeyDown={(e) => e.key === "Enter" && findCustomer()}
+                                    placeholder="هاتف أو اسم العميل"
+                                    className="flex-1 border border-gray-300 rounded-lg px-3 py-2 text-right"
+                                />
+                            </div>
+                        )}
+                    </div>
+                    <div className="flex gap-2 mb-4">
+                        <input
+                            type="number"
+                            min="0"
+                            step="0.01"
+                            value={tip}
+                            onChange={(e) => setTip(e.target.value)}
+                            placeholder="الإكرامية"
+                            className="w-1/2 border border-gray-300 rounded-lg px-3 py-2 text-right"
+                        />
+                        <input
+                            type="number"
+                            min="1"
+                            value={guests}
+                            onChange={(e) => setGuests(e.target.value)}
+                            placeholder="عدد الأشخاص"
+                            className="w-1/2 border border-gray-300 rounded-lg px-3 py-2 text-right"
+                        />
+                    </div>
                     <Cart
                         items={cart}
                         onUpdateQuantity={updateQuantity}